@@ -12,6 +12,52 @@ pub struct Cli {
     pub command: Option<Commands>,
 }
 
+/// Packet filters shared by `listen`, `reply` and `mqtt bridge`.
+///
+/// The port filter is declared by each command, since `mqtt bridge` already
+/// uses `--port` for the broker port.
+#[derive(Args, Debug)]
+pub struct PacketFilterArgs {
+    /// Only packets from this node (hex ID like !04e1c43b, or node name)
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// Only packets on this channel index (0-7)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=7))]
+    pub channel: Option<u32>,
+
+    /// Only packets whose decoded content matches this regular expression
+    #[arg(long = "match")]
+    pub r#match: Option<String>,
+
+    /// Only packets received with at least this SNR (dB)
+    #[arg(long, allow_negative_numbers = true)]
+    pub min_snr: Option<f32>,
+
+    /// Only packets heard directly (zero hops away)
+    #[arg(long)]
+    pub direct_only: bool,
+}
+
+/// Reference point for `distance`, `bearing` and `altitude_diff`, used
+/// instead of the local node's position.
+#[derive(Args, Debug)]
+pub struct OriginArgs {
+    /// Latitude to measure distances from (default: the local node's position)
+    #[arg(long, requires = "from_lon", allow_negative_numbers = true)]
+    pub from_lat: Option<f64>,
+
+    /// Longitude to measure distances from
+    #[arg(long, requires = "from_lat", allow_negative_numbers = true)]
+    pub from_lon: Option<f64>,
+}
+
+impl OriginArgs {
+    pub fn point(&self) -> Option<(f64, f64)> {
+        self.from_lat.zip(self.from_lon)
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct ConnectionArgs {
     /// Host address for TCP connection
@@ -41,29 +87,85 @@ pub struct ConnectionArgs {
     /// Output results as JSON instead of human-readable text
     #[arg(long, global = true)]
     pub json: bool,
+
+    /// Units for distances and heights (default: metric, or `units` in config.toml)
+    #[arg(long, global = true, value_enum)]
+    pub units: Option<UnitsArg>,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// List all nodes in the mesh network
+    #[command(args_conflicts_with_subcommands = true)]
     Nodes {
-        /// Comma-separated list of fields to display (e.g. id,name,battery,snr,hops,last_heard,hw_model,role,position)
+        #[command(subcommand)]
+        action: Option<NodesAction>,
+
+        /// Comma-separated list of fields to display (e.g. id,name,battery,snr,hops,last_heard,hw_model,role,position,distance,bearing,altitude_diff)
         #[arg(long)]
         fields: Option<String>,
+
+        #[command(flatten)]
+        origin: OriginArgs,
+
+        /// Output format; anything but `table` takes precedence over --json
+        #[arg(long, value_enum, default_value_t = NodesFormatArg::Table)]
+        format: NodesFormatArg,
+
+        /// Sort by this column (default: node number)
+        #[arg(long, value_enum)]
+        sort: Option<NodeSortArg>,
+
+        /// Reverse the sort order
+        #[arg(long, requires = "sort")]
+        desc: bool,
+
+        /// Only nodes matching a field expression, e.g. "battery<20", "hops<=1", "name~base". Repeatable; all must match
+        #[arg(long = "where", value_name = "EXPR")]
+        r#where: Vec<String>,
+
+        /// Only nodes heard within this long (e.g. 30m, 2h, 7d)
+        #[arg(long)]
+        heard_within: Option<String>,
+
+        /// Only nodes with this role (e.g. ROUTER, CLIENT_MUTE)
+        #[arg(long)]
+        role: Option<String>,
+
+        /// Only nodes marked as favorite
+        #[arg(long)]
+        favorites: bool,
+
+        /// Only nodes with a known position
+        #[arg(long)]
+        has_position: bool,
+
+        /// Show at most this many nodes, after filtering and sorting
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Read the nodes from a file saved with `nodes snapshot save` instead of the device
+        #[arg(long, value_name = "FILE")]
+        from_snapshot: Option<String>,
     },
 
     /// Send a text message to the mesh network
     Send {
-        /// The message text to send
-        message: String,
+        /// The message text to send, or "-" to read it from stdin
+        #[arg(required_unless_present_any = ["react", "file"])]
+        message: Option<String>,
 
-        /// Destination node ID in hex (e.g. 04e1c43b or '!04e1c43b'). Omit to broadcast.
-        #[arg(long, conflicts_with = "to")]
-        dest: Option<String>,
+        /// Read the message text from a file
+        #[arg(long, conflicts_with_all = ["message", "react"])]
+        file: Option<String>,
 
-        /// Destination node name (e.g. Pedro). Searches known nodes by name.
-        #[arg(long, conflicts_with = "dest")]
-        to: Option<String>,
+        /// Destination node ID in hex (e.g. 04e1c43b or '!04e1c43b'). Repeat to send to several nodes; omit both --dest and --to to broadcast.
+        #[arg(long)]
+        dest: Vec<String>,
+
+        /// Destination node name or alias (e.g. Pedro), or @group for a group from config.toml. Repeatable.
+        #[arg(long)]
+        to: Vec<String>,
 
         /// Channel index (0-7)
         #[arg(long, default_value_t = 0)]
@@ -80,17 +182,80 @@ pub enum Commands {
         /// Send via PRIVATE_APP port instead of TEXT_MESSAGE_APP
         #[arg(long)]
         private: bool,
+
+        /// Reply to the message with this packet ID (hex, see listen --meta or messages list)
+        #[arg(long)]
+        reply_to: Option<String>,
+
+        /// React to the --reply-to message with an emoji instead of sending text
+        #[arg(long, requires = "reply_to", conflicts_with_all = ["message", "private"])]
+        react: Option<String>,
+
+        /// Seconds to wait between packets: the parts of a long message and each recipient
+        #[arg(long, default_value_t = 2)]
+        part_delay: u64,
     },
 
     /// Stream incoming packets from the mesh network in real time
     Listen {
-        /// Write packets as JSON Lines to a log file
+        /// Append packets to a log file (JSON Lines by default)
         #[arg(long)]
         log: Option<String>,
+
+        /// Log file format
+        #[arg(long, value_enum, default_value_t = LogFormatArg::Jsonl, requires = "log")]
+        log_format: LogFormatArg,
+
+        /// Rotate the log when it reaches a size (e.g. 10M, 500K) or once a day ("daily")
+        #[arg(long, requires = "log")]
+        log_rotate: Option<String>,
+
+        /// Number of rotated log files to keep
+        #[arg(long, default_value_t = 5, requires = "log_rotate", value_parser = clap::value_parser!(u32).range(1..))]
+        log_keep: u32,
+
+        /// Compress rotated log files with gzip
+        #[arg(long, requires = "log_rotate")]
+        log_gzip: bool,
+
+        /// Group encrypted packets by channel hash and print a report on exit
+        #[arg(long)]
+        stats_channels: bool,
+
+        /// Show a refreshing per-node airtime/utilization summary instead of packets, with a final JSON report
+        #[arg(long)]
+        stats: bool,
+
+        /// Refresh interval in seconds for the --stats summary
+        #[arg(long, default_value_t = 10)]
+        stats_interval: u64,
+
+        /// Only show packets on these ports (comma-separated, e.g. TELEMETRY_APP,POSITION_APP)
+        #[arg(long, value_delimiter = ',')]
+        port: Vec<String>,
+
+        #[command(flatten)]
+        filter: PacketFilterArgs,
+
+        /// Show link metadata (packet id, SNR, RSSI, hops, relay, flags) on each line
+        #[arg(long)]
+        meta: bool,
+
+        /// Print every copy of a packet instead of collapsing copies heard via relays
+        #[arg(long)]
+        show_duplicates: bool,
+
+        /// Join "(1/3)"-numbered parts of long text messages back into one message
+        #[arg(long)]
+        reassemble: bool,
     },
 
     /// Show local node and device information
-    Info,
+    Info {
+        /// Read the information from a file saved with `nodes snapshot save` instead of the device
+        #[arg(long, value_name = "FILE")]
+        from_snapshot: Option<String>,
+    },
 
     /// Get or set device configuration
     Config {
@@ -129,7 +294,10 @@ pub enum Commands {
     },
 
     /// Auto-reply to incoming messages with signal info (SNR, RSSI, hops)
-    Reply,
+    Reply {
+        #[command(flatten)]
+        filter: PacketFilterArgs,
+    },
 
     /// Remote GPIO operations
     Gpio {
@@ -160,6 +328,9 @@ pub enum Commands {
         /// Refresh interval in seconds
         #[arg(long, default_value_t = 5)]
         interval: u64,
+
+        #[command(flatten)]
+        origin: OriginArgs,
     },
 
     /// Waypoint management (send, delete, list)
@@ -177,32 +348,113 @@ pub enum Commands {
     /// Trace route to a node, showing each hop with SNR
     Traceroute {
         /// Destination node ID in hex (e.g. 04e1c43b or '!04e1c43b')
-        #[arg(long, conflicts_with = "to", required_unless_present = "to")]
+        #[arg(long, conflicts_with_all = ["to", "all"], required_unless_present_any = ["to", "all"])]
         dest: Option<String>,
 
-        /// Destination node name (e.g. Pedro). Searches known nodes by name.
-        #[arg(long, conflicts_with = "dest", required_unless_present = "dest")]
+        /// Destination node name or alias (e.g. Pedro), or @group to trace every member
+        #[arg(long, conflicts_with_all = ["dest", "all"], required_unless_present_any = ["dest", "all"])]
         to: Option<String>,
 
+        /// Trace every known node in turn and save the results as a survey
+        #[arg(long)]
+        all: bool,
+
+        /// With --all, only nodes heard within this long, e.g. 1d or 6h
+        #[arg(long)]
+        heard_within: Option<String>,
+
+        /// With --all, minimum seconds between traceroute requests (the firmware rate-limits them)
+        #[arg(long, default_value_t = 30)]
+        delay: u64,
+
+        /// Repeat the trace this many times and show live per-hop statistics, like mtr
+        #[arg(long, default_value_t = 1, conflicts_with = "all", value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+
+        /// With --count, seconds between traces (the firmware rate-limits them)
+        #[arg(long, default_value_t = 30)]
+        interval: u64,
+
         /// Timeout in seconds to wait for traceroute response
         #[arg(long, default_value_t = 60)]
         timeout: u64,
     },
 
-    /// Ping a node and wait for ACK to measure round-trip time
+    /// Ping a node and measure round-trip time from its ACK, without a visible message
     Ping {
         /// Destination node ID in hex (e.g. 04e1c43b or '!04e1c43b')
         #[arg(long, conflicts_with = "to", required_unless_present = "to")]
         dest: Option<String>,
 
-        /// Destination node name (e.g. Pedro). Searches known nodes by name.
+        /// Destination node name or alias (e.g. Pedro), or @group to ping every member
         #[arg(long, conflicts_with = "dest", required_unless_present = "dest")]
         to: Option<String>,
 
-        /// Timeout in seconds to wait for ACK
+        /// Number of pings to send
+        #[arg(short = 'c', long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+
+        /// Seconds between pings; each waits for its ACK or timeout first
+        #[arg(short = 'i', long, default_value_t = 10)]
+        interval: u64,
+
+        /// Channel index (0-7)
+        #[arg(long, default_value_t = 0)]
+        channel: u32,
+
+        /// Timeout in seconds to wait for each ACK
         #[arg(long, default_value_t = 30)]
         timeout: u64,
     },
+
+    /// Map the mesh from NeighborInfo reports: links, SNR and single points of failure
+    Topology {
+        /// How long to listen for NeighborInfo packets, e.g. 30m or 2h. Ends early once every requested node has answered.
+        #[arg(long, default_value = "5m")]
+        duration: String,
+
+        /// Ask this node (ID in hex) for its neighbors. Repeatable.
+        #[arg(long, conflicts_with = "from_log")]
+        dest: Vec<String>,
+
+        /// Ask this node (name or alias), or every member of @group, for its neighbors. Repeatable.
+        #[arg(long, conflicts_with = "from_log")]
+        to: Vec<String>,
+
+        /// Seconds to wait between requests
+        #[arg(long, default_value_t = 5)]
+        delay: u64,
+
+        /// Build the topology from a `listen --log` file instead of the device
+        #[arg(long, value_name = "FILE")]
+        from_log: Option<String>,
+
+        /// Output format; `dot` takes precedence over --json
+        #[arg(long, value_enum, default_value_t = TopologyFormatArg::Summary)]
+        format: TopologyFormatArg,
+    },
+
+    /// LoRa radio calculations (airtime, link budget, frequency slot)
+    Lora {
+        #[command(subcommand)]
+        action: LoraAction,
+    },
+
+    /// Read back `listen --log` JSON Lines files (no device needed)
+    Log {
+        #[command(subcommand)]
+        action: LogAction,
+    },
+
+    /// Browse stored text messages (no device needed)
+    Messages {
+        #[command(subcommand)]
+        action: MessagesAction,
+
+        /// Join "(1/3)"-numbered parts of long text messages back into one message
+        #[arg(long, global = true)]
+        reassemble: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -289,6 +541,32 @@ pub enum NodeAction {
         #[arg(long, conflicts_with = "dest", required_unless_present = "dest")]
         to: Option<String>,
     },
+    /// Remove every node not heard for a while from the local NodeDB
+    Prune {
+        /// Remove nodes not heard for this long (e.g. 14d, 12h). Nodes never heard count as stale.
+        #[arg(long)]
+        older_than: String,
+
+        /// Keep nodes marked as favorite
+        #[arg(long)]
+        keep_favorites: bool,
+
+        /// Only nodes with this role (e.g. CLIENT_MUTE)
+        #[arg(long)]
+        role: Option<String>,
+
+        /// List the nodes that would be removed without removing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Remove without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+
+        /// Seconds to wait between removals
+        #[arg(long, default_value_t = 1)]
+        delay: u64,
+    },
     /// Mark a node as favorite
     SetFavorite {
         /// Node ID in hex (e.g. 04e1c43b)
@@ -448,7 +726,7 @@ pub enum RequestAction {
         #[arg(long, conflicts_with = "to", required_unless_present = "to")]
         dest: Option<String>,
 
-        /// Target node name
+        /// Target node name or alias, or @group to ask every member
         #[arg(long, conflicts_with = "dest", required_unless_present = "dest")]
         to: Option<String>,
 
@@ -466,13 +744,16 @@ pub enum RequestAction {
         #[arg(long, conflicts_with = "to", required_unless_present = "to")]
         dest: Option<String>,
 
-        /// Target node name
+        /// Target node name or alias, or @group to ask every member
         #[arg(long, conflicts_with = "dest", required_unless_present = "dest")]
         to: Option<String>,
 
         /// Timeout in seconds
         #[arg(long, default_value_t = 30)]
         timeout: u64,
+
+        #[command(flatten)]
+        origin: OriginArgs,
     },
     /// Request device metadata from a remote node
     Metadata {
@@ -480,7 +761,7 @@ pub enum RequestAction {
         #[arg(long, conflicts_with = "to", required_unless_present = "to")]
         dest: Option<String>,
 
-        /// Target node name
+        /// Target node name or alias, or @group to ask every member
         #[arg(long, conflicts_with = "dest", required_unless_present = "dest")]
         to: Option<String>,
 
@@ -552,6 +833,13 @@ pub enum MqttAction {
         /// MQTT password
         #[arg(long)]
         password: Option<String>,
+
+        /// Only forward packets on these ports (comma-separated, e.g. TEXT_MESSAGE_APP)
+        #[arg(long, value_delimiter = ',')]
+        filter_port: Vec<String>,
+
+        #[command(flatten)]
+        filter: PacketFilterArgs,
     },
 }
 
@@ -616,18 +904,219 @@ pub enum ConfigFileAction {
     Show,
     /// Set a configuration value
     Set {
-        /// Config key (host, port, serial, ble, json)
+        /// Config key (host, port, serial, ble, json, units)
         key: String,
         /// Value to set
         value: String,
     },
     /// Remove a configuration value (reset to default)
     Unset {
-        /// Config key to remove (host, port, serial, ble, json)
+        /// Config key to remove (host, port, serial, ble, json, units)
         key: String,
     },
     /// Print the config file path
     Path,
+    /// Add or change an address book entry, usable wherever --to takes a node name
+    Alias {
+        /// Nickname (e.g. base)
+        name: String,
+        /// Node ID in hex (e.g. 04e1c43b or '!04e1c43b')
+        node: String,
+    },
+    /// Remove an address book entry
+    Unalias {
+        /// Nickname to remove
+        name: String,
+    },
+    /// Define a group of nodes, addressed as --to @NAME
+    Group {
+        /// Group name, without the @
+        name: String,
+        /// Aliases, node names or node IDs ('!04e1c43b'), space or comma separated
+        #[arg(required = true, value_delimiter = ',')]
+        members: Vec<String>,
+    },
+    /// Remove a group
+    Ungroup {
+        /// Group name to remove
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogAction {
+    /// Re-render a packet log with the live listen formatter
+    Show {
+        /// Path to a JSON Lines file written by `listen --log`
+        file: String,
+
+        /// Only packets on these ports (comma-separated, e.g. TELEMETRY_APP,POSITION_APP)
+        #[arg(long, value_delimiter = ',')]
+        port: Vec<String>,
+
+        #[command(flatten)]
+        filter: PacketFilterArgs,
+
+        /// Only packets received at or after this time (e.g. 2h, 2026-10-18 09:00, RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only packets received at or before this time
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Show link metadata (packet id, SNR, RSSI, hops, relay, flags) on each line
+        #[arg(long)]
+        meta: bool,
+
+        /// Also show the copies repeated by relays that listen logged as duplicates
+        #[arg(long)]
+        show_duplicates: bool,
+    },
+    /// Per-node and per-port packet counts with activity timelines
+    Stats {
+        /// Path to a JSON Lines file written by `listen --log`
+        file: String,
+
+        /// Only packets on these ports (comma-separated, e.g. TELEMETRY_APP,POSITION_APP)
+        #[arg(long, value_delimiter = ',')]
+        port: Vec<String>,
+
+        #[command(flatten)]
+        filter: PacketFilterArgs,
+
+        /// Only packets received at or after this time (e.g. 2h, 2026-10-18 09:00, RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only packets received at or before this time
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Timeline bucket size (e.g. 15m, 1h, 1d); chosen from the log's time span if omitted
+        #[arg(long)]
+        bucket: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MessagesAction {
+    /// Show the most recent sent and received messages
+    List {
+        /// Number of messages to show (0 for all)
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Only messages on this channel index (0-7)
+        #[arg(long, value_parser = clap::value_parser!(u32).range(0..=7))]
+        channel: Option<u32>,
+    },
+    /// Show the direct messages exchanged with one node
+    Thread {
+        /// Node ID in hex (e.g. !04e1c43b) or node name
+        #[arg(long)]
+        with: String,
+
+        /// Number of messages to show (0 for all)
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Find messages containing some text (case-insensitive)
+    Search {
+        /// Text to search for
+        text: String,
+    },
+    /// Show received messages not shown by `messages unread` before, and mark them read
+    Unread,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NodesAction {
+    /// Show recorded sightings of a node: trends, first/last seen, gaps and reboots (no device needed)
+    History {
+        /// Node ID in hex (e.g. !04e1c43b) or node name; omit to list every recorded node
+        node: Option<String>,
+
+        /// Only sightings since this time (e.g. 7d, 2026-10-01)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Report silences longer than this as gaps (e.g. 30m, 3h)
+        #[arg(long, default_value = "3h")]
+        gap: String,
+
+        /// Number of recent sightings to list (0 for none)
+        #[arg(long, default_value_t = 10)]
+        last: usize,
+    },
+    /// Save the device's node DB to a file, or compare two saved files
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotAction {
+    /// Save nodes, channels, metadata and configuration to a JSON file
+    Save {
+        /// Output file path
+        file: String,
+    },
+    /// Report nodes that appeared, disappeared or changed between two snapshots (no device needed)
+    Diff {
+        /// Older snapshot file
+        old: String,
+
+        /// Newer snapshot file
+        new: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LoraAction {
+    /// Compute time-on-air, data rate, sensitivity, link budget and centre frequency
+    Calc {
+        /// Application payload size in bytes (the 16-byte mesh header is added automatically)
+        #[arg(long, default_value_t = 32)]
+        payload: usize,
+
+        /// Calculate from flags only, without connecting to a device (defaults: US, LongFast)
+        #[arg(long)]
+        offline: bool,
+
+        /// Region code (e.g. US, EU_868, ANZ, LORA_24)
+        #[arg(long)]
+        region: Option<String>,
+
+        /// Modem preset
+        #[arg(long)]
+        preset: Option<ModemPresetArg>,
+
+        /// Custom bandwidth in kHz (disables the preset)
+        #[arg(long)]
+        bandwidth: Option<u32>,
+
+        /// Custom spreading factor 7-12 (disables the preset)
+        #[arg(long)]
+        spread_factor: Option<u32>,
+
+        /// Custom coding rate denominator 5-8 (disables the preset)
+        #[arg(long)]
+        coding_rate: Option<u32>,
+
+        /// Frequency slot (1-based, 0 = derive from primary channel name)
+        #[arg(long)]
+        channel_num: Option<u32>,
+
+        /// Primary channel name used to derive the frequency slot
+        #[arg(long)]
+        channel_name: Option<String>,
+
+        /// Transmit power in dBm (0 = region maximum)
+        #[arg(long)]
+        tx_power: Option<i32>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -719,6 +1208,56 @@ impl From<&ModemPresetArg> for i32 {
     }
 }
 
+#[derive(Debug, Clone, ValueEnum)]
+pub enum LogFormatArg {
+    /// One JSON object per line, readable by `log show`/`log stats`
+    Jsonl,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum UnitsArg {
+    /// Meters and kilometers
+    Metric,
+    /// Feet and miles
+    Imperial,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum TopologyFormatArg {
+    /// Adjacency list with SNR, isolated nodes and single points of failure
+    Summary,
+    /// Graphviz DOT graph (render with `dot -Tsvg`)
+    Dot,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum NodesFormatArg {
+    /// Aligned columns for the terminal
+    Table,
+    /// Comma-separated values with a header row (all fields by default)
+    Csv,
+    /// Tab-separated values with a header row (all fields by default)
+    Tsv,
+    /// Markdown table
+    Markdown,
+    /// GeoJSON FeatureCollection of the nodes with a position
+    Geojson,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum NodeSortArg {
+    #[value(name = "last_heard", alias = "last-heard")]
+    LastHeard,
+    Snr,
+    Battery,
+    Hops,
+    Name,
+    /// Distance from the local node's position
+    Distance,
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum TelemetryTypeArg {
     Device,
@@ -10,6 +10,7 @@ use qrcode::render::svg;
 use qrcode::QrCode;
 use serde::Serialize;
 
+use super::channel_hash::{hash_for_channel, primary_psk};
 use super::parsers::parse_bool;
 use super::{Command, CommandContext};
 
@@ -19,6 +20,8 @@ struct ChannelListJson {
     name: String,
     role: String,
     encryption: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<u8>,
     uplink_enabled: bool,
     downlink_enabled: bool,
 }
@@ -42,6 +45,8 @@ pub struct ChannelListCommand {
 impl Command for ChannelListCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
         let channels = ctx.node_db.channels();
+        let lora = ctx.node_db.local_config().lora.as_ref();
+        let primary = primary_psk(channels);
 
         if channels.is_empty() {
             if self.json {
@@ -77,6 +82,7 @@ impl Command for ChannelListCommand {
                         encryption: settings
                             .map(|s| format_psk(&s.psk))
                             .unwrap_or_else(|| "Unknown".to_string()),
+                        hash: hash_for_channel(ch, primary, lora),
                         uplink_enabled: settings.is_some_and(|s| s.uplink_enabled),
                         downlink_enabled: settings.is_some_and(|s| s.downlink_enabled),
                     }
@@ -88,7 +94,7 @@ impl Command for ChannelListCommand {
 
         println!("{}", "Channels".bold().underline());
         for ch in channels {
-            print_channel(ch);
+            print_channel(ch, primary, lora);
         }
         println!();
 
@@ -493,7 +499,11 @@ fn rand_byte() -> u8 {
     (id & 0xFF) as u8
 }
 
-fn print_channel(ch: &protobufs::Channel) {
+fn print_channel(
+    ch: &protobufs::Channel,
+    primary_psk: &[u8],
+    lora: Option<&protobufs::config::LoRaConfig>,
+) {
     let role = channel::Role::try_from(ch.role);
     let role_str = match role {
         Ok(channel::Role::Primary) => "Primary".green().to_string(),
@@ -518,15 +528,20 @@ fn print_channel(ch: &protobufs::Channel) {
         .map(|s| format_psk(&s.psk))
         .unwrap_or_else(|| "Unknown".to_string());
 
+    let hash = hash_for_channel(ch, primary_psk, lora)
+        .map(|h| format!("0x{:02x}", h))
+        .unwrap_or_else(|| "-".to_string());
+
     let uplink = settings.is_some_and(|s| s.uplink_enabled);
     let downlink = settings.is_some_and(|s| s.downlink_enabled);
 
     println!(
-        "  {:<6} {:<14} {:<12} {:<12} uplink: {:<5} downlink: {}",
+        "  {:<6} {:<14} {:<12} {:<12} hash: {:<5} uplink: {:<5} downlink: {}",
         format!("[{}]", ch.index).bold(),
         name,
         role_str,
         encryption,
+        hash,
         uplink,
         downlink
     );
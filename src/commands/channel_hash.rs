@@ -0,0 +1,136 @@
+use meshtastic::protobufs::config::lo_ra_config::ModemPreset;
+use meshtastic::protobufs::{self, channel};
+
+use crate::node_db::NodeDb;
+
+/// Well-known default PSK used when a channel's key is the 1-byte "default" index.
+const DEFAULT_PSK: [u8; 16] = [
+    0xd4, 0xf1, 0xbb, 0x3a, 0x20, 0x29, 0x07, 0x59, 0xf0, 0xbc, 0xff, 0xab, 0xcf, 0x4e, 0x69, 0x01,
+];
+
+/// A configured channel together with the hash that identifies it on air.
+pub struct KnownChannel {
+    pub index: i32,
+    pub name: String,
+    pub hash: u8,
+}
+
+/// Computes the one-byte channel hash carried by encrypted packets, exactly as the
+/// firmware does: XOR of all name bytes, XOR'd with all bytes of the expanded PSK.
+pub fn channel_hash(name: &str, psk: &[u8]) -> u8 {
+    xor_bytes(name.as_bytes()) ^ xor_bytes(&expand_psk(psk))
+}
+
+/// Expands a stored PSK into the actual AES key the firmware uses.
+/// Single-byte PSKs are indices into the default key; short keys are zero-padded.
+pub fn expand_psk(psk: &[u8]) -> Vec<u8> {
+    match psk.len() {
+        0 => vec![],
+        1 => {
+            let index = psk[0];
+            if index == 0 {
+                return vec![];
+            }
+            let mut key = DEFAULT_PSK.to_vec();
+            key[15] = key[15].wrapping_add(index - 1);
+            key
+        }
+        2..=15 => {
+            let mut key = psk.to_vec();
+            key.resize(16, 0);
+            key
+        }
+        17..=31 => {
+            let mut key = psk.to_vec();
+            key.resize(32, 0);
+            key
+        }
+        _ => psk.to_vec(),
+    }
+}
+
+/// Returns the name the firmware hashes for a channel. Channels without a name use
+/// the modem preset's short display name (or "Custom" when presets are disabled).
+pub fn effective_channel_name(
+    settings: &protobufs::ChannelSettings,
+    lora: Option<&protobufs::config::LoRaConfig>,
+) -> String {
+    if !settings.name.is_empty() {
+        return settings.name.clone();
+    }
+    match lora {
+        Some(l) if !l.use_preset => "Custom".to_string(),
+        Some(l) => modem_preset_display_name(l.modem_preset).to_string(),
+        None => modem_preset_display_name(ModemPreset::LongFast as i32).to_string(),
+    }
+}
+
+pub fn modem_preset_display_name(preset: i32) -> &'static str {
+    match ModemPreset::try_from(preset) {
+        Ok(ModemPreset::LongFast) => "LongFast",
+        Ok(ModemPreset::LongSlow) => "LongSlow",
+        Ok(ModemPreset::VeryLongSlow) => "VLongSlow",
+        Ok(ModemPreset::MediumSlow) => "MediumSlow",
+        Ok(ModemPreset::MediumFast) => "MediumFast",
+        Ok(ModemPreset::ShortSlow) => "ShortSlow",
+        Ok(ModemPreset::ShortFast) => "ShortFast",
+        Ok(ModemPreset::LongModerate) => "LongMod",
+        Ok(ModemPreset::ShortTurbo) => "ShortTurbo",
+        Err(_) => "Invalid",
+    }
+}
+
+/// The PSK stored on the primary channel, or an empty key when there is none.
+pub fn primary_psk(channels: &[protobufs::Channel]) -> &[u8] {
+    channels
+        .iter()
+        .find(|ch| ch.role == channel::Role::Primary as i32)
+        .and_then(|ch| ch.settings.as_ref())
+        .map(|s| s.psk.as_slice())
+        .unwrap_or_default()
+}
+
+/// Hash of a single channel using the device's LoRa config to resolve empty names.
+/// Secondary channels without a PSK of their own are encrypted with the primary
+/// channel's key, so `primary_psk` is hashed in their place. Disabled channels
+/// have no hash.
+pub fn hash_for_channel(
+    ch: &protobufs::Channel,
+    primary_psk: &[u8],
+    lora: Option<&protobufs::config::LoRaConfig>,
+) -> Option<u8> {
+    if ch.role == channel::Role::Disabled as i32 {
+        return None;
+    }
+    let settings = ch.settings.as_ref()?;
+    let name = effective_channel_name(settings, lora);
+    let psk = if settings.psk.is_empty() && ch.role == channel::Role::Secondary as i32 {
+        primary_psk
+    } else {
+        &settings.psk
+    };
+    Some(channel_hash(&name, psk))
+}
+
+/// All enabled channels in the node DB with their computed hashes.
+pub fn known_channels(node_db: &NodeDb) -> Vec<KnownChannel> {
+    let lora = node_db.local_config().lora.as_ref();
+    let primary = primary_psk(node_db.channels());
+    node_db
+        .channels()
+        .iter()
+        .filter_map(|ch| {
+            let hash = hash_for_channel(ch, primary, lora)?;
+            let name = effective_channel_name(ch.settings.as_ref()?, lora);
+            Some(KnownChannel {
+                index: ch.index,
+                name,
+                hash,
+            })
+        })
+        .collect()
+}
+
+fn xor_bytes(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |acc, b| acc ^ b)
+}
@@ -3,6 +3,7 @@ use colored::Colorize;
 use crate::cli::ConfigFileAction;
 use crate::config_file;
 
+use super::destination::parse_node_hex;
 use super::parsers::parse_bool;
 
 pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Result<()> {
@@ -28,7 +29,10 @@ pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Resu
                 || config.port.is_some()
                 || config.serial.is_some()
                 || config.ble.is_some()
-                || config.json.is_some();
+                || config.json.is_some()
+                || config.units.is_some()
+                || !config.aliases.is_empty()
+                || !config.groups.is_empty();
 
             if !has_any {
                 println!("  {}", "(config file is empty)".dimmed());
@@ -50,6 +54,38 @@ pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Resu
             if let Some(json_val) = config.json {
                 println!("  {:<12} {}", "json:".dimmed(), json_val);
             }
+            if let Some(ref units) = config.units {
+                println!("  {:<12} {}", "units:".dimmed(), units);
+            }
+
+            let has_keys = config.host.is_some()
+                || config.port.is_some()
+                || config.serial.is_some()
+                || config.ble.is_some()
+                || config.json.is_some()
+                || config.units.is_some();
+            if !config.aliases.is_empty() {
+                if has_keys {
+                    println!();
+                }
+                println!("{}", "Aliases".bold());
+                for (name, node) in &config.aliases {
+                    println!("  {:<12} {}", format!("{}:", name).dimmed(), node);
+                }
+            }
+            if !config.groups.is_empty() {
+                if has_keys || !config.aliases.is_empty() {
+                    println!();
+                }
+                println!("{}", "Groups".bold());
+                for (name, members) in &config.groups {
+                    println!(
+                        "  {:<12} {}",
+                        format!("@{}:", name).dimmed(),
+                        members.join(", ")
+                    );
+                }
+            }
         }
         ConfigFileAction::Set { key, value } => {
             let mut config = config_file::load();
@@ -68,9 +104,19 @@ pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Resu
                         anyhow::anyhow!("Invalid value '{}' for json. Expected true/false.", value)
                     })?);
                 }
+                "units" => {
+                    let units = value.to_lowercase();
+                    if units != "metric" && units != "imperial" {
+                        anyhow::bail!(
+                            "Invalid value '{}' for units. Expected metric or imperial.",
+                            value
+                        );
+                    }
+                    config.units = Some(units);
+                }
                 other => {
                     anyhow::bail!(
-                        "Unknown config key '{}'. Valid keys: host, port, serial, ble, json",
+                        "Unknown config key '{}'. Valid keys: host, port, serial, ble, json, units",
                         other
                     );
                 }
@@ -93,9 +139,10 @@ pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Resu
                 "serial" => config.serial = None,
                 "ble" => config.ble = None,
                 "json" => config.json = None,
+                "units" => config.units = None,
                 other => {
                     anyhow::bail!(
-                        "Unknown config key '{}'. Valid keys: host, port, serial, ble, json",
+                        "Unknown config key '{}'. Valid keys: host, port, serial, ble, json, units",
                         other
                     );
                 }
@@ -109,6 +156,81 @@ pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Resu
                 println!("{} Unset {}", "ok".green(), key.bold());
             }
         }
+        ConfigFileAction::Alias { name, node } => {
+            check_book_name(name)?;
+            let id = parse_node_hex(node)?;
+            let mut config = config_file::load();
+            let node = format!("!{:08x}", id);
+            config.aliases.insert(name.clone(), node.clone());
+            config_file::save(&config)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            } else {
+                println!("{} Alias {} = {}", "ok".green(), name.bold(), node);
+            }
+        }
+        ConfigFileAction::Unalias { name } => {
+            let mut config = config_file::load();
+            if config.aliases.remove(name).is_none() {
+                anyhow::bail!("No alias named '{}'", name);
+            }
+            config_file::save(&config)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            } else {
+                println!("{} Removed alias {}", "ok".green(), name.bold());
+            }
+        }
+        ConfigFileAction::Group { name, members } => {
+            let name = name.trim_start_matches('@');
+            check_book_name(name)?;
+            let members: Vec<String> = members
+                .iter()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect();
+            if members.is_empty() {
+                anyhow::bail!("Group '@{}' needs at least one member", name);
+            }
+            if let Some(nested) = members.iter().find(|m| m.starts_with('@')) {
+                anyhow::bail!("Groups cannot contain other groups ('{}')", nested);
+            }
+
+            let mut config = config_file::load();
+            config.groups.insert(name.to_string(), members.clone());
+            config_file::save(&config)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            } else {
+                println!(
+                    "{} Group {} = {}",
+                    "ok".green(),
+                    format!("@{}", name).bold(),
+                    members.join(", ")
+                );
+            }
+        }
+        ConfigFileAction::Ungroup { name } => {
+            let name = name.trim_start_matches('@');
+            let mut config = config_file::load();
+            if config.groups.remove(name).is_none() {
+                anyhow::bail!("No group named '@{}'", name);
+            }
+            config_file::save(&config)?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            } else {
+                println!(
+                    "{} Removed group {}",
+                    "ok".green(),
+                    format!("@{}", name).bold()
+                );
+            }
+        }
         ConfigFileAction::Path => {
             let path = config_file::config_path();
             if json {
@@ -124,3 +246,18 @@ pub fn handle_config_file(action: &ConfigFileAction, json: bool) -> anyhow::Resu
 
     Ok(())
 }
+
+/// Alias and group names are used bare on the command line, so they cannot
+/// look like node IDs or group references.
+fn check_book_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty()
+        || name.starts_with(['!', '@'])
+        || name.contains(|c: char| c == ',' || c.is_whitespace())
+    {
+        anyhow::bail!(
+            "Invalid name '{}'. Names cannot be empty, start with '!' or '@', or contain commas or spaces",
+            name
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,734 @@
+use meshtastic::protobufs::config::device_config::Role;
+use meshtastic::protobufs::config::lo_ra_config::{ModemPreset, RegionCode};
+use meshtastic::protobufs::store_and_forward::{self, RequestResponse};
+use meshtastic::protobufs::telemetry::Variant as TelemetryVariant;
+use meshtastic::protobufs::{
+    admin_message, hardware_message, routing, AdminMessage, HardwareMessage, HardwareModel,
+    MapReport, NeighborInfo, Paxcount, PortNum, Position, RouteDiscovery, Routing, StoreAndForward,
+    Telemetry, User, Waypoint,
+};
+use meshtastic::Message;
+use serde_json::{json, Value};
+
+/// Traceroute SNRs are sent as dB * 4; this value marks an unknown SNR.
+const SNR_UNKNOWN: i32 = i8::MIN as i32;
+
+// ── Text ──────────────────────────────────────────────────────────
+
+/// One-line human-readable summary of a decoded payload.
+pub fn format_payload(port: &PortNum, payload: &[u8]) -> String {
+    match port {
+        PortNum::TextMessageApp | PortNum::RangeTestApp | PortNum::DetectionSensorApp => {
+            format_text(payload)
+        }
+        PortNum::PositionApp => format_position(payload),
+        PortNum::NodeinfoApp => format_nodeinfo(payload),
+        PortNum::RoutingApp => format_routing(payload),
+        PortNum::TelemetryApp => format_telemetry(payload),
+        PortNum::NeighborinfoApp => format_neighborinfo(payload),
+        PortNum::WaypointApp => format_waypoint(payload),
+        PortNum::TracerouteApp => format_traceroute(payload),
+        PortNum::StoreForwardApp => format_store_forward(payload),
+        PortNum::PaxcounterApp => format_paxcounter(payload),
+        PortNum::AdminApp => format_admin(payload),
+        PortNum::MapReportApp => format_map_report(payload),
+        PortNum::RemoteHardwareApp => format_remote_hardware(payload),
+        _ => format!("{} bytes", payload.len()),
+    }
+}
+
+pub fn format_text(payload: &[u8]) -> String {
+    String::from_utf8(payload.to_vec())
+        .unwrap_or_else(|_| format!("<invalid UTF-8: {} bytes>", payload.len()))
+}
+
+fn decode_error(payload: &[u8]) -> String {
+    format!("<decode error: {} bytes>", payload.len())
+}
+
+fn format_position(payload: &[u8]) -> String {
+    let Ok(pos) = Position::decode(payload) else {
+        return decode_error(payload);
+    };
+
+    let lat = pos.latitude_i.unwrap_or(0) as f64 * 1e-7;
+    let lon = pos.longitude_i.unwrap_or(0) as f64 * 1e-7;
+    let alt = pos.altitude.unwrap_or(0);
+    let sats = pos.sats_in_view;
+
+    let mut parts = vec![format!("{:.5}, {:.5}", lat, lon)];
+    if alt != 0 {
+        parts.push(format!("{}m", alt));
+    }
+    if sats > 0 {
+        parts.push(format!("{} sats", sats));
+    }
+    parts.join(", ")
+}
+
+fn format_nodeinfo(payload: &[u8]) -> String {
+    let Ok(user) = User::decode(payload) else {
+        return decode_error(payload);
+    };
+    format!("{} ({})", user.long_name, user.short_name)
+}
+
+fn routing_error_name(code: i32) -> &'static str {
+    match code {
+        0 => "ACK",
+        1 => "NO_ROUTE",
+        2 => "GOT_NAK",
+        3 => "TIMEOUT",
+        4 => "NO_INTERFACE",
+        5 => "MAX_RETRANSMIT",
+        6 => "NO_CHANNEL",
+        7 => "TOO_LARGE",
+        8 => "NO_RESPONSE",
+        9 => "DUTY_CYCLE_LIMIT",
+        _ => "UNKNOWN",
+    }
+}
+
+fn format_routing(payload: &[u8]) -> String {
+    let Ok(routing) = Routing::decode(payload) else {
+        return decode_error(payload);
+    };
+
+    match routing.variant {
+        Some(routing::Variant::ErrorReason(code)) => routing_error_name(code).to_string(),
+        Some(routing::Variant::RouteRequest(_)) => "Route request".to_string(),
+        Some(routing::Variant::RouteReply(_)) => "Route reply".to_string(),
+        None => "Unknown routing".to_string(),
+    }
+}
+
+fn format_telemetry(payload: &[u8]) -> String {
+    let Ok(telemetry) = Telemetry::decode(payload) else {
+        return decode_error(payload);
+    };
+
+    let (label, parts) = match telemetry.variant {
+        Some(TelemetryVariant::DeviceMetrics(m)) => {
+            let mut parts = Vec::new();
+            if let Some(bat) = m.battery_level {
+                parts.push(format!("battery {}%", bat));
+            }
+            if let Some(v) = m.voltage {
+                parts.push(format!("{:.2}V", v));
+            }
+            if let Some(cu) = m.channel_utilization {
+                parts.push(format!("ch_util {:.1}%", cu));
+            }
+            if let Some(at) = m.air_util_tx {
+                parts.push(format!("air_tx {:.1}%", at));
+            }
+            if let Some(up) = m.uptime_seconds {
+                parts.push(format!("uptime {}s", up));
+            }
+            return join_or_empty(parts, "device metrics (empty)");
+        }
+        Some(TelemetryVariant::EnvironmentMetrics(m)) => {
+            let mut parts = Vec::new();
+            if let Some(t) = m.temperature {
+                parts.push(format!("{:.1}C", t));
+            }
+            if let Some(h) = m.relative_humidity {
+                parts.push(format!("{:.1}% humidity", h));
+            }
+            if let Some(p) = m.barometric_pressure {
+                parts.push(format!("{:.1} hPa", p));
+            }
+            return join_or_empty(parts, "env metrics (empty)");
+        }
+        Some(TelemetryVariant::PowerMetrics(m)) => {
+            return format!(
+                "power: ch1 {:.2}V/{:.1}mA, ch2 {:.2}V/{:.1}mA",
+                m.ch1_voltage.unwrap_or(0.0),
+                m.ch1_current.unwrap_or(0.0),
+                m.ch2_voltage.unwrap_or(0.0),
+                m.ch2_current.unwrap_or(0.0)
+            );
+        }
+        Some(TelemetryVariant::AirQualityMetrics(m)) => {
+            let mut parts = Vec::new();
+            if let Some(v) = m.pm10_standard {
+                parts.push(format!("pm1.0 {} ug/m3", v));
+            }
+            if let Some(v) = m.pm25_standard {
+                parts.push(format!("pm2.5 {} ug/m3", v));
+            }
+            if let Some(v) = m.pm100_standard {
+                parts.push(format!("pm10 {} ug/m3", v));
+            }
+            if let Some(v) = m.co2 {
+                parts.push(format!("co2 {} ppm", v));
+            }
+            if let Some(v) = m.pm_voc_idx {
+                parts.push(format!("voc {:.1}", v));
+            }
+            if let Some(v) = m.pm_nox_idx {
+                parts.push(format!("nox {:.1}", v));
+            }
+            ("air quality", parts)
+        }
+        Some(TelemetryVariant::LocalStats(m)) => (
+            "local stats",
+            vec![
+                format!("ch_util {:.1}%", m.channel_utilization),
+                format!("air_tx {:.1}%", m.air_util_tx),
+                format!("tx {}", m.num_packets_tx),
+                format!(
+                    "rx {} ({} bad, {} dupes)",
+                    m.num_packets_rx, m.num_packets_rx_bad, m.num_rx_dupe
+                ),
+                format!("relayed {}", m.num_tx_relay),
+                format!("nodes {}/{}", m.num_online_nodes, m.num_total_nodes),
+            ],
+        ),
+        Some(TelemetryVariant::HealthMetrics(m)) => {
+            let mut parts = Vec::new();
+            if let Some(v) = m.heart_bpm {
+                parts.push(format!("{} bpm", v));
+            }
+            if let Some(v) = m.sp_o2 {
+                parts.push(format!("spo2 {}%", v));
+            }
+            if let Some(v) = m.temperature {
+                parts.push(format!("{:.1}C", v));
+            }
+            ("health", parts)
+        }
+        Some(TelemetryVariant::HostMetrics(m)) => {
+            let mut parts = vec![format!("uptime {}s", m.uptime_seconds)];
+            if m.freemem_bytes > 0 {
+                parts.push(format!("free mem {} MB", m.freemem_bytes / 1_048_576));
+            }
+            if m.diskfree1_bytes > 0 {
+                parts.push(format!("free disk {} MB", m.diskfree1_bytes / 1_048_576));
+            }
+            parts.push(format!(
+                "load {:.2} {:.2} {:.2}",
+                m.load1 as f64 / 100.0,
+                m.load5 as f64 / 100.0,
+                m.load15 as f64 / 100.0
+            ));
+            ("host", parts)
+        }
+        None => return "telemetry (no data)".to_string(),
+    };
+
+    if parts.is_empty() {
+        format!("{} (empty)", label)
+    } else {
+        format!("{}: {}", label, parts.join(", "))
+    }
+}
+
+fn join_or_empty(parts: Vec<String>, empty: &str) -> String {
+    if parts.is_empty() {
+        empty.to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn format_snr(snr_list: &[i32], idx: usize) -> Option<String> {
+    snr_list
+        .get(idx)
+        .filter(|&&s| s != SNR_UNKNOWN)
+        .map(|&s| format!("{:.1}dB", s as f64 / 4.0))
+}
+
+fn format_hops(route: &[u32], snrs: &[i32]) -> String {
+    let mut hops: Vec<String> = route
+        .iter()
+        .enumerate()
+        .map(|(i, num)| match format_snr(snrs, i) {
+            Some(snr) => format!("!{:08x} ({})", num, snr),
+            None => format!("!{:08x}", num),
+        })
+        .collect();
+    // The last SNR is the final hop into the destination
+    if let Some(snr) = format_snr(snrs, route.len()) {
+        hops.push(format!("dest ({})", snr));
+    }
+    if hops.is_empty() {
+        "direct".to_string()
+    } else {
+        hops.join(" -> ")
+    }
+}
+
+fn format_traceroute(payload: &[u8]) -> String {
+    let Ok(route) = RouteDiscovery::decode(payload) else {
+        return decode_error(payload);
+    };
+    let mut text = format!("towards: {}", format_hops(&route.route, &route.snr_towards));
+    if !route.route_back.is_empty() || !route.snr_back.is_empty() {
+        text.push_str(&format!(
+            " | back: {}",
+            format_hops(&route.route_back, &route.snr_back)
+        ));
+    }
+    text
+}
+
+fn format_neighborinfo(payload: &[u8]) -> String {
+    let Ok(info) = NeighborInfo::decode(payload) else {
+        return decode_error(payload);
+    };
+    if info.neighbors.is_empty() {
+        return format!("!{:08x}: no neighbors", info.node_id);
+    }
+    let neighbors: Vec<String> = info
+        .neighbors
+        .iter()
+        .map(|n| format!("!{:08x} ({:.1}dB)", n.node_id, n.snr))
+        .collect();
+    format!(
+        "!{:08x}: {} neighbors: {}",
+        info.node_id,
+        info.neighbors.len(),
+        neighbors.join(", ")
+    )
+}
+
+fn format_waypoint(payload: &[u8]) -> String {
+    let Ok(wp) = Waypoint::decode(payload) else {
+        return decode_error(payload);
+    };
+    let mut text = format!(
+        "#{} \"{}\" at {:.5}, {:.5}",
+        wp.id,
+        wp.name,
+        wp.latitude_i.unwrap_or(0) as f64 * 1e-7,
+        wp.longitude_i.unwrap_or(0) as f64 * 1e-7
+    );
+    if !wp.description.is_empty() {
+        text.push_str(&format!(" - {}", wp.description));
+    }
+    if wp.expire > 0 {
+        if let Some(dt) = chrono::DateTime::from_timestamp(wp.expire as i64, 0) {
+            text.push_str(&format!(", expires {}", dt.format("%Y-%m-%d %H:%M UTC")));
+        }
+    }
+    if wp.locked_to != 0 {
+        text.push_str(&format!(", locked to !{:08x}", wp.locked_to));
+    }
+    text
+}
+
+fn store_forward_kind(sf: &StoreAndForward) -> String {
+    RequestResponse::try_from(sf.rr)
+        .map(|rr| rr.as_str_name().to_string())
+        .unwrap_or_else(|_| sf.rr.to_string())
+}
+
+fn format_store_forward(payload: &[u8]) -> String {
+    let Ok(sf) = StoreAndForward::decode(payload) else {
+        return decode_error(payload);
+    };
+    let kind = store_forward_kind(&sf);
+    match &sf.variant {
+        Some(store_and_forward::Variant::Stats(s)) => format!(
+            "{}: {}/{} messages saved, {} requests, uptime {}s",
+            kind, s.messages_saved, s.messages_max, s.requests, s.up_time
+        ),
+        Some(store_and_forward::Variant::History(h)) => format!(
+            "{}: {} messages in the last {} min",
+            kind,
+            h.history_messages,
+            h.window / 60
+        ),
+        Some(store_and_forward::Variant::Heartbeat(hb)) => format!(
+            "{}: every {}s{}",
+            kind,
+            hb.period,
+            if hb.secondary != 0 {
+                " (secondary)"
+            } else {
+                ""
+            }
+        ),
+        Some(store_and_forward::Variant::Text(text)) => {
+            format!("{}: {}", kind, String::from_utf8_lossy(text))
+        }
+        None => kind,
+    }
+}
+
+fn format_paxcounter(payload: &[u8]) -> String {
+    let Ok(pax) = Paxcount::decode(payload) else {
+        return decode_error(payload);
+    };
+    format!("{} wifi, {} ble, uptime {}s", pax.wifi, pax.ble, pax.uptime)
+}
+
+/// Name of the admin message variant, e.g. `GetConfigRequest`.
+fn admin_variant_name(variant: &admin_message::PayloadVariant) -> &'static str {
+    use admin_message::PayloadVariant as Admin;
+    match variant {
+        Admin::GetChannelRequest(_) => "GetChannelRequest",
+        Admin::GetChannelResponse(_) => "GetChannelResponse",
+        Admin::GetOwnerRequest(_) => "GetOwnerRequest",
+        Admin::GetOwnerResponse(_) => "GetOwnerResponse",
+        Admin::GetConfigRequest(_) => "GetConfigRequest",
+        Admin::GetConfigResponse(_) => "GetConfigResponse",
+        Admin::GetModuleConfigRequest(_) => "GetModuleConfigRequest",
+        Admin::GetModuleConfigResponse(_) => "GetModuleConfigResponse",
+        Admin::GetCannedMessageModuleMessagesRequest(_) => "GetCannedMessageModuleMessagesRequest",
+        Admin::GetCannedMessageModuleMessagesResponse(_) => {
+            "GetCannedMessageModuleMessagesResponse"
+        }
+        Admin::GetDeviceMetadataRequest(_) => "GetDeviceMetadataRequest",
+        Admin::GetDeviceMetadataResponse(_) => "GetDeviceMetadataResponse",
+        Admin::GetRingtoneRequest(_) => "GetRingtoneRequest",
+        Admin::GetRingtoneResponse(_) => "GetRingtoneResponse",
+        Admin::GetDeviceConnectionStatusRequest(_) => "GetDeviceConnectionStatusRequest",
+        Admin::GetDeviceConnectionStatusResponse(_) => "GetDeviceConnectionStatusResponse",
+        Admin::SetHamMode(_) => "SetHamMode",
+        Admin::GetNodeRemoteHardwarePinsRequest(_) => "GetNodeRemoteHardwarePinsRequest",
+        Admin::GetNodeRemoteHardwarePinsResponse(_) => "GetNodeRemoteHardwarePinsResponse",
+        Admin::EnterDfuModeRequest(_) => "EnterDfuModeRequest",
+        Admin::DeleteFileRequest(_) => "DeleteFileRequest",
+        Admin::SetScale(_) => "SetScale",
+        Admin::BackupPreferences(_) => "BackupPreferences",
+        Admin::RestorePreferences(_) => "RestorePreferences",
+        Admin::RemoveBackupPreferences(_) => "RemoveBackupPreferences",
+        Admin::SendInputEvent(_) => "SendInputEvent",
+        Admin::SetOwner(_) => "SetOwner",
+        Admin::SetChannel(_) => "SetChannel",
+        Admin::SetConfig(_) => "SetConfig",
+        Admin::SetModuleConfig(_) => "SetModuleConfig",
+        Admin::SetCannedMessageModuleMessages(_) => "SetCannedMessageModuleMessages",
+        Admin::SetRingtoneMessage(_) => "SetRingtoneMessage",
+        Admin::RemoveByNodenum(_) => "RemoveByNodenum",
+        Admin::SetFavoriteNode(_) => "SetFavoriteNode",
+        Admin::RemoveFavoriteNode(_) => "RemoveFavoriteNode",
+        Admin::SetFixedPosition(_) => "SetFixedPosition",
+        Admin::RemoveFixedPosition(_) => "RemoveFixedPosition",
+        Admin::SetTimeOnly(_) => "SetTimeOnly",
+        Admin::GetUiConfigRequest(_) => "GetUiConfigRequest",
+        Admin::GetUiConfigResponse(_) => "GetUiConfigResponse",
+        Admin::StoreUiConfig(_) => "StoreUiConfig",
+        Admin::SetIgnoredNode(_) => "SetIgnoredNode",
+        Admin::RemoveIgnoredNode(_) => "RemoveIgnoredNode",
+        Admin::BeginEditSettings(_) => "BeginEditSettings",
+        Admin::CommitEditSettings(_) => "CommitEditSettings",
+        Admin::AddContact(_) => "AddContact",
+        Admin::KeyVerification(_) => "KeyVerification",
+        Admin::FactoryResetDevice(_) => "FactoryResetDevice",
+        Admin::RebootOtaSeconds(_) => "RebootOtaSeconds",
+        Admin::ExitSimulator(_) => "ExitSimulator",
+        Admin::RebootSeconds(_) => "RebootSeconds",
+        Admin::ShutdownSeconds(_) => "ShutdownSeconds",
+        Admin::FactoryResetConfig(_) => "FactoryResetConfig",
+        Admin::NodedbReset(_) => "NodedbReset",
+    }
+}
+
+fn format_admin(payload: &[u8]) -> String {
+    let Ok(admin) = AdminMessage::decode(payload) else {
+        return decode_error(payload);
+    };
+    match &admin.payload_variant {
+        Some(variant) => admin_variant_name(variant).to_string(),
+        None => "(empty admin message)".to_string(),
+    }
+}
+
+fn format_map_report(payload: &[u8]) -> String {
+    let Ok(report) = MapReport::decode(payload) else {
+        return decode_error(payload);
+    };
+    let mut text = format!(
+        "{} ({}), {}, fw {}, {} {}",
+        report.long_name,
+        report.short_name,
+        hw_model_name(report.hw_model),
+        report.firmware_version,
+        region_name(report.region),
+        modem_preset_name(report.modem_preset)
+    );
+    if report.latitude_i != 0 || report.longitude_i != 0 {
+        text.push_str(&format!(
+            ", at {:.5}, {:.5}",
+            report.latitude_i as f64 * 1e-7,
+            report.longitude_i as f64 * 1e-7
+        ));
+    }
+    text.push_str(&format!(", {} nodes online", report.num_online_local_nodes));
+    text
+}
+
+fn hardware_message_kind(msg: &HardwareMessage) -> String {
+    hardware_message::Type::try_from(msg.r#type)
+        .map(|t| t.as_str_name().to_string())
+        .unwrap_or_else(|_| msg.r#type.to_string())
+}
+
+fn format_remote_hardware(payload: &[u8]) -> String {
+    let Ok(msg) = HardwareMessage::decode(payload) else {
+        return decode_error(payload);
+    };
+    format!(
+        "{} mask 0x{:x} value 0x{:x}",
+        hardware_message_kind(&msg),
+        msg.gpio_mask,
+        msg.gpio_value
+    )
+}
+
+fn hw_model_name(value: i32) -> String {
+    HardwareModel::try_from(value)
+        .map(|m| m.as_str_name().to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn region_name(value: i32) -> String {
+    RegionCode::try_from(value)
+        .map(|r| r.as_str_name().to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn modem_preset_name(value: i32) -> String {
+    ModemPreset::try_from(value)
+        .map(|p| p.as_str_name().to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn role_name(value: i32) -> String {
+    Role::try_from(value)
+        .map(|r| r.as_str_name().to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+// ── JSON ──────────────────────────────────────────────────────────
+
+/// Structured JSON for a decoded payload, or `None` if the port is not
+/// understood or the payload does not decode.
+pub fn payload_to_json(port: &PortNum, payload: &[u8]) -> Option<Value> {
+    let value = match port {
+        PortNum::TextMessageApp | PortNum::RangeTestApp | PortNum::DetectionSensorApp => {
+            json!({ "text": String::from_utf8_lossy(payload) })
+        }
+        PortNum::PositionApp => {
+            let pos = Position::decode(payload).ok()?;
+            json!({
+                "latitude": pos.latitude_i.map(|v| v as f64 * 1e-7),
+                "longitude": pos.longitude_i.map(|v| v as f64 * 1e-7),
+                "altitude": pos.altitude,
+                "sats_in_view": pos.sats_in_view,
+                "time": pos.time,
+            })
+        }
+        PortNum::NodeinfoApp => {
+            let user = User::decode(payload).ok()?;
+            json!({
+                "id": user.id,
+                "long_name": user.long_name,
+                "short_name": user.short_name,
+                "hw_model": hw_model_name(user.hw_model),
+                "role": role_name(user.role),
+            })
+        }
+        PortNum::RoutingApp => {
+            let routing = Routing::decode(payload).ok()?;
+            match routing.variant {
+                Some(routing::Variant::ErrorReason(code)) => {
+                    json!({ "type": "error_reason", "error_reason": routing_error_name(code) })
+                }
+                Some(routing::Variant::RouteRequest(r)) => {
+                    json!({ "type": "route_request", "route": route_to_json(&r.route) })
+                }
+                Some(routing::Variant::RouteReply(r)) => {
+                    json!({ "type": "route_reply", "route": route_to_json(&r.route) })
+                }
+                None => json!({ "type": "empty" }),
+            }
+        }
+        PortNum::TelemetryApp => telemetry_to_json(&Telemetry::decode(payload).ok()?),
+        PortNum::NeighborinfoApp => {
+            let info = NeighborInfo::decode(payload).ok()?;
+            json!({
+                "node_id": format!("!{:08x}", info.node_id),
+                "last_sent_by": format!("!{:08x}", info.last_sent_by_id),
+                "broadcast_interval_secs": info.node_broadcast_interval_secs,
+                "neighbors": info.neighbors.iter().map(|n| json!({
+                    "node_id": format!("!{:08x}", n.node_id),
+                    "snr": n.snr,
+                    "last_rx_time": n.last_rx_time,
+                    "broadcast_interval_secs": n.node_broadcast_interval_secs,
+                })).collect::<Vec<_>>(),
+            })
+        }
+        PortNum::WaypointApp => {
+            let wp = Waypoint::decode(payload).ok()?;
+            json!({
+                "id": wp.id,
+                "name": wp.name,
+                "description": wp.description,
+                "latitude": wp.latitude_i.map(|v| v as f64 * 1e-7),
+                "longitude": wp.longitude_i.map(|v| v as f64 * 1e-7),
+                "expire": wp.expire,
+                "locked_to": (wp.locked_to != 0).then(|| format!("!{:08x}", wp.locked_to)),
+                "icon": char::from_u32(wp.icon).filter(|_| wp.icon != 0).map(String::from),
+            })
+        }
+        PortNum::TracerouteApp => {
+            let route = RouteDiscovery::decode(payload).ok()?;
+            json!({
+                "route": route_to_json(&route.route),
+                "snr_towards": snr_list_to_json(&route.snr_towards),
+                "route_back": route_to_json(&route.route_back),
+                "snr_back": snr_list_to_json(&route.snr_back),
+            })
+        }
+        PortNum::StoreForwardApp => {
+            let sf = StoreAndForward::decode(payload).ok()?;
+            let kind = store_forward_kind(&sf);
+            match sf.variant {
+                Some(store_and_forward::Variant::Stats(s)) => json!({
+                    "rr": kind,
+                    "messages_total": s.messages_total,
+                    "messages_saved": s.messages_saved,
+                    "messages_max": s.messages_max,
+                    "up_time": s.up_time,
+                    "requests": s.requests,
+                    "requests_history": s.requests_history,
+                    "heartbeat": s.heartbeat,
+                    "return_max": s.return_max,
+                    "return_window": s.return_window,
+                }),
+                Some(store_and_forward::Variant::History(h)) => json!({
+                    "rr": kind,
+                    "history_messages": h.history_messages,
+                    "window": h.window,
+                    "last_request": h.last_request,
+                }),
+                Some(store_and_forward::Variant::Heartbeat(hb)) => json!({
+                    "rr": kind,
+                    "period": hb.period,
+                    "secondary": hb.secondary,
+                }),
+                Some(store_and_forward::Variant::Text(text)) => json!({
+                    "rr": kind,
+                    "text": String::from_utf8_lossy(&text),
+                }),
+                None => json!({ "rr": kind }),
+            }
+        }
+        PortNum::PaxcounterApp => {
+            let pax = Paxcount::decode(payload).ok()?;
+            json!({ "wifi": pax.wifi, "ble": pax.ble, "uptime": pax.uptime })
+        }
+        PortNum::AdminApp => {
+            let admin = AdminMessage::decode(payload).ok()?;
+            json!({ "type": admin.payload_variant.as_ref().map(admin_variant_name) })
+        }
+        PortNum::MapReportApp => {
+            let r = MapReport::decode(payload).ok()?;
+            json!({
+                "long_name": r.long_name,
+                "short_name": r.short_name,
+                "role": role_name(r.role),
+                "hw_model": hw_model_name(r.hw_model),
+                "firmware_version": r.firmware_version,
+                "region": region_name(r.region),
+                "modem_preset": modem_preset_name(r.modem_preset),
+                "has_default_channel": r.has_default_channel,
+                "latitude": r.latitude_i as f64 * 1e-7,
+                "longitude": r.longitude_i as f64 * 1e-7,
+                "altitude": r.altitude,
+                "position_precision": r.position_precision,
+                "num_online_local_nodes": r.num_online_local_nodes,
+            })
+        }
+        PortNum::RemoteHardwareApp => {
+            let msg = HardwareMessage::decode(payload).ok()?;
+            json!({
+                "type": hardware_message_kind(&msg),
+                "gpio_mask": msg.gpio_mask,
+                "gpio_value": msg.gpio_value,
+            })
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn snr_list_to_json(snrs: &[i32]) -> Vec<Option<f64>> {
+    snrs.iter()
+        .map(|&s| (s != SNR_UNKNOWN).then_some(s as f64 / 4.0))
+        .collect()
+}
+
+fn route_to_json(route: &[u32]) -> Vec<String> {
+    route.iter().map(|num| format!("!{:08x}", num)).collect()
+}
+
+pub fn telemetry_to_json(telemetry: &Telemetry) -> Value {
+    match &telemetry.variant {
+        Some(TelemetryVariant::DeviceMetrics(m)) => json!({
+            "type": "device",
+            "battery_level": m.battery_level,
+            "voltage": m.voltage,
+            "channel_utilization": m.channel_utilization,
+            "air_util_tx": m.air_util_tx,
+            "uptime_seconds": m.uptime_seconds,
+        }),
+        Some(TelemetryVariant::EnvironmentMetrics(m)) => json!({
+            "type": "environment",
+            "temperature": m.temperature,
+            "relative_humidity": m.relative_humidity,
+            "barometric_pressure": m.barometric_pressure,
+        }),
+        Some(TelemetryVariant::PowerMetrics(m)) => json!({
+            "type": "power",
+            "ch1_voltage": m.ch1_voltage,
+            "ch1_current": m.ch1_current,
+            "ch2_voltage": m.ch2_voltage,
+            "ch2_current": m.ch2_current,
+            "ch3_voltage": m.ch3_voltage,
+            "ch3_current": m.ch3_current,
+        }),
+        Some(TelemetryVariant::AirQualityMetrics(m)) => json!({
+            "type": "air_quality",
+            "pm10_standard": m.pm10_standard,
+            "pm25_standard": m.pm25_standard,
+            "pm100_standard": m.pm100_standard,
+            "co2": m.co2,
+            "voc_index": m.pm_voc_idx,
+            "nox_index": m.pm_nox_idx,
+        }),
+        Some(TelemetryVariant::LocalStats(m)) => json!({
+            "type": "local_stats",
+            "uptime_seconds": m.uptime_seconds,
+            "channel_utilization": m.channel_utilization,
+            "air_util_tx": m.air_util_tx,
+            "packets_tx": m.num_packets_tx,
+            "packets_rx": m.num_packets_rx,
+            "packets_rx_bad": m.num_packets_rx_bad,
+            "rx_duplicates": m.num_rx_dupe,
+            "tx_relayed": m.num_tx_relay,
+            "online_nodes": m.num_online_nodes,
+            "total_nodes": m.num_total_nodes,
+        }),
+        Some(TelemetryVariant::HealthMetrics(m)) => json!({
+            "type": "health",
+            "heart_bpm": m.heart_bpm,
+            "sp_o2": m.sp_o2,
+            "temperature": m.temperature,
+        }),
+        Some(TelemetryVariant::HostMetrics(m)) => json!({
+            "type": "host",
+            "uptime_seconds": m.uptime_seconds,
+            "freemem_bytes": m.freemem_bytes,
+            "diskfree1_bytes": m.diskfree1_bytes,
+            "load1": m.load1 as f64 / 100.0,
+            "load5": m.load5 as f64 / 100.0,
+            "load15": m.load15 as f64 / 100.0,
+        }),
+        None => json!({ "type": "empty" }),
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::{HashMap, VecDeque};
+
+use meshtastic::protobufs::MeshPacket;
+
+use crate::node_db::NodeDb;
+
+/// How many recent `(from, id)` pairs are remembered for duplicate detection.
+const HISTORY_CAPACITY: usize = 4096;
+
+/// Every copy heard so far of one `(from, id)` packet.
+#[derive(Clone, Default)]
+pub struct Sighting {
+    pub count: u32,
+    /// Last byte of each relaying node, in the order they were first heard.
+    pub relays: Vec<u32>,
+}
+
+/// Remembers recently heard packets so copies rebroadcast by relays can be
+/// recognised as duplicates.
+#[derive(Default)]
+pub struct PacketHistory {
+    sightings: HashMap<(u32, u32), Sighting>,
+    order: VecDeque<(u32, u32)>,
+}
+
+impl PacketHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one copy of `packet`. Returns the updated sighting if an earlier
+    /// copy was already heard, `None` for the first copy. Packets without an
+    /// `id` are never treated as duplicates.
+    pub fn record(&mut self, packet: &MeshPacket) -> Option<&Sighting> {
+        if packet.id == 0 {
+            return None;
+        }
+
+        let key = (packet.from, packet.id);
+        let duplicate = self.sightings.contains_key(&key);
+        if !duplicate {
+            self.order.push_back(key);
+            if self.order.len() > HISTORY_CAPACITY {
+                if let Some(old) = self.order.pop_front() {
+                    self.sightings.remove(&old);
+                }
+            }
+        }
+
+        let sighting = self.sightings.entry(key).or_default();
+        sighting.count += 1;
+        if packet.relay_node != 0 && !sighting.relays.contains(&packet.relay_node) {
+            sighting.relays.push(packet.relay_node);
+        }
+
+        duplicate.then_some(&*sighting)
+    }
+}
+
+/// Formats a `relay_node` byte, naming the node when exactly one known node
+/// ends in that byte.
+pub fn relay_label(relay: u32, node_db: &NodeDb) -> String {
+    let mut matches = node_db
+        .nodes()
+        .keys()
+        .filter(|&&num| num & 0xFF == relay & 0xFF);
+    match (matches.next(), matches.next()) {
+        (Some(&num), None) => match node_db.node_name(num) {
+            Some(name) => format!("{:02x} ({})", relay, name),
+            None => format!("{:02x} (!{:08x})", relay, num),
+        },
+        _ => format!("{:02x}", relay),
+    }
+}
@@ -3,13 +3,19 @@ use colored::Colorize;
 use meshtastic::packet::PacketDestination;
 use meshtastic::types::NodeId;
 
+use crate::config_file::{self, AppConfig};
 use crate::error::CliError;
-use crate::node_db::NodeDb;
+use crate::node_db::{NameMatch, NodeDb};
 
 pub enum DestinationSpec {
     Broadcast,
     NodeId(u32),
     NodeName(String),
+    /// Address book entry from config.toml
+    Alias {
+        name: String,
+        id: u32,
+    },
 }
 
 pub fn resolve_destination(
@@ -22,69 +28,212 @@ pub fn resolve_destination(
             PacketDestination::Node(NodeId::new(*id)),
             format!("!{:08x}", id),
         )),
+        DestinationSpec::Alias { name, id } => Ok((
+            PacketDestination::Node(NodeId::new(*id)),
+            format!("{} (!{:08x})", name, id),
+        )),
         DestinationSpec::NodeName(name) => {
-            let matches = node_db.find_by_name(name);
+            let matches = node_db.search_by_name(name);
+            let best = matches.first().map(|(_, _, kind)| *kind);
+            let top: Vec<_> = matches
+                .iter()
+                .filter(|(_, _, kind)| Some(*kind) == best)
+                .collect();
 
-            match matches.len() {
-                0 => bail!(
-                    "No node found with name '{}'. Use 'nodes' command to list known nodes.",
+            match top.as_slice() {
+                [] => bail!(
+                    "No node found matching '{}'. Use 'nodes' command to list known nodes.",
                     name
                 ),
-                1 => {
-                    let (num, node) = &matches[0];
+                [(num, node, kind)] => {
                     let node_name = node
                         .user
                         .as_ref()
                         .map(|u| u.long_name.as_str())
                         .unwrap_or("Unknown");
+                    let how = match kind {
+                        NameMatch::LongName => String::new(),
+                        other => format!(" by {}", other.describe()),
+                    };
                     println!(
-                        "{} Resolved '{}' to !{:08x} ({})",
+                        "{} Resolved '{}' to !{:08x} ({}){}",
                         "→".cyan(),
                         name,
                         num,
-                        node_name
+                        node_name,
+                        how
                     );
                     Ok((
                         PacketDestination::Node(NodeId::new(*num)),
                         format!("{} (!{:08x})", node_name, num),
                     ))
                 }
-                _ => {
-                    let mut msg = format!(
-                        "Multiple nodes found with name '{}'. Use --dest with the node ID:\n",
-                        name
-                    );
-                    for (num, node) in &matches {
-                        let node_name = node
-                            .user
-                            .as_ref()
-                            .map(|u| u.long_name.as_str())
-                            .unwrap_or("Unknown");
-                        msg.push_str(&format!("  !{:08x}  {}\n", num, node_name));
-                    }
-                    bail!("{}", msg.trim_end())
+                _ => Err(AmbiguousNode {
+                    name: name.clone(),
+                    candidates: top
+                        .iter()
+                        .map(|(num, node, kind)| {
+                            let user = node.user.as_ref();
+                            let label = match user {
+                                Some(u) if !u.short_name.is_empty() => {
+                                    format!("{} [{}]", u.long_name, u.short_name)
+                                }
+                                Some(u) => u.long_name.clone(),
+                                None => "Unknown".to_string(),
+                            };
+                            (*num, format!("{}  ({})", label, kind.describe()))
+                        })
+                        .collect(),
                 }
+                .into()),
             }
         }
     }
 }
 
+/// Several nodes match a `--to` name equally well.
+#[derive(Debug)]
+pub struct AmbiguousNode {
+    pub name: String,
+    /// Node number and a display label for each candidate, best first
+    pub candidates: Vec<(u32, String)>,
+}
+
+impl std::fmt::Display for AmbiguousNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Multiple nodes match '{}'. Use --dest with the node ID:",
+            self.name
+        )?;
+        for (num, label) in &self.candidates {
+            write!(f, "\n  !{:08x}  {}", num, label)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AmbiguousNode {}
+
 pub fn parse_dest_spec(
     dest: &Option<String>,
     to: &Option<String>,
 ) -> Result<DestinationSpec, CliError> {
     match (dest, to) {
-        (Some(hex_str), None) => {
-            let stripped = hex_str.strip_prefix('!').unwrap_or(hex_str);
-            let node_num = u32::from_str_radix(stripped, 16).map_err(|_| {
+        (Some(hex_str), None) => Ok(DestinationSpec::NodeId(parse_node_hex(hex_str)?)),
+        (None, Some(name)) => parse_name(name, &config_file::load()),
+        _ => Ok(DestinationSpec::Broadcast),
+    }
+}
+
+/// Like [`parse_dest_spec`], but a `--to @group` expands to one spec per
+/// group member. For commands that fan out over groups.
+pub fn parse_dest_targets(
+    dest: &Option<String>,
+    to: &Option<String>,
+) -> Result<Vec<DestinationSpec>, CliError> {
+    match to {
+        Some(name) if name.starts_with('@') => expand_group(name, &config_file::load()),
+        _ => Ok(vec![parse_dest_spec(dest, to)?]),
+    }
+}
+
+/// Parses repeatable `--dest` / `--to` values into one spec per recipient,
+/// IDs first. No values means a single broadcast.
+pub fn parse_dest_specs(dest: &[String], to: &[String]) -> Result<Vec<DestinationSpec>, CliError> {
+    if dest.is_empty() && to.is_empty() {
+        return Ok(vec![DestinationSpec::Broadcast]);
+    }
+    let config = config_file::load();
+    let mut specs = dest
+        .iter()
+        .map(|d| parse_node_hex(d).map(DestinationSpec::NodeId))
+        .collect::<Result<Vec<_>, _>>()?;
+    for name in to {
+        if name.starts_with('@') {
+            specs.extend(expand_group(name, &config)?);
+        } else {
+            specs.push(parse_name(name, &config)?);
+        }
+    }
+    Ok(specs)
+}
+
+pub(super) fn parse_node_hex(hex_str: &str) -> Result<u32, CliError> {
+    let stripped = hex_str.strip_prefix('!').unwrap_or(hex_str);
+    u32::from_str_radix(stripped, 16).map_err(|_| {
+        CliError::InvalidArgument(format!(
+            "Invalid node ID '{}'. Expected hex format like !abcd1234",
+            hex_str
+        ))
+    })
+}
+
+/// A `--to` value: an alias from the address book, else a node name.
+fn parse_name(name: &str, config: &AppConfig) -> Result<DestinationSpec, CliError> {
+    if name.starts_with('@') {
+        return Err(CliError::InvalidArgument(format!(
+            "Group '{}' can only be used with send, ping, request and traceroute",
+            name
+        )));
+    }
+    match config
+        .aliases
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+    {
+        Some((alias, node)) => {
+            let id = parse_node_hex(node).map_err(|_| {
                 CliError::InvalidArgument(format!(
-                    "Invalid node ID '{}'. Expected hex format like !abcd1234",
-                    hex_str
+                    "Alias '{}' in {} has an invalid node ID '{}'",
+                    alias,
+                    config_file::config_path().display(),
+                    node
                 ))
             })?;
-            Ok(DestinationSpec::NodeId(node_num))
+            Ok(DestinationSpec::Alias {
+                name: alias.clone(),
+                id,
+            })
         }
-        (None, Some(name)) => Ok(DestinationSpec::NodeName(name.clone())),
-        _ => Ok(DestinationSpec::Broadcast),
+        None => Ok(DestinationSpec::NodeName(name.to_string())),
+    }
+}
+
+/// Expands `@group` into its members. Members starting with `!` are node IDs;
+/// others are aliases or node names.
+fn expand_group(name: &str, config: &AppConfig) -> Result<Vec<DestinationSpec>, CliError> {
+    let group = name.trim_start_matches('@');
+    let Some((_, members)) = config
+        .groups
+        .iter()
+        .find(|(g, _)| g.eq_ignore_ascii_case(group))
+    else {
+        return Err(CliError::InvalidArgument(format!(
+            "Unknown group '{}'. Define it with 'config-file group {} <MEMBERS>'",
+            name, group
+        )));
+    };
+    if members.is_empty() {
+        return Err(CliError::InvalidArgument(format!(
+            "Group '{}' has no members",
+            name
+        )));
     }
+
+    members
+        .iter()
+        .map(|member| {
+            if member.starts_with('!') {
+                parse_node_hex(member).map(DestinationSpec::NodeId)
+            } else if member.starts_with('@') {
+                Err(CliError::InvalidArgument(format!(
+                    "Group '{}' contains group '{}'; groups cannot be nested",
+                    name, member
+                )))
+            } else {
+                parse_name(member, config)
+            }
+        })
+        .collect()
 }
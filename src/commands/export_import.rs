@@ -7,6 +7,7 @@ use colored::Colorize;
 use meshtastic::protobufs::{self, channel, ChannelSettings};
 use serde_yaml::Value;
 
+use super::parsers::hex_encode;
 use super::{Command, CommandContext};
 
 // ── ExportConfigCommand ────────────────────────────────────────────
@@ -616,10 +617,6 @@ fn export_channel(ch: &protobufs::Channel) -> Value {
     ])
 }
 
-fn hex_encode(bytes: &[u8]) -> String {
-    bytes.iter().map(|b| format!("{:02x}", b)).collect()
-}
-
 // ── Import helpers ─────────────────────────────────────────────────
 
 fn yaml_to_string(v: &Value) -> String {
@@ -0,0 +1,47 @@
+use anyhow::bail;
+use async_trait::async_trait;
+use colored::Colorize;
+use serde_json::json;
+
+use super::{Command, CommandContext};
+
+/// Runs one command per member of a `--to @group`, one after another. A
+/// failure is reported and the remaining members are still tried. With
+/// `--json` the members' results (or `{"error": ...}`) form one array.
+pub struct FanOutCommand {
+    pub commands: Vec<Box<dyn Command + Send + Sync>>,
+    pub json: bool,
+}
+
+#[async_trait]
+impl Command for FanOutCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let total = self.commands.len();
+        let mut failed = 0;
+
+        // Each member's JSON result is collected and printed as one array
+        if self.json {
+            ctx.json_results = Some(Vec::new());
+        }
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 && !self.json {
+                println!();
+            }
+            if let Err(e) = command.execute(ctx).await {
+                eprintln!("{} {}", "✗".red(), e);
+                if let Some(results) = &mut ctx.json_results {
+                    results.push(json!({ "error": format!("{:#}", e) }));
+                }
+                failed += 1;
+            }
+        }
+        if let Some(results) = ctx.json_results.take() {
+            println!("{}", serde_json::to_string_pretty(&results)?);
+        }
+
+        if failed > 0 {
+            bail!("{} of {} group members failed", failed, total);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use meshtastic::packet::PacketDestination;
+use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
+use meshtastic::protobufs::{MeshPacket, PortNum};
+use regex::Regex;
+
+use super::decode::format_payload;
+use super::destination::{parse_dest_spec, resolve_destination, DestinationSpec};
+use crate::cli::PacketFilterArgs;
+use crate::error::CliError;
+use crate::node_db::NodeDb;
+
+/// Packet selection criteria shared by `listen`, `reply` and `mqtt bridge`.
+pub struct PacketFilter {
+    from: Option<DestinationSpec>,
+    ports: Vec<i32>,
+    channel: Option<u32>,
+    pattern: Option<Regex>,
+    min_snr: Option<f32>,
+    direct_only: bool,
+}
+
+/// A filter whose `--from` node has been resolved against the node DB.
+pub struct ActiveFilter<'a> {
+    filter: &'a PacketFilter,
+    from: Option<u32>,
+}
+
+impl PacketFilter {
+    pub fn from_args(args: &PacketFilterArgs, ports: &[String]) -> Result<Self, CliError> {
+        let from = match &args.from {
+            Some(node) if looks_like_node_id(node) => {
+                Some(parse_dest_spec(&Some(node.clone()), &None)?)
+            }
+            Some(name) => Some(parse_dest_spec(&None, &Some(name.clone()))?),
+            None => None,
+        };
+
+        let ports = ports
+            .iter()
+            .map(|p| parse_port(p))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pattern = args
+            .r#match
+            .as_ref()
+            .map(|m| Regex::new(m))
+            .transpose()
+            .map_err(|e| CliError::InvalidArgument(format!("Invalid --match regex: {}", e)))?;
+
+        Ok(Self {
+            from,
+            ports,
+            channel: args.channel,
+            pattern,
+            min_snr: args.min_snr,
+            direct_only: args.direct_only,
+        })
+    }
+
+    pub fn resolve(&self, node_db: &NodeDb) -> anyhow::Result<ActiveFilter<'_>> {
+        let from = match &self.from {
+            Some(spec) => match resolve_destination(spec, node_db)? {
+                (PacketDestination::Node(id), _) => Some(id.id()),
+                _ => None,
+            },
+            None => None,
+        };
+        Ok(ActiveFilter { filter: self, from })
+    }
+}
+
+impl ActiveFilter<'_> {
+    pub fn matches(&self, packet: &MeshPacket) -> bool {
+        let f = self.filter;
+
+        if self.from.is_some_and(|from| packet.from != from) {
+            return false;
+        }
+        if f.min_snr.is_some_and(|min| packet.rx_snr < min) {
+            return false;
+        }
+        if f.direct_only && packet.hop_start.saturating_sub(packet.hop_limit) > 0 {
+            return false;
+        }
+
+        let decoded = match &packet.payload_variant {
+            Some(MeshPayload::Decoded(data)) => Some(data),
+            _ => None,
+        };
+
+        // Undecoded packets carry the channel hash in `channel`, not an index
+        if f.channel
+            .is_some_and(|ch| decoded.is_none() || packet.channel != ch)
+        {
+            return false;
+        }
+        if !f.ports.is_empty() && !decoded.is_some_and(|d| f.ports.contains(&d.portnum)) {
+            return false;
+        }
+
+        if let Some(ref pattern) = f.pattern {
+            let Some(data) = decoded else {
+                return false;
+            };
+            let port = PortNum::try_from(data.portnum).unwrap_or(PortNum::UnknownApp);
+            if !pattern.is_match(&format_payload(&port, &data.payload)) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Parses a port name such as `TELEMETRY_APP`, `telemetry` or a raw port number.
+pub fn parse_port(value: &str) -> Result<i32, CliError> {
+    let upper = value.trim().to_uppercase();
+    if let Some(port) =
+        PortNum::from_str_name(&upper).or_else(|| PortNum::from_str_name(&format!("{}_APP", upper)))
+    {
+        return Ok(port as i32);
+    }
+    upper.parse::<i32>().map_err(|_| {
+        CliError::InvalidArgument(format!(
+            "Unknown port '{}'. Use a port name like TEXT_MESSAGE_APP, POSITION_APP or TELEMETRY_APP.",
+            value
+        ))
+    })
+}
+
+pub(super) fn looks_like_node_id(value: &str) -> bool {
+    let stripped = value.strip_prefix('!');
+    let hex = stripped.unwrap_or(value);
+    (stripped.is_some() || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
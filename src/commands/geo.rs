@@ -0,0 +1,168 @@
+use meshtastic::protobufs::{NodeInfo, Position};
+
+use crate::cli::UnitsArg;
+
+/// Mean Earth radius used for great-circle distances.
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+pub const METERS_PER_MILE: f64 = 1609.344;
+pub const FEET_PER_METER: f64 = 3.280_84;
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl From<&UnitsArg> for Units {
+    fn from(arg: &UnitsArg) -> Self {
+        match arg {
+            UnitsArg::Metric => Units::Metric,
+            UnitsArg::Imperial => Units::Imperial,
+        }
+    }
+}
+
+impl Units {
+    /// Distance in the unit `format_distance` switches to for longer
+    /// ranges: kilometers or miles.
+    pub fn long_distance(self, meters: f64) -> f64 {
+        match self {
+            Units::Metric => meters / 1000.0,
+            Units::Imperial => meters / METERS_PER_MILE,
+        }
+    }
+
+    /// Height in meters or feet.
+    pub fn height(self, meters: f64) -> f64 {
+        match self {
+            Units::Metric => meters,
+            Units::Imperial => meters * FEET_PER_METER,
+        }
+    }
+
+    pub fn format_distance(self, meters: f64) -> String {
+        match self {
+            Units::Metric if meters < 1000.0 => format!("{:.0} m", meters),
+            Units::Metric => format!("{:.1} km", meters / 1000.0),
+            Units::Imperial if meters < METERS_PER_MILE / 4.0 => {
+                format!("{:.0} ft", meters * FEET_PER_METER)
+            }
+            Units::Imperial => format!("{:.1} mi", meters / METERS_PER_MILE),
+        }
+    }
+
+    /// Signed height difference, e.g. `+35 m` or `-120 ft`.
+    pub fn format_height_diff(self, meters: f64) -> String {
+        let unit = match self {
+            Units::Metric => "m",
+            Units::Imperial => "ft",
+        };
+        format!("{:+.0} {}", self.height(meters), unit)
+    }
+}
+
+/// The point distances and bearings are measured from.
+#[derive(Clone, Copy)]
+pub struct Origin {
+    pub lat: f64,
+    pub lon: f64,
+    /// Meters above sea level, when known
+    pub alt: Option<i32>,
+}
+
+impl Origin {
+    /// `--from-lat/--from-lon` when given, else the position of `local`.
+    pub fn resolve(from: Option<(f64, f64)>, local: Option<&NodeInfo>) -> Option<Self> {
+        if let Some((lat, lon)) = from {
+            return Some(Self {
+                lat,
+                lon,
+                alt: None,
+            });
+        }
+        let pos = local?.position.as_ref()?;
+        let (lat, lon) = position_lat_lon(pos)?;
+        Some(Self {
+            lat,
+            lon,
+            alt: pos.altitude,
+        })
+    }
+
+    /// Where `pos` lies as seen from this origin, if it has coordinates.
+    pub fn relative(&self, pos: &Position) -> Option<Relative> {
+        let to = position_lat_lon(pos)?;
+        let from = (self.lat, self.lon);
+        Some(Relative {
+            distance_m: distance_m(from, to),
+            bearing_deg: bearing_deg(from, to),
+            altitude_diff_m: self.alt.zip(pos.altitude).map(|(a, b)| b - a),
+        })
+    }
+
+    pub fn relative_to_node(&self, node: &NodeInfo) -> Option<Relative> {
+        self.relative(node.position.as_ref()?)
+    }
+}
+
+/// Distance, bearing and height difference from an [`Origin`].
+#[derive(Clone, Copy)]
+pub struct Relative {
+    pub distance_m: f64,
+    /// Initial great-circle bearing, 0-360 degrees clockwise from north
+    pub bearing_deg: f64,
+    pub altitude_diff_m: Option<i32>,
+}
+
+impl Relative {
+    /// Bearing with its compass point, e.g. `045° NE`.
+    pub fn format_bearing(&self) -> String {
+        // 359.5° rounds to 360, which is 000
+        format!(
+            "{:03}° {}",
+            self.bearing_deg.round() as u32 % 360,
+            compass_point(self.bearing_deg)
+        )
+    }
+}
+
+fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing + 22.5).rem_euclid(360.0) / 45.0) as usize % 8]
+}
+
+/// Latitude and longitude in degrees, or `None` when the position is unset
+/// (firmware reports 0/0 for "no fix").
+pub fn position_lat_lon(pos: &Position) -> Option<(f64, f64)> {
+    let lat = pos.latitude_i.unwrap_or(0);
+    let lon = pos.longitude_i.unwrap_or(0);
+    if lat == 0 && lon == 0 {
+        return None;
+    }
+    Some((lat as f64 / 1e7, lon as f64 / 1e7))
+}
+
+pub fn node_lat_lon(node: &NodeInfo) -> Option<(f64, f64)> {
+    node.position.as_ref().and_then(position_lat_lon)
+}
+
+/// Great-circle (haversine) distance in meters.
+pub fn distance_m(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+}
+
+/// Initial great-circle bearing in degrees, 0-360 clockwise from north.
+pub fn bearing_deg(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+    let dlon = lon2 - lon1;
+    let y = dlon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
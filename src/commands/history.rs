@@ -0,0 +1,667 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::Local;
+use colored::Colorize;
+use meshtastic::packet::PacketDestination;
+use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
+use meshtastic::protobufs::telemetry::Variant as TelemetryVariant;
+use meshtastic::protobufs::{self, MeshPacket, NodeInfo, PortNum, Position, Telemetry};
+use meshtastic::Message;
+use serde::{Deserialize, Serialize};
+
+use super::dedup::PacketHistory;
+use super::destination::{parse_dest_spec, resolve_destination};
+use super::filter::looks_like_node_id;
+use super::geo::{distance_m, position_lat_lon, Units};
+use super::listen::hops_away;
+use super::parsers::{
+    format_time, format_uptime, parse_duration, parse_node_id, parse_time, sparkline,
+};
+use super::store::JsonlStore;
+use crate::config_file::config_dir;
+use crate::node_db::NodeDb;
+
+/// Numeric fields summarized by `nodes history`.
+const METRICS: [&str; 5] = ["snr", "rssi", "hops", "battery", "voltage"];
+/// Values shown as a sparkline in the metrics table.
+const SPARKLINE_POINTS: usize = 24;
+const STORE: JsonlStore<NodeSighting> = JsonlStore::new("sightings.jsonl", "sighting store");
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SightingSource {
+    /// Taken from the device's node DB when connecting
+    Handshake,
+    /// A packet heard from the node
+    Packet,
+}
+
+/// One line of the sighting store (`sightings.jsonl` in the config directory).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NodeSighting {
+    /// Unix time the node was heard
+    pub time: i64,
+    pub node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub source: SightingSource,
+    /// Our node, which heard the packet or reported the node in its node DB
+    pub gateway: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub via_mqtt: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snr: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rssi: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hops: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<f32>,
+    /// Seconds since the node booted, from its device metrics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<i32>,
+}
+
+impl NodeSighting {
+    /// Builds a record from a node DB entry, timed at its `last_heard`.
+    /// `None` for nodes never heard.
+    fn from_node(node: &NodeInfo, gateway: u32) -> Option<Self> {
+        if node.last_heard == 0 {
+            return None;
+        }
+        let metrics = node.device_metrics.as_ref();
+        let mut sighting = Self {
+            time: node.last_heard as i64,
+            node: format!("!{:08x}", node.num),
+            name: node.user.as_ref().map(|u| u.long_name.clone()),
+            source: SightingSource::Handshake,
+            gateway: format!("!{:08x}", gateway),
+            via_mqtt: node.via_mqtt,
+            snr: (node.snr != 0.0).then_some(node.snr),
+            rssi: None,
+            hops: node.hops_away,
+            battery: metrics.and_then(|m| m.battery_level),
+            voltage: metrics.and_then(|m| m.voltage),
+            uptime: metrics.and_then(|m| m.uptime_seconds),
+            latitude: None,
+            longitude: None,
+            altitude: None,
+        };
+        if let Some(ref pos) = node.position {
+            sighting.set_position(pos);
+        }
+        Some(sighting)
+    }
+
+    /// Builds a record from a packet: link quality always, plus battery and
+    /// voltage from device telemetry and the fix from position packets.
+    fn from_packet(packet: &MeshPacket, node_db: &NodeDb) -> Option<Self> {
+        if packet.from == 0 {
+            return None;
+        }
+        let time = if packet.rx_time != 0 {
+            packet.rx_time as i64
+        } else {
+            Local::now().timestamp()
+        };
+        // Packets from our own node never crossed the radio
+        let heard = packet.rx_snr != 0.0 || packet.rx_rssi != 0;
+        let mut sighting = Self {
+            time,
+            node: format!("!{:08x}", packet.from),
+            name: node_db.node_name(packet.from).map(|s| s.to_string()),
+            source: SightingSource::Packet,
+            gateway: format!("!{:08x}", node_db.my_node_num()),
+            via_mqtt: packet.via_mqtt,
+            snr: heard.then_some(packet.rx_snr),
+            rssi: heard.then_some(packet.rx_rssi),
+            hops: (packet.hop_start != 0).then(|| hops_away(packet)),
+            battery: None,
+            voltage: None,
+            uptime: None,
+            latitude: None,
+            longitude: None,
+            altitude: None,
+        };
+
+        let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant else {
+            return Some(sighting);
+        };
+        match PortNum::try_from(data.portnum) {
+            Ok(PortNum::TelemetryApp) => {
+                if let Ok(Telemetry {
+                    variant: Some(TelemetryVariant::DeviceMetrics(m)),
+                    ..
+                }) = Telemetry::decode(data.payload.as_slice())
+                {
+                    sighting.battery = m.battery_level;
+                    sighting.voltage = m.voltage;
+                    sighting.uptime = m.uptime_seconds;
+                }
+            }
+            Ok(PortNum::PositionApp) => {
+                if let Ok(pos) = Position::decode(data.payload.as_slice()) {
+                    sighting.set_position(&pos);
+                }
+            }
+            Ok(PortNum::NodeinfoApp) => {
+                if let Ok(user) = protobufs::User::decode(data.payload.as_slice()) {
+                    sighting.name = Some(user.long_name);
+                }
+            }
+            _ => {}
+        }
+        Some(sighting)
+    }
+
+    fn set_position(&mut self, pos: &Position) {
+        if let Some((lat, lon)) = position_lat_lon(pos) {
+            self.latitude = Some(lat);
+            self.longitude = Some(lon);
+            self.altitude = pos.altitude;
+        }
+    }
+
+    fn node_num(&self) -> Option<u32> {
+        parse_node_id(&self.node)
+    }
+
+    /// The value of one of [`METRICS`].
+    fn metric(&self, metric: &str) -> Option<f64> {
+        match metric {
+            "snr" => self.snr.map(f64::from),
+            "rssi" => self.rssi.map(f64::from),
+            "hops" => self.hops.map(f64::from),
+            "battery" => self.battery.map(f64::from),
+            "voltage" => self.voltage.map(f64::from),
+            _ => None,
+        }
+    }
+
+    fn lat_lon(&self) -> Option<(f64, f64)> {
+        Some((self.latitude?, self.longitude?))
+    }
+}
+
+/// Records a sighting for the first copy of every packet heard.
+#[derive(Default)]
+pub struct SightingRecorder {
+    history: PacketHistory,
+    warned: bool,
+}
+
+impl SightingRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Storage errors are reported once as a warning; the command carries on.
+    pub fn record(&mut self, packet: &MeshPacket, node_db: &NodeDb) {
+        if self.history.record(packet).is_some() {
+            return;
+        }
+        let Some(sighting) = NodeSighting::from_packet(packet, node_db) else {
+            return;
+        };
+        if let Err(e) = STORE.append(std::slice::from_ref(&sighting)) {
+            if !self.warned {
+                eprintln!("{} Failed to store node sighting: {:#}", "!".yellow(), e);
+                self.warned = true;
+            }
+        }
+    }
+}
+
+/// Records every node in the handshake node DB heard since the previous
+/// handshake, so repeated connections don't repeat the same sighting.
+pub fn record_handshake(node_db: &NodeDb) {
+    if let Err(e) = try_record_handshake(node_db) {
+        eprintln!("{} Failed to store node sightings: {:#}", "!".yellow(), e);
+    }
+}
+
+fn try_record_handshake(node_db: &NodeDb) -> anyhow::Result<()> {
+    let mut heard = read_heard_marker();
+    let gateway = node_db.my_node_num();
+    let mut nodes: Vec<&NodeInfo> = node_db.nodes().values().collect();
+    nodes.sort_by_key(|n| (n.last_heard, n.num));
+
+    let sightings: Vec<NodeSighting> = nodes
+        .into_iter()
+        .filter(|n| heard.get(&n.num).is_none_or(|&last| n.last_heard > last))
+        .filter_map(|n| NodeSighting::from_node(n, gateway))
+        .collect();
+    if sightings.is_empty() {
+        return Ok(());
+    }
+    STORE.append(&sightings)?;
+
+    for sighting in &sightings {
+        if let Some(num) = sighting.node_num() {
+            heard.insert(num, sighting.time as u32);
+        }
+    }
+    save_heard_marker(&heard)
+}
+
+/// `last_heard` of each node as of the latest handshake recorded.
+fn heard_marker_path() -> PathBuf {
+    config_dir().join("sightings.heard")
+}
+
+fn read_heard_marker() -> HashMap<u32, u32> {
+    let marker: BTreeMap<String, u32> = std::fs::read_to_string(heard_marker_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    marker
+        .into_iter()
+        .filter_map(|(id, time)| Some((parse_node_id(&id)?, time)))
+        .collect()
+}
+
+fn save_heard_marker(heard: &HashMap<u32, u32>) -> anyhow::Result<()> {
+    let marker: BTreeMap<String, u32> = heard
+        .iter()
+        .map(|(num, time)| (format!("!{:08x}", num), *time))
+        .collect();
+    let path = heard_marker_path();
+    std::fs::write(&path, serde_json::to_string(&marker)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A node DB holding the latest name recorded for each node, so a node name
+/// resolves like `send --to` without a device.
+fn node_db_from_sightings(sightings: &[NodeSighting]) -> NodeDb {
+    NodeDb::from_names(
+        sightings
+            .iter()
+            .filter_map(|s| Some((s.node_num()?, s.name.clone()?))),
+    )
+}
+
+// ── nodes history command ─────────────────────────────────────────
+
+#[derive(Serialize)]
+struct MetricSummary {
+    min: f64,
+    avg: f64,
+    max: f64,
+    latest: f64,
+    samples: usize,
+    #[serde(skip)]
+    recent: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct Gap {
+    from: i64,
+    to: i64,
+    seconds: i64,
+}
+
+#[derive(Serialize)]
+struct Reboot {
+    /// Estimated boot time
+    time: i64,
+    /// Uptime reported before the reboot
+    uptime_before: u32,
+}
+
+#[derive(Serialize)]
+struct NodeHistoryJson<'a> {
+    id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    first_seen: i64,
+    last_seen: i64,
+    count: usize,
+    gateways: Vec<&'a str>,
+    metrics: BTreeMap<&'static str, MetricSummary>,
+    gaps: Vec<Gap>,
+    reboots: Vec<Reboot>,
+    sightings: Vec<&'a NodeSighting>,
+}
+
+#[derive(Serialize)]
+struct NodeOverviewJson<'a> {
+    id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    first_seen: i64,
+    last_seen: i64,
+    count: usize,
+}
+
+pub struct HistoryOptions<'a> {
+    pub node: Option<&'a str>,
+    pub since: Option<&'a str>,
+    pub gap: &'a str,
+    pub last: usize,
+}
+
+pub fn handle_node_history(
+    options: &HistoryOptions,
+    units: Units,
+    json: bool,
+) -> anyhow::Result<()> {
+    let since = options.since.map(parse_time).transpose()?;
+    let gap_secs = parse_duration(options.gap)?.as_secs().max(1) as i64;
+
+    let mut sightings = STORE.load()?;
+    sightings.sort_by_key(|s| s.time);
+    let node_db = node_db_from_sightings(&sightings);
+    sightings.retain(|s| since.is_none_or(|t| s.time >= t));
+
+    let Some(node) = options.node else {
+        return print_overview(&sightings, json);
+    };
+
+    let spec = if looks_like_node_id(node) {
+        parse_dest_spec(&Some(node.to_string()), &None)?
+    } else {
+        parse_dest_spec(&None, &Some(node.to_string()))?
+    };
+    let (dest, label) = resolve_destination(&spec, &node_db)?;
+    let PacketDestination::Node(node) = dest else {
+        anyhow::bail!("nodes history needs a node ID or name");
+    };
+    let id = format!("!{:08x}", node.id());
+    let node_sightings: Vec<&NodeSighting> = sightings.iter().filter(|s| s.node == id).collect();
+
+    let (Some(first), Some(last)) = (node_sightings.first(), node_sightings.last()) else {
+        if json {
+            println!("null");
+        } else {
+            println!("{} No sightings of {} recorded.", "->".cyan(), label);
+        }
+        return Ok(());
+    };
+
+    let name = node_sightings.iter().rev().find_map(|s| s.name.as_deref());
+    let gateways: Vec<&str> = node_sightings
+        .iter()
+        .map(|s| s.gateway.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut metrics = BTreeMap::new();
+    for metric in METRICS {
+        if let Some(summary) = summarize(node_sightings.iter().filter_map(|s| s.metric(metric))) {
+            metrics.insert(metric, summary);
+        }
+    }
+
+    let gaps = find_gaps(&node_sightings, gap_secs);
+    let reboots = find_reboots(&node_sightings);
+
+    if json {
+        let record = NodeHistoryJson {
+            id: &id,
+            name,
+            first_seen: first.time,
+            last_seen: last.time,
+            count: node_sightings.len(),
+            gateways,
+            metrics,
+            gaps,
+            reboots,
+            sightings: node_sightings,
+        };
+        println!("{}", serde_json::to_string_pretty(&record)?);
+        return Ok(());
+    }
+
+    let title = match name {
+        Some(name) => format!("{} ({})", name, id),
+        None => id.clone(),
+    };
+    println!("{} History of {}", "->".cyan(), title.bold());
+    let packets = node_sightings
+        .iter()
+        .filter(|s| s.source == SightingSource::Packet)
+        .count();
+    println!("  {:<12} {}", "First seen", format_time(first.time));
+    println!(
+        "  {:<12} {} ({} ago)",
+        "Last seen",
+        format_time(last.time),
+        span(Local::now().timestamp() - last.time)
+    );
+    println!(
+        "  {:<12} {} ({} packets, {} handshakes)",
+        "Sightings",
+        node_sightings.len(),
+        packets,
+        node_sightings.len() - packets
+    );
+    println!("  {:<12} {}", "Gateways", gateways.join(", "));
+    if let Some(note) = position_note(&node_sightings, units) {
+        println!("  {:<12} {}", "Positions", note);
+    }
+
+    if !metrics.is_empty() {
+        println!(
+            "\n  {:<8} {:>8} {:>8} {:>8} {:>8}  {}",
+            "Metric".bold(),
+            "Min".bold(),
+            "Avg".bold(),
+            "Max".bold(),
+            "Latest".bold(),
+            "Trend".bold()
+        );
+        for (metric, m) in METRICS.iter().filter_map(|k| Some((k, metrics.get(k)?))) {
+            let label = match *metric {
+                "snr" => "SNR",
+                "rssi" => "RSSI",
+                "hops" => "Hops",
+                "battery" => "Battery",
+                _ => "Voltage",
+            };
+            let precision = if *metric == "voltage" { 2 } else { 1 };
+            println!(
+                "  {:<8} {:>8.p$} {:>8.p$} {:>8.p$} {:>8.p$}  {}",
+                label,
+                m.min,
+                m.avg,
+                m.max,
+                m.latest,
+                sparkline(&m.recent, false),
+                p = precision
+            );
+        }
+    }
+
+    if !gaps.is_empty() {
+        println!("\n  {} over {}:", "Gaps".bold(), span(gap_secs));
+        for gap in &gaps {
+            println!(
+                "    {} → {}  ({})",
+                format_time(gap.from),
+                format_time(gap.to),
+                span(gap.seconds)
+            );
+        }
+    }
+
+    if !reboots.is_empty() {
+        println!("\n  {}:", "Reboots".bold());
+        for reboot in &reboots {
+            println!(
+                "    {}  (up {} before)",
+                format_time(reboot.time),
+                span(reboot.uptime_before as i64)
+            );
+        }
+    }
+
+    if options.last > 0 {
+        let skip = node_sightings.len().saturating_sub(options.last);
+        println!(
+            "\n  {:<19}  {:<9} {:>6} {:>6} {:>4} {:>7} {:>7}  {}",
+            "Time".bold(),
+            "Source".bold(),
+            "SNR".bold(),
+            "RSSI".bold(),
+            "Hops".bold(),
+            "Battery".bold(),
+            "Voltage".bold(),
+            "Position".bold()
+        );
+        for s in &node_sightings[skip..] {
+            let source = match s.source {
+                SightingSource::Handshake => "handshake",
+                SightingSource::Packet if s.via_mqtt => "mqtt",
+                SightingSource::Packet => "packet",
+            };
+            let opt = |v: Option<String>| v.unwrap_or_else(|| "-".into());
+            println!(
+                "  {:<19}  {:<9} {:>6} {:>6} {:>4} {:>7} {:>7}  {}",
+                format_time(s.time),
+                source,
+                opt(s.snr.map(|v| format!("{:.1}", v))),
+                opt(s.rssi.map(|v| v.to_string())),
+                opt(s.hops.map(|v| v.to_string())),
+                opt(s.battery.map(|v| format!("{}%", v))),
+                opt(s.voltage.map(|v| format!("{:.2}V", v))),
+                opt(s
+                    .lat_lon()
+                    .map(|(lat, lon)| format!("{:.5}, {:.5}", lat, lon)))
+            );
+        }
+    }
+    Ok(())
+}
+
+/// One line per node with recorded sightings, most recently seen first.
+fn print_overview(sightings: &[NodeSighting], json: bool) -> anyhow::Result<()> {
+    let mut by_node: BTreeMap<&str, Vec<&NodeSighting>> = BTreeMap::new();
+    for sighting in sightings {
+        by_node.entry(&sighting.node).or_default().push(sighting);
+    }
+    let mut rows: Vec<NodeOverviewJson> = by_node
+        .into_iter()
+        .map(|(id, list)| NodeOverviewJson {
+            id,
+            name: list.iter().rev().find_map(|s| s.name.as_deref()),
+            first_seen: list[0].time,
+            last_seen: list[list.len() - 1].time,
+            count: list.len(),
+        })
+        .collect();
+    rows.sort_by_key(|r| std::cmp::Reverse(r.last_seen));
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("{} No node sightings recorded.", "->".cyan());
+        return Ok(());
+    }
+    println!(
+        "{:<12} {:<20} {:>9}  {:<19}  {:<19}",
+        "ID".bold(),
+        "Name".bold(),
+        "Sightings".bold(),
+        "First Seen".bold(),
+        "Last Seen".bold()
+    );
+    for row in &rows {
+        println!(
+            "{:<12} {:<20} {:>9}  {:<19}  {:<19}",
+            row.id,
+            row.name.unwrap_or("Unknown"),
+            row.count,
+            format_time(row.first_seen),
+            format_time(row.last_seen)
+        );
+    }
+    println!("\n{} {} nodes", "->".cyan(), rows.len());
+    Ok(())
+}
+
+/// Min, average, max and latest of a series, rounded to two decimals as the
+/// values come from `f32` fields.
+fn summarize(values: impl Iterator<Item = f64>) -> Option<MetricSummary> {
+    let values: Vec<f64> = values.collect();
+    let round = |v: f64| (v * 100.0).round() / 100.0;
+    let latest = *values.last()?;
+    let recent = values[values.len().saturating_sub(SPARKLINE_POINTS)..].to_vec();
+    Some(MetricSummary {
+        min: round(values.iter().copied().fold(f64::INFINITY, f64::min)),
+        avg: round(values.iter().sum::<f64>() / values.len() as f64),
+        max: round(values.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        latest: round(latest),
+        samples: values.len(),
+        recent,
+    })
+}
+
+/// Periods longer than `gap_secs` without any sighting.
+fn find_gaps(sightings: &[&NodeSighting], gap_secs: i64) -> Vec<Gap> {
+    sightings
+        .windows(2)
+        .filter(|w| w[1].time - w[0].time > gap_secs)
+        .map(|w| Gap {
+            from: w[0].time,
+            to: w[1].time,
+            seconds: w[1].time - w[0].time,
+        })
+        .collect()
+}
+
+/// Points where the reported uptime went down, i.e. the node restarted.
+fn find_reboots(sightings: &[&NodeSighting]) -> Vec<Reboot> {
+    let with_uptime: Vec<(i64, u32)> = sightings
+        .iter()
+        .filter_map(|s| Some((s.time, s.uptime?)))
+        .collect();
+    with_uptime
+        .windows(2)
+        .filter(|w| w[1].1 < w[0].1)
+        .map(|w| Reboot {
+            time: w[1].0 - w[1].1 as i64,
+            uptime_before: w[0].1,
+        })
+        .collect()
+}
+
+/// How many distinct fixes were recorded and how far the node moved from
+/// the first one.
+fn position_note(sightings: &[&NodeSighting], units: Units) -> Option<String> {
+    let fixes: Vec<(f64, f64)> = sightings.iter().filter_map(|s| s.lat_lon()).collect();
+    let first = *fixes.first()?;
+    let distinct = fixes
+        .iter()
+        .map(|(lat, lon)| ((lat * 1e5).round() as i64, (lon * 1e5).round() as i64))
+        .collect::<BTreeSet<_>>()
+        .len();
+    let furthest = fixes
+        .iter()
+        .map(|&p| distance_m(first, p))
+        .fold(0.0, f64::max);
+    if distinct == 1 {
+        return Some(format!("{:.5}, {:.5}", first.0, first.1));
+    }
+    Some(format!(
+        "{} distinct, up to {} from the first",
+        distinct,
+        units.format_distance(furthest)
+    ))
+}
+
+fn span(secs: i64) -> String {
+    format_uptime(secs.clamp(0, u32::MAX as i64) as u32, false)
+}
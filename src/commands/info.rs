@@ -4,6 +4,7 @@ use meshtastic::protobufs::{self, channel, HardwareModel};
 use serde::Serialize;
 
 use super::{Command, CommandContext};
+use crate::node_db::NodeDb;
 
 pub struct InfoCommand {
     pub json: bool,
@@ -56,7 +57,14 @@ struct ChannelInfoJson {
 #[async_trait]
 impl Command for InfoCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
-        let node_db = &ctx.node_db;
+        self.show(&ctx.node_db)
+    }
+}
+
+impl InfoCommand {
+    /// Prints the local node of `node_db`, the device's or one loaded from a
+    /// snapshot file.
+    pub fn show(&self, node_db: &NodeDb) -> anyhow::Result<()> {
         let my_info = node_db.my_node_info();
         let local_node = node_db.local_node();
         let metadata = node_db.metadata();
@@ -207,13 +215,13 @@ fn print_field(label: &str, value: &str) {
     println!("  {:<16} {}", format!("{}:", label).dimmed(), value);
 }
 
-fn format_hardware(hw_model: i32) -> String {
+pub(super) fn format_hardware(hw_model: i32) -> String {
     HardwareModel::try_from(hw_model)
         .map(|m| m.as_str_name().replace('_', " "))
         .unwrap_or_else(|_| format!("Unknown ({})", hw_model))
 }
 
-fn format_role(role: i32) -> String {
+pub(super) fn format_role(role: i32) -> String {
     use meshtastic::protobufs::config::device_config::Role;
     Role::try_from(role)
         .map(|r| r.as_str_name().replace('_', " "))
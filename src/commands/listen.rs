@@ -1,60 +1,91 @@
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
+use crossterm::{cursor, execute, terminal};
 use meshtastic::protobufs::from_radio::PayloadVariant;
-use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
-use meshtastic::protobufs::telemetry::Variant as TelemetryVariant;
-use meshtastic::protobufs::{MeshPacket, PortNum, Position, Routing, Telemetry, User};
-use meshtastic::Message;
+use meshtastic::protobufs::mesh_packet::{PayloadVariant as MeshPayload, Priority};
+use meshtastic::protobufs::{MeshPacket, PortNum};
 use serde::Serialize;
 
+use super::channel_hash::{known_channels, KnownChannel};
+use super::decode::format_payload;
+use super::dedup::{relay_label, PacketHistory, Sighting};
+use super::filter::PacketFilter;
+use super::history::SightingRecorder;
+use super::lora::packet_airtime_ms;
+use super::messages::{format_reply, recent_texts, MessageRecorder};
+use super::packet_log::{build_packet_json, LogOptions, PacketLogWriter};
+use super::parts::{PartBuffer, Reassembly};
+use super::stats::MeshStats;
 use super::{Command, CommandContext};
 use crate::node_db::NodeDb;
 
 #[derive(Serialize)]
-struct PacketJson {
-    from: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    from_name: Option<String>,
-    to: String,
-    port: String,
-    channel: u32,
-    rx_time: u32,
+struct ChannelStatsJson {
+    total_encrypted: u64,
+    channels: Vec<ChannelHashJson>,
+    unknown_channels: usize,
+    unknown_packets: u64,
+}
+
+#[derive(Serialize)]
+struct ChannelHashJson {
+    hash: u8,
+    packets: u64,
+    airtime_ms: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
-    payload: Option<String>,
+    channel_index: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    payload_size: Option<usize>,
+    channel_name: Option<String>,
 }
 
 const BROADCAST_ADDR: u32 = 0xFFFFFFFF;
+/// How many recent text messages are kept for quoting replies and reactions.
+const RECENT_TEXTS: usize = 256;
+
+#[derive(Default)]
+struct HashCount {
+    packets: u64,
+    airtime_ms: f64,
+}
 
 pub struct ListenCommand {
-    pub log_path: Option<PathBuf>,
+    pub log: Option<LogOptions>,
+    pub stats_channels: bool,
+    pub stats: bool,
+    pub stats_interval_secs: u64,
+    pub filter: PacketFilter,
+    pub meta: bool,
+    pub show_duplicates: bool,
+    pub reassemble: bool,
     pub json: bool,
 }
 
 #[async_trait]
 impl Command for ListenCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
-        let mut log_writer = match &self.log_path {
-            Some(path) => {
-                let file = std::fs::File::create(path)?;
+        let filter = self.filter.resolve(&ctx.node_db)?;
+
+        let mut log_writer = match &self.log {
+            Some(options) => {
+                let writer = PacketLogWriter::open(options.clone())?;
                 if !self.json {
                     println!(
                         "{} Logging packets to {}",
                         "->".cyan(),
-                        path.display().to_string().bold()
+                        writer.path().display().to_string().bold()
                     );
                 }
-                Some(std::io::BufWriter::new(file))
+                Some(writer)
             }
             None => None,
         };
 
-        if !self.json {
+        if !self.json && !self.stats {
             println!(
                 "{} Listening for packets... Press {} to stop.\n",
                 "→".cyan(),
@@ -62,51 +93,387 @@ impl Command for ListenCommand {
             );
         }
 
-        while let Some(packet) = ctx.packet_receiver.recv().await {
+        let channels = known_channels(&ctx.node_db);
+        let lora = ctx.node_db.local_config().lora.clone().unwrap_or_default();
+        let mut hash_counts: BTreeMap<u8, HashCount> = BTreeMap::new();
+        let mut stats = MeshStats::new(lora.clone());
+        let mut history = PacketHistory::new();
+        let mut recorder = MessageRecorder::new();
+        let mut sightings = SightingRecorder::new();
+        let mut recent = RecentTexts::from_store();
+        let mut notes = DuplicateNotes::default();
+        let mut parts = PartBuffer::new();
+        let mut refresh =
+            tokio::time::interval(Duration::from_secs(self.stats_interval_secs.max(1)));
+
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        let mut disconnected = false;
+        loop {
+            let packet = tokio::select! {
+                packet = ctx.packet_receiver.recv() => packet,
+                _ = refresh.tick(), if self.stats => {
+                    if !self.json {
+                        stats.render(&ctx.node_db)?;
+                    }
+                    continue;
+                }
+                _ = &mut ctrl_c => break,
+            };
+            let Some(packet) = packet else {
+                disconnected = true;
+                break;
+            };
             let Some(PayloadVariant::Packet(mesh_packet)) = packet.payload_variant else {
                 continue;
             };
+            // Text messages reach the inbox whatever the display filters
+            recorder.record(&mesh_packet, &ctx.node_db);
+            sightings.record(&mesh_packet, &ctx.node_db);
+            recent.remember(&mesh_packet);
+            if !filter.matches(&mesh_packet) {
+                continue;
+            }
 
-            if self.json {
-                print_packet_json(&mesh_packet, &ctx.node_db);
-            } else {
-                print_packet(&mesh_packet, &ctx.node_db);
+            if let Some(MeshPayload::Encrypted(ref bytes)) = mesh_packet.payload_variant {
+                let entry = hash_counts.entry(mesh_packet.channel as u8).or_default();
+                entry.packets += 1;
+                entry.airtime_ms += packet_airtime_ms(&lora, bytes.len());
             }
 
+            let sighting = history.record(&mesh_packet).cloned();
+            let duplicate = sighting.is_some();
+
+            // Every copy is logged, flagged, so relay and path data survive;
+            // only the live display collapses duplicates
             if let Some(ref mut writer) = log_writer {
-                write_packet_log(&mesh_packet, &ctx.node_db, writer);
+                if let Err(e) = writer.write(&mesh_packet, &ctx.node_db, &channels, duplicate) {
+                    eprintln!("{} Failed to write packet log: {}", "!".yellow(), e);
+                }
+            }
+
+            if self.stats {
+                stats.record(&mesh_packet);
+            } else if let Some(sighting) = sighting.filter(|_| !self.show_duplicates) {
+                if !self.json {
+                    notes.print(&mesh_packet, &sighting, &ctx.node_db)?;
+                }
+            } else if duplicate {
+                self.show(
+                    &mesh_packet,
+                    &ctx.node_db,
+                    &channels,
+                    &recent,
+                    &mut notes,
+                    true,
+                );
+            } else if self.reassemble {
+                let reassembly = parts.push(&mesh_packet);
+                // Parts that never completed are shown as they arrived
+                for stale in parts.expire(false) {
+                    self.show(&stale, &ctx.node_db, &channels, &recent, &mut notes, false);
+                }
+                match reassembly {
+                    Reassembly::Single => self.show(
+                        &mesh_packet,
+                        &ctx.node_db,
+                        &channels,
+                        &recent,
+                        &mut notes,
+                        false,
+                    ),
+                    Reassembly::Held => {}
+                    Reassembly::Complete(joined) => {
+                        self.show(&joined, &ctx.node_db, &channels, &recent, &mut notes, false)
+                    }
+                }
+            } else {
+                self.show(
+                    &mesh_packet,
+                    &ctx.node_db,
+                    &channels,
+                    &recent,
+                    &mut notes,
+                    false,
+                );
             }
         }
 
-        if !self.json {
+        for stale in parts.expire(true) {
+            self.show(&stale, &ctx.node_db, &channels, &recent, &mut notes, false);
+        }
+
+        if self.stats {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&stats.report(&ctx.node_db))?
+            );
+        }
+
+        if self.stats_channels {
+            print_channel_stats(&hash_counts, &channels, self.json)?;
+        }
+
+        if disconnected && !self.json {
             println!("\nDisconnected from device.");
         }
         Ok(())
     }
 }
 
-fn print_packet(packet: &MeshPacket, node_db: &NodeDb) {
+/// Text of recently heard messages by packet ID, so replies and reactions can
+/// quote the message they refer to.
+#[derive(Default)]
+pub(super) struct RecentTexts {
+    texts: HashMap<u32, String>,
+    order: VecDeque<u32>,
+}
+
+impl RecentTexts {
+    /// Starts from the latest messages in the message store, which also holds
+    /// the ones we sent.
+    pub(super) fn from_store() -> Self {
+        let mut recent = Self::default();
+        for (id, text) in recent_texts(RECENT_TEXTS) {
+            recent.insert(id, text);
+        }
+        recent
+    }
+
+    pub(super) fn remember(&mut self, packet: &MeshPacket) {
+        if let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant {
+            if data.portnum == PortNum::TextMessageApp as i32 && packet.id != 0 {
+                self.insert(
+                    packet.id,
+                    String::from_utf8_lossy(&data.payload).into_owned(),
+                );
+            }
+        }
+    }
+
+    fn insert(&mut self, id: u32, text: String) {
+        if self.texts.insert(id, text).is_none() {
+            self.order.push_back(id);
+            if self.order.len() > RECENT_TEXTS {
+                if let Some(old) = self.order.pop_front() {
+                    self.texts.remove(&old);
+                }
+            }
+        }
+    }
+
+    fn get(&self, id: u32) -> Option<&str> {
+        self.texts.get(&id).map(|s| s.as_str())
+    }
+}
+
+/// Keeps a single "heard N times" line under a packet up to date as further
+/// copies arrive through relays.
+#[derive(Default)]
+struct DuplicateNotes {
+    /// `(from, id)` of the packet whose line was printed last.
+    last: Option<(u32, u32)>,
+    /// Whether the last line printed is a note for `last`.
+    noted: bool,
+    /// Whether that note names its packet because it is not directly below it.
+    labelled: bool,
+}
+
+impl DuplicateNotes {
+    fn packet_printed(&mut self, packet: &MeshPacket) {
+        self.last = Some((packet.from, packet.id));
+        self.noted = false;
+    }
+
+    fn print(
+        &mut self,
+        packet: &MeshPacket,
+        sighting: &Sighting,
+        node_db: &NodeDb,
+    ) -> anyhow::Result<()> {
+        let key = (packet.from, packet.id);
+        let mut stdout = std::io::stdout();
+        if self.last != Some(key) {
+            self.labelled = true;
+        } else if self.noted && stdout.is_terminal() {
+            execute!(
+                stdout,
+                cursor::MoveToPreviousLine(1),
+                terminal::Clear(terminal::ClearType::CurrentLine)
+            )?;
+        } else if !self.noted {
+            self.labelled = false;
+        }
+
+        let label = if self.labelled {
+            format!("!{:08x} #{:08x} ", packet.from, packet.id)
+        } else {
+            String::new()
+        };
+        let relays = if sighting.relays.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<String> = sighting
+                .relays
+                .iter()
+                .map(|&r| relay_label(r, node_db))
+                .collect();
+            format!(" via relays {}", names.join(", "))
+        };
+        println!(
+            "  {} {}",
+            "↳".dimmed(),
+            format!("{}heard {} times{}", label, sighting.count, relays).dimmed()
+        );
+        stdout.flush()?;
+
+        self.last = Some(key);
+        self.noted = true;
+        Ok(())
+    }
+}
+
+impl ListenCommand {
+    fn show(
+        &self,
+        packet: &MeshPacket,
+        node_db: &NodeDb,
+        channels: &[KnownChannel],
+        recent: &RecentTexts,
+        notes: &mut DuplicateNotes,
+        duplicate: bool,
+    ) {
+        if self.json {
+            print_packet_json(packet, node_db, channels, duplicate);
+        } else {
+            print_packet(packet, node_db, channels, recent, self.meta);
+            notes.packet_printed(packet);
+        }
+    }
+}
+
+fn print_channel_stats(
+    hash_counts: &BTreeMap<u8, HashCount>,
+    channels: &[KnownChannel],
+    json: bool,
+) -> anyhow::Result<()> {
+    let lookup = |hash: u8| channels.iter().find(|c| c.hash == hash);
+
+    let total: u64 = hash_counts.values().map(|c| c.packets).sum();
+    let unknown: Vec<(&u8, &HashCount)> = hash_counts
+        .iter()
+        .filter(|(hash, _)| lookup(**hash).is_none())
+        .collect();
+    let unknown_packets: u64 = unknown.iter().map(|(_, count)| count.packets).sum();
+
+    if json {
+        let report = ChannelStatsJson {
+            total_encrypted: total,
+            channels: hash_counts
+                .iter()
+                .map(|(&hash, count)| {
+                    let known = lookup(hash);
+                    ChannelHashJson {
+                        hash,
+                        packets: count.packets,
+                        airtime_ms: count.airtime_ms,
+                        channel_index: known.map(|c| c.index),
+                        channel_name: known.map(|c| c.name.clone()),
+                    }
+                })
+                .collect(),
+            unknown_channels: unknown.len(),
+            unknown_packets,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} ({} encrypted packets)",
+        "Channel hash report".bold().underline(),
+        total
+    );
+    if hash_counts.is_empty() {
+        println!("  {}", "(no encrypted packets received)".dimmed());
+        return Ok(());
+    }
+
+    println!(
+        "  {:<8} {:<10} {:<12} {}",
+        "Hash".bold(),
+        "Packets".bold(),
+        "Airtime".bold(),
+        "Channel".bold()
+    );
+    for (&hash, count) in hash_counts {
+        let channel = match lookup(hash) {
+            Some(c) => format!("{} [{}]", c.name, c.index).green().to_string(),
+            None => "(unknown)".dimmed().to_string(),
+        };
+        println!(
+            "  {:<8} {:<10} {:<12} {}",
+            format!("0x{:02x}", hash),
+            count.packets,
+            format!("{:.1}s", count.airtime_ms / 1000.0),
+            channel
+        );
+    }
+    println!(
+        "\n  Unknown channels: {} ({} packets)",
+        unknown.len(),
+        unknown_packets
+    );
+    Ok(())
+}
+
+pub(super) fn print_packet(
+    packet: &MeshPacket,
+    node_db: &NodeDb,
+    channels: &[KnownChannel],
+    recent: &RecentTexts,
+    meta: bool,
+) {
     let timestamp = format_timestamp(packet.rx_time);
     let from = format_node(packet.from, node_db);
     let to = format_destination(packet.to, node_db);
     let channel = packet.channel;
+    let meta_info = if meta {
+        format!(" [{}]", format_meta(packet))
+    } else {
+        String::new()
+    };
 
     let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant else {
+        let hash_label = match channels.iter().find(|c| c.hash as u32 == channel) {
+            Some(c) => format!("hash 0x{:02x}: {}", channel, c.name),
+            None => format!("hash 0x{:02x}: unknown channel", channel),
+        };
         println!(
-            "{} {} {} {} | {}",
+            "{} {} {} {}{} | {} ({})",
             timestamp.dimmed(),
             from,
             "→".dimmed(),
             to,
-            "Encrypted packet".dimmed()
+            meta_info.dimmed(),
+            "Encrypted packet".dimmed(),
+            hash_label.dimmed()
         );
         return;
     };
 
     let port = PortNum::try_from(data.portnum).unwrap_or(PortNum::UnknownApp);
-    let content = format_payload(&port, &data.payload);
+    let mut content = format_payload(&port, &data.payload);
+    let mut port_label = format_port_label(&port);
+    if port == PortNum::TextMessageApp && data.reply_id != 0 {
+        let emoji = data.emoji != 0;
+        content = format_reply(&content, data.reply_id, emoji, recent.get(data.reply_id));
+        if emoji {
+            port_label = "Reaction:".yellow().to_string();
+        }
+    }
 
-    let port_label = format_port_label(&port);
     let channel_info = if channel > 0 {
         format!(" ch:{}", channel)
     } else {
@@ -114,17 +481,58 @@ fn print_packet(packet: &MeshPacket, node_db: &NodeDb) {
     };
 
     println!(
-        "{} {} {} {}{} | {} {}",
+        "{} {} {} {}{}{} | {} {}",
         timestamp.dimmed(),
         from,
         "→".dimmed(),
         to,
         channel_info.dimmed(),
+        meta_info.dimmed(),
         port_label,
         content
     );
 }
 
+/// Hops the packet travelled, computed as in `reply`.
+pub(super) fn hops_away(packet: &MeshPacket) -> u32 {
+    packet.hop_start.saturating_sub(packet.hop_limit)
+}
+
+pub(super) fn priority_name(priority: i32) -> String {
+    Priority::try_from(priority)
+        .map(|p| p.as_str_name().to_string())
+        .unwrap_or_else(|_| priority.to_string())
+}
+
+/// Compact link metadata for the `--meta` column.
+fn format_meta(packet: &MeshPacket) -> String {
+    let mut parts = vec![
+        format!("id:{:08x}", packet.id),
+        format!("snr:{:.1}", packet.rx_snr),
+        format!("rssi:{}", packet.rx_rssi),
+        format!("hops:{}/{}", hops_away(packet), packet.hop_start),
+    ];
+    if packet.relay_node != 0 {
+        parts.push(format!("relay:{:02x}", packet.relay_node));
+    }
+    if packet.next_hop != 0 {
+        parts.push(format!("next:{:02x}", packet.next_hop));
+    }
+    if packet.priority != Priority::Unset as i32 && packet.priority != Priority::Default as i32 {
+        parts.push(format!("prio:{}", priority_name(packet.priority)));
+    }
+    if packet.want_ack {
+        parts.push("want_ack".to_string());
+    }
+    if packet.via_mqtt {
+        parts.push("mqtt".to_string());
+    }
+    if packet.pki_encrypted {
+        parts.push("pki".to_string());
+    }
+    parts.join(" ")
+}
+
 fn format_timestamp(rx_time: u32) -> String {
     if rx_time == 0 {
         return "          ".to_string();
@@ -167,242 +575,23 @@ fn format_port_label(port: &PortNum) -> String {
         PortNum::RangeTestApp => "RangeTest:",
         PortNum::StoreForwardApp => "StoreForward:",
         PortNum::AdminApp => "Admin:",
+        PortNum::PaxcounterApp => "Paxcounter:",
+        PortNum::MapReportApp => "MapReport:",
+        PortNum::RemoteHardwareApp => "RemoteHW:",
         _ => "Unknown:",
     };
     label.yellow().to_string()
 }
 
-fn format_payload(port: &PortNum, payload: &[u8]) -> String {
-    match port {
-        PortNum::TextMessageApp => format_text(payload),
-        PortNum::PositionApp => format_position(payload),
-        PortNum::NodeinfoApp => format_nodeinfo(payload),
-        PortNum::RoutingApp => format_routing(payload),
-        PortNum::TelemetryApp => format_telemetry(payload),
-        _ => format!("{} bytes", payload.len()),
-    }
-}
-
-fn format_text(payload: &[u8]) -> String {
-    String::from_utf8(payload.to_vec())
-        .unwrap_or_else(|_| format!("<invalid UTF-8: {} bytes>", payload.len()))
-}
-
-fn format_position(payload: &[u8]) -> String {
-    let Ok(pos) = Position::decode(payload) else {
-        return format!("<decode error: {} bytes>", payload.len());
-    };
-
-    let lat = pos.latitude_i.unwrap_or(0) as f64 * 1e-7;
-    let lon = pos.longitude_i.unwrap_or(0) as f64 * 1e-7;
-    let alt = pos.altitude.unwrap_or(0);
-    let sats = pos.sats_in_view;
-
-    let mut parts = vec![format!("{:.5}, {:.5}", lat, lon)];
-    if alt != 0 {
-        parts.push(format!("{}m", alt));
-    }
-    if sats > 0 {
-        parts.push(format!("{} sats", sats));
-    }
-    parts.join(", ")
-}
-
-fn format_nodeinfo(payload: &[u8]) -> String {
-    let Ok(user) = User::decode(payload) else {
-        return format!("<decode error: {} bytes>", payload.len());
-    };
-    format!("{} ({})", user.long_name, user.short_name)
-}
-
-fn format_routing(payload: &[u8]) -> String {
-    let Ok(routing) = Routing::decode(payload) else {
-        return format!("<decode error: {} bytes>", payload.len());
-    };
-
-    match routing.variant {
-        Some(meshtastic::protobufs::routing::Variant::ErrorReason(code)) => {
-            let reason = match code {
-                0 => "ACK",
-                1 => "NO_ROUTE",
-                2 => "GOT_NAK",
-                3 => "TIMEOUT",
-                4 => "NO_INTERFACE",
-                5 => "MAX_RETRANSMIT",
-                6 => "NO_CHANNEL",
-                7 => "TOO_LARGE",
-                8 => "NO_RESPONSE",
-                9 => "DUTY_CYCLE_LIMIT",
-                _ => "UNKNOWN",
-            };
-            reason.to_string()
-        }
-        Some(meshtastic::protobufs::routing::Variant::RouteRequest(_)) => {
-            "Route request".to_string()
-        }
-        Some(meshtastic::protobufs::routing::Variant::RouteReply(_)) => "Route reply".to_string(),
-        None => "Unknown routing".to_string(),
-    }
-}
-
-fn print_packet_json(packet: &MeshPacket, node_db: &NodeDb) {
-    let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant else {
-        let pkt = PacketJson {
-            from: format!("!{:08x}", packet.from),
-            from_name: node_db.node_name(packet.from).map(|s| s.to_string()),
-            to: if packet.to == BROADCAST_ADDR {
-                "broadcast".to_string()
-            } else {
-                format!("!{:08x}", packet.to)
-            },
-            port: "encrypted".to_string(),
-            channel: packet.channel,
-            rx_time: packet.rx_time,
-            payload: None,
-            payload_size: None,
-        };
-        if let Ok(json) = serde_json::to_string(&pkt) {
-            println!("{}", json);
-        }
-        return;
-    };
-
-    let port = PortNum::try_from(data.portnum).unwrap_or(PortNum::UnknownApp);
-    let payload_str = match &port {
-        PortNum::TextMessageApp => Some(format_text(&data.payload)),
-        _ => None,
-    };
-
-    let pkt = PacketJson {
-        from: format!("!{:08x}", packet.from),
-        from_name: node_db.node_name(packet.from).map(|s| s.to_string()),
-        to: if packet.to == BROADCAST_ADDR {
-            "broadcast".to_string()
-        } else {
-            format!("!{:08x}", packet.to)
-        },
-        port: format!("{:?}", port),
-        channel: packet.channel,
-        rx_time: packet.rx_time,
-        payload: payload_str,
-        payload_size: Some(data.payload.len()),
-    };
-    if let Ok(json) = serde_json::to_string(&pkt) {
-        println!("{}", json);
-    }
-}
-
-fn format_telemetry(payload: &[u8]) -> String {
-    let Ok(telemetry) = Telemetry::decode(payload) else {
-        return format!("<decode error: {} bytes>", payload.len());
-    };
-
-    match telemetry.variant {
-        Some(TelemetryVariant::DeviceMetrics(m)) => {
-            let mut parts = Vec::new();
-            if let Some(bat) = m.battery_level {
-                parts.push(format!("battery {}%", bat));
-            }
-            if let Some(v) = m.voltage {
-                parts.push(format!("{:.2}V", v));
-            }
-            if let Some(cu) = m.channel_utilization {
-                parts.push(format!("ch_util {:.1}%", cu));
-            }
-            if let Some(at) = m.air_util_tx {
-                parts.push(format!("air_tx {:.1}%", at));
-            }
-            if let Some(up) = m.uptime_seconds {
-                parts.push(format!("uptime {}s", up));
-            }
-            if parts.is_empty() {
-                "device metrics (empty)".to_string()
-            } else {
-                parts.join(", ")
-            }
-        }
-        Some(TelemetryVariant::EnvironmentMetrics(m)) => {
-            let mut parts = Vec::new();
-            if let Some(t) = m.temperature {
-                parts.push(format!("{:.1}C", t));
-            }
-            if let Some(h) = m.relative_humidity {
-                parts.push(format!("{:.1}% humidity", h));
-            }
-            if let Some(p) = m.barometric_pressure {
-                parts.push(format!("{:.1} hPa", p));
-            }
-            if parts.is_empty() {
-                "env metrics (empty)".to_string()
-            } else {
-                parts.join(", ")
-            }
-        }
-        Some(TelemetryVariant::PowerMetrics(m)) => {
-            format!(
-                "power: ch1 {:.2}V/{:.1}mA, ch2 {:.2}V/{:.1}mA",
-                m.ch1_voltage.unwrap_or(0.0),
-                m.ch1_current.unwrap_or(0.0),
-                m.ch2_voltage.unwrap_or(0.0),
-                m.ch2_current.unwrap_or(0.0)
-            )
-        }
-        Some(TelemetryVariant::AirQualityMetrics(_)) => "air quality metrics".to_string(),
-        Some(TelemetryVariant::LocalStats(_)) => "local stats".to_string(),
-        Some(TelemetryVariant::HealthMetrics(_)) => "health metrics".to_string(),
-        Some(TelemetryVariant::HostMetrics(_)) => "host metrics".to_string(),
-        None => "telemetry (no data)".to_string(),
-    }
-}
-
-fn write_packet_log(
+pub(super) fn print_packet_json(
     packet: &MeshPacket,
     node_db: &NodeDb,
-    writer: &mut std::io::BufWriter<std::fs::File>,
+    channels: &[KnownChannel],
+    duplicate: bool,
 ) {
-    let json = build_packet_json(packet, node_db);
-    if let Ok(line) = serde_json::to_string(&json) {
-        let _ = writeln!(writer, "{}", line);
-        let _ = writer.flush();
-    }
-}
-
-fn build_packet_json(packet: &MeshPacket, node_db: &NodeDb) -> PacketJson {
-    let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant else {
-        return PacketJson {
-            from: format!("!{:08x}", packet.from),
-            from_name: node_db.node_name(packet.from).map(|s| s.to_string()),
-            to: if packet.to == BROADCAST_ADDR {
-                "broadcast".to_string()
-            } else {
-                format!("!{:08x}", packet.to)
-            },
-            port: "encrypted".to_string(),
-            channel: packet.channel,
-            rx_time: packet.rx_time,
-            payload: None,
-            payload_size: None,
-        };
-    };
-
-    let port = PortNum::try_from(data.portnum).unwrap_or(PortNum::UnknownApp);
-    let payload_str = match &port {
-        PortNum::TextMessageApp => Some(format_text(&data.payload)),
-        _ => None,
-    };
-
-    PacketJson {
-        from: format!("!{:08x}", packet.from),
-        from_name: node_db.node_name(packet.from).map(|s| s.to_string()),
-        to: if packet.to == BROADCAST_ADDR {
-            "broadcast".to_string()
-        } else {
-            format!("!{:08x}", packet.to)
-        },
-        port: format!("{:?}", port),
-        channel: packet.channel,
-        rx_time: packet.rx_time,
-        payload: payload_str,
-        payload_size: Some(data.payload.len()),
+    if let Ok(json) =
+        serde_json::to_string(&build_packet_json(packet, node_db, channels, duplicate))
+    {
+        println!("{}", json);
     }
 }
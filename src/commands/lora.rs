@@ -0,0 +1,481 @@
+use anyhow::bail;
+use async_trait::async_trait;
+use colored::Colorize;
+use meshtastic::protobufs::config::lo_ra_config::{ModemPreset, RegionCode};
+use meshtastic::protobufs::config::LoRaConfig;
+use meshtastic::protobufs::ChannelSettings;
+use serde::Serialize;
+
+use super::channel_hash::effective_channel_name;
+use super::{Command, CommandContext};
+use crate::cli::LoraAction;
+
+/// Size of the unencrypted MeshPacket header that precedes every LoRa payload.
+pub const MESH_HEADER_LEN: usize = 16;
+
+/// Preamble length used by the firmware for all modem presets.
+const PREAMBLE_SYMBOLS: u32 = 16;
+
+/// Receiver noise figure assumed for SX126x/SX127x radios.
+const NOISE_FIGURE_DB: f64 = 6.0;
+
+// ── Modem parameters ──────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+pub struct LoraParams {
+    pub bandwidth_khz: f64,
+    pub spread_factor: u32,
+    /// Coding rate denominator (5-8 for 4/5 through 4/8).
+    pub coding_rate: u32,
+}
+
+impl LoraParams {
+    /// Derives the effective modem parameters from a LoRa config the same way the firmware does.
+    pub fn from_config(lora: &LoRaConfig) -> Self {
+        let wide = region_info(lora.region).is_some_and(|r| r.wide_lora);
+        if lora.use_preset {
+            let preset = ModemPreset::try_from(lora.modem_preset).unwrap_or(ModemPreset::LongFast);
+            return Self::from_preset(preset, wide);
+        }
+        Self {
+            bandwidth_khz: custom_bandwidth_khz(lora.bandwidth),
+            spread_factor: lora.spread_factor.clamp(7, 12),
+            coding_rate: lora.coding_rate.clamp(5, 8),
+        }
+    }
+
+    pub fn from_preset(preset: ModemPreset, wide: bool) -> Self {
+        let (bw, wide_bw, sf, cr) = match preset {
+            ModemPreset::ShortTurbo => (500.0, 1625.0, 7, 5),
+            ModemPreset::ShortFast => (250.0, 812.5, 7, 5),
+            ModemPreset::ShortSlow => (250.0, 812.5, 8, 5),
+            ModemPreset::MediumFast => (250.0, 812.5, 9, 5),
+            ModemPreset::MediumSlow => (250.0, 812.5, 10, 5),
+            ModemPreset::LongFast => (250.0, 812.5, 11, 5),
+            ModemPreset::LongModerate => (125.0, 406.25, 11, 8),
+            ModemPreset::LongSlow => (125.0, 406.25, 12, 8),
+            ModemPreset::VeryLongSlow => (62.5, 203.125, 12, 8),
+        };
+        Self {
+            bandwidth_khz: if wide { wide_bw } else { bw },
+            spread_factor: sf,
+            coding_rate: cr,
+        }
+    }
+
+    pub fn symbol_time_ms(&self) -> f64 {
+        (1u64 << self.spread_factor) as f64 / self.bandwidth_khz
+    }
+
+    /// Time on air in milliseconds for a LoRa frame of `payload_len` bytes
+    /// (explicit header, CRC on), per Semtech AN1200.13.
+    pub fn airtime_ms(&self, payload_len: usize) -> f64 {
+        let t_sym = self.symbol_time_ms();
+        let sf = self.spread_factor as f64;
+        let low_dr_optimize = if t_sym > 16.0 { 1.0 } else { 0.0 };
+        let cr = (self.coding_rate - 4) as f64;
+
+        let numerator = 8.0 * payload_len as f64 - 4.0 * sf + 28.0 + 16.0;
+        let denominator = 4.0 * (sf - 2.0 * low_dr_optimize);
+        let payload_symbols = 8.0 + ((numerator / denominator).ceil() * (cr + 4.0)).max(0.0);
+
+        let preamble_ms = (PREAMBLE_SYMBOLS as f64 + 4.25) * t_sym;
+        preamble_ms + payload_symbols * t_sym
+    }
+
+    pub fn data_rate_bps(&self) -> f64 {
+        let sf = self.spread_factor as f64;
+        sf * (self.bandwidth_khz * 1000.0 / (1u64 << self.spread_factor) as f64)
+            * (4.0 / self.coding_rate as f64)
+    }
+
+    pub fn sensitivity_dbm(&self) -> f64 {
+        let snr_limit = match self.spread_factor {
+            ..=7 => -7.5,
+            8 => -10.0,
+            9 => -12.5,
+            10 => -15.0,
+            11 => -17.5,
+            _ => -20.0,
+        };
+        -174.0 + 10.0 * (self.bandwidth_khz * 1000.0).log10() + NOISE_FIGURE_DB + snr_limit
+    }
+}
+
+/// Airtime of a mesh packet whose encoded (or encrypted) data portion is `data_len` bytes.
+pub fn packet_airtime_ms(lora: &LoRaConfig, data_len: usize) -> f64 {
+    LoraParams::from_config(lora).airtime_ms(MESH_HEADER_LEN + data_len)
+}
+
+fn custom_bandwidth_khz(bandwidth: u32) -> f64 {
+    match bandwidth {
+        31 => 31.25,
+        62 => 62.5,
+        200 => 203.125,
+        400 => 406.25,
+        800 => 812.5,
+        1600 => 1625.0,
+        0 => 250.0,
+        bw => bw as f64,
+    }
+}
+
+/// The `bandwidth` config value that selects `khz`; the inverse of
+/// `custom_bandwidth_khz`.
+fn custom_bandwidth_code(khz: f64) -> u32 {
+    match khz {
+        31.25 => 31,
+        62.5 => 62,
+        203.125 => 200,
+        406.25 => 400,
+        812.5 => 800,
+        bw => bw as u32,
+    }
+}
+
+// ── Regions and frequency slots ───────────────────────────────────
+
+pub struct RegionInfo {
+    pub freq_start: f64,
+    pub freq_end: f64,
+    pub duty_cycle: u32,
+    pub spacing: f64,
+    pub power_limit: i32,
+    pub wide_lora: bool,
+}
+
+pub fn region_info(region: i32) -> Option<RegionInfo> {
+    let (freq_start, freq_end, duty_cycle, power_limit, wide_lora) =
+        match RegionCode::try_from(region).ok()? {
+            RegionCode::Unset => return None,
+            RegionCode::Us => (902.0, 928.0, 100, 30, false),
+            RegionCode::Eu433 => (433.0, 434.0, 10, 12, false),
+            RegionCode::Eu868 => (869.4, 869.65, 10, 27, false),
+            RegionCode::Cn => (470.0, 510.0, 100, 19, false),
+            RegionCode::Jp => (920.5, 923.5, 100, 13, false),
+            RegionCode::Anz => (915.0, 928.0, 100, 30, false),
+            RegionCode::Anz433 => (433.05, 434.79, 100, 14, false),
+            RegionCode::Ru => (868.7, 869.2, 100, 20, false),
+            RegionCode::Kr => (920.0, 923.0, 100, 23, false),
+            RegionCode::Tw => (920.0, 925.0, 100, 27, false),
+            RegionCode::In => (865.0, 867.0, 100, 30, false),
+            RegionCode::Nz865 => (864.0, 868.0, 100, 36, false),
+            RegionCode::Th => (920.0, 925.0, 100, 16, false),
+            RegionCode::Lora24 => (2400.0, 2483.5, 100, 10, true),
+            RegionCode::Ua433 => (433.0, 434.7, 10, 10, false),
+            RegionCode::Ua868 => (868.0, 868.6, 1, 14, false),
+            RegionCode::My433 => (433.0, 435.0, 100, 20, false),
+            RegionCode::My919 => (919.0, 924.0, 100, 27, false),
+            RegionCode::Sg923 => (917.0, 925.0, 100, 20, false),
+            RegionCode::Ph433 => (433.0, 434.7, 100, 10, false),
+            RegionCode::Ph868 => (868.0, 869.4, 100, 14, false),
+            RegionCode::Ph915 => (915.0, 918.0, 100, 24, false),
+            RegionCode::Kz433 => (433.075, 434.775, 100, 10, false),
+            RegionCode::Kz863 => (863.0, 868.0, 100, 30, false),
+            RegionCode::Np865 => (865.0, 868.0, 100, 30, false),
+            RegionCode::Br902 => (902.0, 907.5, 100, 30, false),
+        };
+    Some(RegionInfo {
+        freq_start,
+        freq_end,
+        duty_cycle,
+        spacing: 0.0,
+        power_limit,
+        wide_lora,
+    })
+}
+
+/// djb2 hash of the primary channel name, used by the firmware to pick a default slot.
+pub fn channel_name_slot_hash(name: &str) -> u32 {
+    name.bytes().fold(5381u32, |h, c| {
+        h.wrapping_shl(5).wrapping_add(h).wrapping_add(c as u32)
+    })
+}
+
+pub fn num_channels(region: &RegionInfo, params: &LoraParams) -> u32 {
+    let bw_mhz = params.bandwidth_khz / 1000.0;
+    let n = ((region.freq_end - region.freq_start + region.spacing) / (region.spacing + bw_mhz))
+        .floor() as u32;
+    n.max(1)
+}
+
+/// Returns the zero-based frequency slot and its centre frequency in MHz.
+pub fn frequency_slot(
+    lora: &LoRaConfig,
+    region: &RegionInfo,
+    params: &LoraParams,
+    primary_channel_name: &str,
+) -> (u32, f64) {
+    let channels = num_channels(region, params);
+    let slot = if lora.channel_num == 0 {
+        channel_name_slot_hash(primary_channel_name) % channels
+    } else {
+        (lora.channel_num - 1) % channels
+    };
+
+    let freq = if lora.override_frequency > 0.0 {
+        lora.override_frequency as f64
+    } else {
+        let bw_mhz = params.bandwidth_khz / 1000.0;
+        region.freq_start + bw_mhz / 2.0 + slot as f64 * (bw_mhz + region.spacing)
+    };
+    (slot, freq + lora.frequency_offset as f64)
+}
+
+// ── lora calc ─────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct LoraCalcJson {
+    region: String,
+    modem: String,
+    bandwidth_khz: f64,
+    spread_factor: u32,
+    coding_rate: String,
+    payload_bytes: usize,
+    frame_bytes: usize,
+    symbol_time_ms: f64,
+    airtime_ms: f64,
+    data_rate_bps: f64,
+    sensitivity_dbm: f64,
+    tx_power_dbm: i32,
+    link_budget_db: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duty_cycle_percent: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_channels: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_slot: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_mhz: Option<f64>,
+    channel_name: String,
+}
+
+pub struct LoraCalcOverrides {
+    pub region: Option<String>,
+    pub preset: Option<i32>,
+    pub bandwidth: Option<u32>,
+    pub spread_factor: Option<u32>,
+    pub coding_rate: Option<u32>,
+    pub channel_num: Option<u32>,
+    pub channel_name: Option<String>,
+    pub tx_power: Option<i32>,
+}
+
+pub struct LoraCalcCommand {
+    pub payload: usize,
+    pub overrides: LoraCalcOverrides,
+    pub json: bool,
+}
+
+#[async_trait]
+impl Command for LoraCalcCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let base = ctx.node_db.local_config().lora.clone().unwrap_or_default();
+        let primary = ctx
+            .node_db
+            .channels()
+            .iter()
+            .find(|c| c.index == 0)
+            .and_then(|c| c.settings.clone())
+            .unwrap_or_default();
+
+        run_calc(base, &primary, self.payload, &self.overrides, self.json)
+    }
+}
+
+/// Runs `lora calc --offline`, which needs no device connection.
+pub fn handle_lora_offline(action: &LoraAction, json: bool) -> anyhow::Result<()> {
+    let LoraAction::Calc { payload, .. } = action;
+    let base = LoRaConfig {
+        use_preset: true,
+        modem_preset: ModemPreset::LongFast as i32,
+        region: RegionCode::Us as i32,
+        ..Default::default()
+    };
+    run_calc(
+        base,
+        &ChannelSettings::default(),
+        *payload,
+        &overrides_from_action(action),
+        json,
+    )
+}
+
+pub fn overrides_from_action(action: &LoraAction) -> LoraCalcOverrides {
+    let LoraAction::Calc {
+        region,
+        preset,
+        bandwidth,
+        spread_factor,
+        coding_rate,
+        channel_num,
+        channel_name,
+        tx_power,
+        ..
+    } = action;
+    LoraCalcOverrides {
+        region: region.clone(),
+        preset: preset.as_ref().map(i32::from),
+        bandwidth: *bandwidth,
+        spread_factor: *spread_factor,
+        coding_rate: *coding_rate,
+        channel_num: *channel_num,
+        channel_name: channel_name.clone(),
+        tx_power: *tx_power,
+    }
+}
+
+fn run_calc(
+    mut lora: LoRaConfig,
+    primary: &ChannelSettings,
+    payload: usize,
+    overrides: &LoraCalcOverrides,
+    json: bool,
+) -> anyhow::Result<()> {
+    if let Some(ref region) = overrides.region {
+        let normalized = region.to_uppercase().replace('-', "_");
+        let Some(code) = RegionCode::from_str_name(&normalized) else {
+            bail!(
+                "Unknown region '{}'. Use a region code such as US, EU_868, ANZ or LORA_24.",
+                region
+            );
+        };
+        lora.region = code as i32;
+    }
+    if let Some(preset) = overrides.preset {
+        lora.use_preset = true;
+        lora.modem_preset = preset;
+    }
+    if overrides.bandwidth.is_some()
+        || overrides.spread_factor.is_some()
+        || overrides.coding_rate.is_some()
+    {
+        let current = LoraParams::from_config(&lora);
+        lora.use_preset = false;
+        lora.bandwidth = overrides
+            .bandwidth
+            .unwrap_or_else(|| custom_bandwidth_code(current.bandwidth_khz));
+        lora.spread_factor = overrides.spread_factor.unwrap_or(current.spread_factor);
+        lora.coding_rate = overrides.coding_rate.unwrap_or(current.coding_rate);
+    }
+    if let Some(num) = overrides.channel_num {
+        lora.channel_num = num;
+    }
+    if let Some(power) = overrides.tx_power {
+        lora.tx_power = power;
+    }
+
+    let channel_name = overrides
+        .channel_name
+        .clone()
+        .unwrap_or_else(|| effective_channel_name(primary, Some(&lora)));
+
+    let params = LoraParams::from_config(&lora);
+    let region = region_info(lora.region);
+    let frame_bytes = MESH_HEADER_LEN + payload;
+    let airtime = params.airtime_ms(frame_bytes);
+    let sensitivity = params.sensitivity_dbm();
+
+    let tx_power = match (lora.tx_power, &region) {
+        (0, Some(r)) => r.power_limit,
+        (p, _) => p,
+    };
+    let link_budget = tx_power as f64 - sensitivity;
+
+    let slot = region
+        .as_ref()
+        .map(|r| frequency_slot(&lora, r, &params, &channel_name));
+
+    let region_name = RegionCode::try_from(lora.region)
+        .map(|r| r.as_str_name().to_string())
+        .unwrap_or_else(|_| lora.region.to_string());
+    let modem = if lora.use_preset {
+        ModemPreset::try_from(lora.modem_preset)
+            .map(|p| p.as_str_name().to_string())
+            .unwrap_or_else(|_| lora.modem_preset.to_string())
+    } else {
+        "CUSTOM".to_string()
+    };
+
+    if json {
+        let result = LoraCalcJson {
+            region: region_name,
+            modem,
+            bandwidth_khz: params.bandwidth_khz,
+            spread_factor: params.spread_factor,
+            coding_rate: format!("4/{}", params.coding_rate),
+            payload_bytes: payload,
+            frame_bytes,
+            symbol_time_ms: params.symbol_time_ms(),
+            airtime_ms: airtime,
+            data_rate_bps: params.data_rate_bps(),
+            sensitivity_dbm: sensitivity,
+            tx_power_dbm: tx_power,
+            link_budget_db: link_budget,
+            duty_cycle_percent: region.as_ref().map(|r| r.duty_cycle),
+            num_channels: region.as_ref().map(|r| num_channels(r, &params)),
+            frequency_slot: slot.map(|(s, _)| s),
+            frequency_mhz: slot.map(|(_, f)| f),
+            channel_name,
+        };
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+
+    println!("{}", "LoRa Calculation".bold().underline());
+    println!("  {:<22} {}", "region:".dimmed(), region_name);
+    println!("  {:<22} {}", "modem:".dimmed(), modem);
+    println!(
+        "  {:<22} {} kHz, SF{}, CR 4/{}",
+        "parameters:".dimmed(),
+        params.bandwidth_khz,
+        params.spread_factor,
+        params.coding_rate
+    );
+    println!();
+    println!(
+        "  {:<22} {} bytes ({} payload + {} header)",
+        "frame size:".dimmed(),
+        frame_bytes,
+        payload,
+        MESH_HEADER_LEN
+    );
+    println!(
+        "  {:<22} {:.2} ms",
+        "symbol time:".dimmed(),
+        params.symbol_time_ms()
+    );
+    println!("  {:<22} {:.1} ms", "time on air:".dimmed(), airtime);
+    println!(
+        "  {:<22} {:.0} bps",
+        "data rate:".dimmed(),
+        params.data_rate_bps()
+    );
+    println!("  {:<22} {:.1} dBm", "sensitivity:".dimmed(), sensitivity);
+    println!("  {:<22} {} dBm", "tx power:".dimmed(), tx_power);
+    println!("  {:<22} {:.1} dB", "link budget:".dimmed(), link_budget);
+
+    match (&region, slot) {
+        (Some(r), Some((slot, freq))) => {
+            println!();
+            println!("  {:<22} {}%", "duty cycle:".dimmed(), r.duty_cycle);
+            println!("  {:<22} {}", "channel name:".dimmed(), channel_name);
+            println!(
+                "  {:<22} {} of {}",
+                "frequency slot:".dimmed(),
+                slot + 1,
+                num_channels(r, &params)
+            );
+            println!("  {:<22} {:.3} MHz", "centre frequency:".dimmed(), freq);
+        }
+        _ => {
+            println!();
+            println!(
+                "  {}",
+                "(region unset — frequency slot cannot be computed)".dimmed()
+            );
+        }
+    }
+
+    Ok(())
+}
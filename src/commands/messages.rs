@@ -0,0 +1,491 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use meshtastic::packet::PacketDestination;
+use meshtastic::protobufs::from_radio::PayloadVariant;
+use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
+use meshtastic::protobufs::{MeshPacket, PortNum};
+use serde::{Deserialize, Serialize};
+
+use super::dedup::PacketHistory;
+use super::destination::{parse_dest_spec, resolve_destination};
+use super::filter::looks_like_node_id;
+use super::parsers::{parse_node_id, truncate};
+use super::parts::parse_part;
+use super::store::JsonlStore;
+use super::CommandContext;
+use crate::cli::MessagesAction;
+use crate::config_file::config_dir;
+use crate::node_db::NodeDb;
+
+const BROADCAST_ADDR: u32 = 0xFFFFFFFF;
+/// Sent and received text messages.
+const STORE: JsonlStore<StoredMessage> = JsonlStore::new("messages.jsonl", "message store");
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Received,
+    Sent,
+}
+
+/// Delivery state of a sent message.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AckStatus {
+    /// Sent without requesting an ACK, or the reply could not be decoded
+    Sent,
+    Acked,
+    Nak,
+    Timeout,
+}
+
+/// One line of the message store (`messages.jsonl` in the config directory).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    /// Unix time the message was received or sent
+    pub time: i64,
+    pub direction: Direction,
+    /// Packet ID, 0 when the library assigned it and it is unknown
+    #[serde(default)]
+    pub id: u32,
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_name: Option<String>,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_name: Option<String>,
+    pub channel: u32,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack: Option<AckStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_id: Option<u32>,
+    /// `text` is an emoji reaction to `reply_id`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub emoji: bool,
+}
+
+impl StoredMessage {
+    /// Builds a record for a text message we sent.
+    pub fn sent(
+        node_db: &NodeDb,
+        to: u32,
+        channel: u32,
+        id: u32,
+        text: &str,
+        ack: AckStatus,
+    ) -> Self {
+        let from = node_db.my_node_num();
+        Self {
+            time: Local::now().timestamp(),
+            direction: Direction::Sent,
+            id,
+            from: format!("!{:08x}", from),
+            from_name: node_db.node_name(from).map(|s| s.to_string()),
+            to: format!("!{:08x}", to),
+            to_name: node_db.node_name(to).map(|s| s.to_string()),
+            channel,
+            text: text.to_string(),
+            ack: Some(ack),
+            reply_id: None,
+            emoji: false,
+        }
+    }
+
+    fn received(packet: &MeshPacket, node_db: &NodeDb) -> Option<Self> {
+        let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant else {
+            return None;
+        };
+        if data.portnum != PortNum::TextMessageApp as i32 || packet.from == node_db.my_node_num() {
+            return None;
+        }
+        let time = if packet.rx_time != 0 {
+            packet.rx_time as i64
+        } else {
+            Local::now().timestamp()
+        };
+        Some(Self {
+            time,
+            direction: Direction::Received,
+            id: packet.id,
+            from: format!("!{:08x}", packet.from),
+            from_name: node_db.node_name(packet.from).map(|s| s.to_string()),
+            to: format!("!{:08x}", packet.to),
+            to_name: node_db.node_name(packet.to).map(|s| s.to_string()),
+            channel: packet.channel,
+            text: String::from_utf8_lossy(&data.payload).into_owned(),
+            ack: None,
+            reply_id: (data.reply_id != 0).then_some(data.reply_id),
+            emoji: data.emoji != 0,
+        })
+    }
+
+    fn sender_num(&self) -> Option<u32> {
+        parse_node_id(&self.from)
+    }
+
+    fn recipient_num(&self) -> Option<u32> {
+        parse_node_id(&self.to)
+    }
+
+    /// The other side of the conversation: the sender of a received message,
+    /// the destination of a sent one.
+    fn peer_label(&self) -> String {
+        let (id, name) = match self.direction {
+            Direction::Received => (&self.from, &self.from_name),
+            Direction::Sent => (&self.to, &self.to_name),
+        };
+        if self.direction == Direction::Sent && self.recipient_num() == Some(BROADCAST_ADDR) {
+            return "broadcast".to_string();
+        }
+        match name {
+            Some(name) => format!("{} ({})", name, id),
+            None => id.clone(),
+        }
+    }
+}
+
+/// Stores received text messages, once per `(from, id)` however many relays
+/// repeat them.
+#[derive(Default)]
+pub struct MessageRecorder {
+    history: PacketHistory,
+}
+
+impl MessageRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `packet` if it is a text message not seen before. Returns whether
+    /// a message was stored; storage errors are reported as warnings.
+    pub fn record(&mut self, packet: &MeshPacket, node_db: &NodeDb) -> bool {
+        let Some(message) = StoredMessage::received(packet, node_db) else {
+            return false;
+        };
+        if self.history.record(packet).is_some() {
+            return false;
+        }
+        store_or_warn(&message)
+    }
+}
+
+/// Stores the text messages among packets that queued up while no command was
+/// reading them, e.g. between shell commands. Returns how many were stored.
+pub fn store_pending(ctx: &mut CommandContext, recorder: &mut MessageRecorder) -> usize {
+    let mut stored = 0;
+    while let Ok(packet) = ctx.packet_receiver.try_recv() {
+        if let Some(PayloadVariant::Packet(ref mesh_packet)) = packet.payload_variant {
+            if recorder.record(mesh_packet, &ctx.node_db) {
+                stored += 1;
+            }
+        }
+    }
+    stored
+}
+
+/// Appends `message` to the store, printing a warning instead of failing the
+/// command that produced it.
+pub fn store_or_warn(message: &StoredMessage) -> bool {
+    match STORE.append(std::slice::from_ref(message)) {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("{} Failed to store message: {:#}", "!".yellow(), e);
+            false
+        }
+    }
+}
+
+/// Number of store records already shown by `messages unread`.
+fn read_marker_path() -> PathBuf {
+    config_dir().join("messages.read")
+}
+
+/// Packet IDs and texts of the last `limit` stored messages, oldest first.
+pub fn recent_texts(limit: usize) -> Vec<(u32, String)> {
+    let messages = STORE.load().unwrap_or_default();
+    let skip = messages.len().saturating_sub(limit);
+    messages
+        .into_iter()
+        .skip(skip)
+        .filter(|m| m.id != 0)
+        .map(|m| (m.id, m.text))
+        .collect()
+}
+
+fn read_marker() -> usize {
+    std::fs::read_to_string(read_marker_path())
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn save_read_marker(count: usize) -> anyhow::Result<()> {
+    let path = read_marker_path();
+    std::fs::write(&path, count.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A node DB holding the names recorded in the store, so `--with <name>`
+/// resolves like `send --to` without a device.
+fn node_db_from_messages(messages: &[StoredMessage]) -> NodeDb {
+    NodeDb::from_names(messages.iter().flat_map(|m| {
+        [(&m.from, &m.from_name), (&m.to, &m.to_name)]
+            .into_iter()
+            .filter_map(|(id, name)| Some((parse_node_id(id)?, name.clone()?)))
+    }))
+}
+
+// ── messages command ──────────────────────────────────────────────
+
+pub fn handle_messages(
+    action: &MessagesAction,
+    reassemble: bool,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut messages = STORE.load()?;
+    let stored = messages.len();
+    let marker = read_marker().min(stored);
+    let mut unread: Vec<bool> = messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| i >= marker && m.direction == Direction::Received)
+        .collect();
+    if reassemble {
+        (messages, unread) = join_parts(messages, unread);
+    }
+    let is_unread = |index: usize| unread[index];
+
+    let (selected, title): (Vec<usize>, String) = match action {
+        MessagesAction::List { limit, channel } => {
+            let indices: Vec<usize> = (0..messages.len())
+                .filter(|&i| channel.is_none_or(|ch| messages[i].channel == ch))
+                .collect();
+            (last_n(indices, *limit), "messages".to_string())
+        }
+        MessagesAction::Thread { with, limit } => {
+            let spec = if looks_like_node_id(with) {
+                parse_dest_spec(&Some(with.clone()), &None)?
+            } else {
+                parse_dest_spec(&None, &Some(with.clone()))?
+            };
+            let (dest, label) = resolve_destination(&spec, &node_db_from_messages(&messages))?;
+            let PacketDestination::Node(node) = dest else {
+                anyhow::bail!("--with needs a node ID or name");
+            };
+            let peer = node.id();
+            // Direct messages only: theirs to us, ours to them
+            let indices: Vec<usize> = (0..messages.len())
+                .filter(|&i| {
+                    let m = &messages[i];
+                    match m.direction {
+                        Direction::Received => {
+                            m.sender_num() == Some(peer)
+                                && m.recipient_num() != Some(BROADCAST_ADDR)
+                        }
+                        Direction::Sent => m.recipient_num() == Some(peer),
+                    }
+                })
+                .collect();
+            (last_n(indices, *limit), format!("messages with {}", label))
+        }
+        MessagesAction::Search { text } => {
+            let query = text.to_lowercase();
+            let indices = (0..messages.len())
+                .filter(|&i| messages[i].text.to_lowercase().contains(&query))
+                .collect();
+            (indices, format!("messages matching '{}'", text))
+        }
+        MessagesAction::Unread => {
+            let indices = (0..messages.len()).filter(|&i| is_unread(i)).collect();
+            if stored > 0 {
+                save_read_marker(stored)?;
+            }
+            (indices, "unread messages".to_string())
+        }
+    };
+
+    if json {
+        let records: Vec<&StoredMessage> = selected.iter().map(|&i| &messages[i]).collect();
+        println!("{}", serde_json::to_string_pretty(&records)?);
+        return Ok(());
+    }
+
+    if selected.is_empty() {
+        println!("{} No {}.", "->".cyan(), title);
+        return Ok(());
+    }
+    for &index in &selected {
+        print_message(&messages[index], is_unread(index), &messages);
+    }
+    let unread_count = unread.iter().filter(|&&u| u).count();
+    let unread_note = match action {
+        MessagesAction::Unread => String::new(),
+        _ if unread_count > 0 => format!(", {} unread", unread_count),
+        _ => String::new(),
+    };
+    println!(
+        "\n{} {} {}{}",
+        "->".cyan(),
+        selected.len(),
+        title,
+        unread_note
+    );
+    Ok(())
+}
+
+/// Replaces each complete set of `(k/n)` parts with one message holding the
+/// joined text. Incomplete sets are left as they are.
+fn join_parts(messages: Vec<StoredMessage>, unread: Vec<bool>) -> (Vec<StoredMessage>, Vec<bool>) {
+    let mut consumed = vec![false; messages.len()];
+    let mut joined = Vec::new();
+    let mut joined_unread = Vec::new();
+
+    for i in 0..messages.len() {
+        if consumed[i] {
+            continue;
+        }
+        let first = &messages[i];
+        let Some((1, total, body)) = parse_part(&first.text) else {
+            joined.push(first.clone());
+            joined_unread.push(unread[i]);
+            continue;
+        };
+
+        let same_stream = |m: &StoredMessage| {
+            m.direction == first.direction
+                && m.from == first.from
+                && m.to == first.to
+                && m.channel == first.channel
+        };
+        let mut members = vec![i];
+        let mut text = body.to_string();
+        for k in 2..=total {
+            let next = (i + 1..messages.len()).find(|&j| {
+                !consumed[j]
+                    && same_stream(&messages[j])
+                    && parse_part(&messages[j].text)
+                        .is_some_and(|(pk, pn, _)| pk == k && pn == total)
+            });
+            let Some(j) = next else {
+                break;
+            };
+            members.push(j);
+            text.push_str(parse_part(&messages[j].text).map_or("", |(_, _, b)| b));
+        }
+        if members.len() < total {
+            joined.push(first.clone());
+            joined_unread.push(unread[i]);
+            continue;
+        }
+
+        for &j in &members {
+            consumed[j] = true;
+        }
+        let statuses: Vec<AckStatus> = members.iter().filter_map(|&j| messages[j].ack).collect();
+        let ack = [
+            AckStatus::Nak,
+            AckStatus::Timeout,
+            AckStatus::Sent,
+            AckStatus::Acked,
+        ]
+        .into_iter()
+        .find(|status| statuses.contains(status));
+        joined.push(StoredMessage {
+            text,
+            ack,
+            ..first.clone()
+        });
+        joined_unread.push(members.iter().any(|&j| unread[j]));
+    }
+    (joined, joined_unread)
+}
+
+fn last_n(indices: Vec<usize>, limit: usize) -> Vec<usize> {
+    if limit == 0 || indices.len() <= limit {
+        indices
+    } else {
+        indices[indices.len() - limit..].to_vec()
+    }
+}
+
+fn print_message(message: &StoredMessage, unread: bool, all: &[StoredMessage]) {
+    let mark = if unread {
+        "*".yellow().bold().to_string()
+    } else {
+        " ".to_string()
+    };
+    let time = DateTime::<Utc>::from_timestamp(message.time, 0)
+        .map(|dt| {
+            DateTime::<Local>::from(dt)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default();
+    let arrow = match message.direction {
+        Direction::Received => "<-".cyan(),
+        Direction::Sent => "->".green(),
+    };
+    let scope = if message.direction == Direction::Received
+        && message.recipient_num() != Some(BROADCAST_ADDR)
+    {
+        "dm".to_string()
+    } else {
+        format!("ch{}", message.channel)
+    };
+    let id = if message.id != 0 {
+        format!("#{:08x}", message.id)
+    } else {
+        String::new()
+    };
+    let text = match message.reply_id {
+        Some(reply_id) => {
+            let quoted = all
+                .iter()
+                .find(|m| m.id == reply_id)
+                .map(|m| m.text.as_str());
+            let reply = format_reply(&message.text, reply_id, message.emoji, quoted);
+            if message.emoji {
+                format!("{} {}", "reacted".dimmed(), reply)
+            } else {
+                reply
+            }
+        }
+        None => message.text.clone(),
+    };
+    let status = match message.ack {
+        Some(AckStatus::Acked) => format!(" {}", "[acked]".green()),
+        Some(AckStatus::Nak) => format!(" {}", "[nak]".red()),
+        Some(AckStatus::Timeout) => format!(" {}", "[no ack]".yellow()),
+        Some(AckStatus::Sent) | None => String::new(),
+    };
+
+    println!(
+        "{} {} {:<9} {} {:<28} {:<4} {}{}",
+        mark,
+        time.dimmed(),
+        id.dimmed(),
+        arrow,
+        message.peer_label(),
+        scope.dimmed(),
+        text,
+        status
+    );
+}
+
+/// Renders a reply or emoji reaction with the message it refers to: a short
+/// quote when that message is known, otherwise its packet ID.
+pub(super) fn format_reply(text: &str, reply_id: u32, emoji: bool, quoted: Option<&str>) -> String {
+    let target = match quoted {
+        Some(quoted) => format!("\"{}\"", truncate(quoted, 30)),
+        None => format!("#{:08x}", reply_id),
+    };
+    if emoji {
+        format!("{} {}", text, format!("to {}", target).dimmed())
+    } else {
+        format!("{} {}", format!("↪ {}", target).dimmed(), text)
+    }
+}
@@ -1,24 +1,42 @@
 mod admin;
 mod channel;
+mod channel_hash;
 mod config;
 mod config_file_cmd;
+mod decode;
+mod dedup;
 mod destination;
 mod device;
 mod export_import;
+mod fanout;
+mod filter;
+mod geo;
 mod gpio;
+mod history;
 mod info;
 mod listen;
+mod lora;
+mod messages;
 mod mqtt_bridge;
 mod node;
+mod node_filter;
 mod nodes;
+mod packet_log;
 pub(crate) mod parsers;
+mod parts;
 mod ping;
 mod position;
 mod reply;
 mod request;
+mod route_stats;
 mod send;
 mod shell;
+mod snapshot;
+mod stats;
+mod store;
 mod support;
+mod survey;
+mod topology;
 mod traceroute;
 mod watch;
 mod waypoint;
@@ -29,15 +47,21 @@ use meshtastic::packet::PacketReceiver;
 use meshtastic::types::MeshChannel;
 
 use crate::cli::{
-    ChannelAction, Commands, ConfigAction, DeviceAction, GpioAction, MqttAction, NodeAction,
-    PositionAction, RequestAction, WaypointAction,
+    ChannelAction, Commands, ConfigAction, DeviceAction, GpioAction, LoraAction, MqttAction,
+    NodeAction, NodesAction, PositionAction, RequestAction, SnapshotAction, WaypointAction,
 };
 use crate::error::CliError;
 use crate::node_db::NodeDb;
 use crate::router::MeshRouter;
 
 pub use config_file_cmd::handle_config_file;
-pub use destination::{parse_dest_spec, resolve_destination, DestinationSpec};
+pub use destination::{parse_dest_spec, parse_dest_targets, resolve_destination, DestinationSpec};
+pub use history::{handle_node_history, record_handshake, HistoryOptions};
+pub use lora::handle_lora_offline;
+pub use messages::handle_messages;
+pub use packet_log::handle_log;
+pub use snapshot::handle_snapshot_diff;
+pub use topology::handle_topology_log;
 
 #[allow(dead_code)]
 pub struct CommandContext {
@@ -45,6 +69,23 @@ pub struct CommandContext {
     pub node_db: NodeDb,
     pub packet_receiver: PacketReceiver,
     pub router: MeshRouter,
+    /// Display units for distances and heights
+    pub units: geo::Units,
+    /// Set while a fan-out collects each command's `--json` result, to print
+    /// them together as one array
+    pub json_results: Option<Vec<serde_json::Value>>,
+}
+
+impl CommandContext {
+    /// Prints a command's `--json` result, or collects it when running as
+    /// part of a fan-out.
+    pub fn print_json<T: serde::Serialize>(&mut self, value: &T) -> anyhow::Result<()> {
+        match &mut self.json_results {
+            Some(results) => results.push(serde_json::to_value(value)?),
+            None => println!("{}", serde_json::to_string_pretty(value)?),
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -52,22 +93,141 @@ pub trait Command {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()>;
 }
 
-pub fn create_command(command: &Commands, json: bool) -> Result<Box<dyn Command + Send>, CliError> {
+/// Builds the command for a single target, or one per member wrapped in a
+/// [`fanout::FanOutCommand`] when `--to @group` expanded to several nodes.
+fn per_target(
+    targets: Vec<DestinationSpec>,
+    json: bool,
+    build: impl Fn(DestinationSpec) -> Box<dyn Command + Send + Sync>,
+) -> Box<dyn Command + Send> {
+    let mut commands: Vec<_> = targets.into_iter().map(build).collect();
+    if commands.len() == 1 {
+        return commands.remove(0);
+    }
+    Box::new(fanout::FanOutCommand { commands, json })
+}
+
+fn nodes_command(command: &Commands, json: bool) -> Result<nodes::NodesCommand, CliError> {
+    let Commands::Nodes {
+        fields,
+        origin,
+        format,
+        sort,
+        desc,
+        r#where,
+        heard_within,
+        role,
+        favorites,
+        has_position,
+        limit,
+        ..
+    } = command
+    else {
+        return Err(CliError::InvalidArgument("Not a nodes command".into()));
+    };
+    let parsed_fields = fields
+        .as_ref()
+        .map(|f| f.split(',').map(|s| s.trim().to_string()).collect());
+    let filter = node_filter::NodeFilter::parse(
+        r#where,
+        heard_within.as_deref(),
+        role.as_deref(),
+        *favorites,
+        *has_position,
+    )?;
+    Ok(nodes::NodesCommand {
+        fields: parsed_fields,
+        from: origin.point(),
+        format: format.into(),
+        filter,
+        sort: sort.as_ref().map(Into::into),
+        desc: *desc,
+        limit: *limit,
+        json,
+    })
+}
+
+/// Runs `nodes` or `info` against a `--from-snapshot` file instead of the
+/// device. Returns `false` for other commands and when no file was given.
+pub fn handle_from_snapshot(
+    command: &Commands,
+    json: bool,
+    units: geo::Units,
+) -> anyhow::Result<bool> {
     match command {
-        Commands::Nodes { fields } => {
-            let parsed_fields = fields
-                .as_ref()
-                .map(|f| f.split(',').map(|s| s.trim().to_string()).collect());
-            Ok(Box::new(nodes::NodesCommand {
-                fields: parsed_fields,
-                json,
-            }))
+        Commands::Nodes {
+            action: None,
+            from_snapshot: Some(file),
+            ..
+        } => {
+            let node_db = snapshot::load_node_db(file)?;
+            nodes_command(command, json)?.show(&node_db, units)?;
+        }
+        Commands::Info {
+            from_snapshot: Some(file),
+        } => {
+            let node_db = snapshot::load_node_db(file)?;
+            info::InfoCommand { json }.show(&node_db)?;
         }
-        Commands::Listen { log } => Ok(Box::new(listen::ListenCommand {
-            log_path: log.as_ref().map(std::path::PathBuf::from),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+pub fn create_command(command: &Commands, json: bool) -> Result<Box<dyn Command + Send>, CliError> {
+    match command {
+        Commands::Nodes { action: None, .. } => Ok(Box::new(nodes_command(command, json)?)),
+        Commands::Nodes {
+            action:
+                Some(NodesAction::Snapshot {
+                    action: SnapshotAction::Save { file },
+                }),
+            ..
+        } => Ok(Box::new(snapshot::SnapshotSaveCommand {
+            file: file.clone(),
+            json,
+        })),
+        Commands::Listen {
+            log,
+            log_format,
+            log_rotate,
+            log_keep,
+            log_gzip,
+            stats_channels,
+            stats,
+            stats_interval,
+            port,
+            filter,
+            meta,
+            show_duplicates,
+            reassemble,
+        } => Ok(Box::new(listen::ListenCommand {
+            log: match log {
+                Some(path) => Some(packet_log::LogOptions {
+                    path: std::path::PathBuf::from(path),
+                    format: log_format.into(),
+                    rotation: log_rotate
+                        .as_deref()
+                        .map(packet_log::LogRotation::parse)
+                        .transpose()?,
+                    keep: *log_keep,
+                    gzip: *log_gzip,
+                }),
+                None => None,
+            },
+            stats_channels: *stats_channels,
+            stats: *stats,
+            stats_interval_secs: *stats_interval,
+            filter: filter::PacketFilter::from_args(filter, port)?,
+            meta: *meta,
+            show_duplicates: *show_duplicates,
+            reassemble: *reassemble,
+            json,
+        })),
+        Commands::Reply { filter } => Ok(Box::new(reply::ReplyCommand {
+            filter: filter::PacketFilter::from_args(filter, &[])?,
             json,
         })),
-        Commands::Reply => Ok(Box::new(reply::ReplyCommand { json })),
         Commands::Shell => Ok(Box::new(shell::ShellCommand)),
         Commands::Support => Ok(Box::new(support::SupportCommand { json })),
         Commands::Gpio { action } => match action {
@@ -122,59 +282,155 @@ pub fn create_command(command: &Commands, json: bool) -> Result<Box<dyn Command
                 topic,
                 username,
                 password,
+                filter_port,
+                filter,
             } => Ok(Box::new(mqtt_bridge::MqttBridgeCommand {
                 broker: broker.clone(),
                 port: *port,
                 topic_prefix: topic.clone(),
                 username: username.clone(),
                 password: password.clone(),
+                filter: filter::PacketFilter::from_args(filter, filter_port)?,
                 json,
             })),
         },
-        Commands::Watch { interval } => Ok(Box::new(watch::WatchCommand {
+        Commands::Watch { interval, origin } => Ok(Box::new(watch::WatchCommand {
             interval_secs: *interval,
+            from: origin.point(),
             json,
         })),
-        Commands::Info => Ok(Box::new(info::InfoCommand { json })),
+        Commands::Info { .. } => Ok(Box::new(info::InfoCommand { json })),
         Commands::Send {
             message,
+            file,
             dest,
             to,
             channel,
             ack,
             timeout,
             private,
+            reply_to,
+            react,
+            part_delay,
         } => {
-            let destination = parse_dest_spec(dest, to)?;
+            let destinations = destination::parse_dest_specs(dest, to)?;
             let mesh_channel = MeshChannel::new(*channel)
                 .map_err(|e| CliError::InvalidArgument(format!("Invalid channel index: {}", e)))?;
+            let reply_id = reply_to
+                .as_deref()
+                .map(parsers::parse_packet_id)
+                .transpose()
+                .map_err(|e| CliError::InvalidArgument(e.to_string()))?;
+            if react.as_deref().is_some_and(|r| r.trim().is_empty()) {
+                return Err(CliError::InvalidArgument(
+                    "--react needs an emoji, e.g. --react 👍".into(),
+                ));
+            }
+
+            let message = match react {
+                Some(emoji) => emoji.clone(),
+                None => send::read_message_text(message.as_deref(), file.as_deref())?,
+            };
 
             Ok(Box::new(send::SendCommand {
-                message: message.clone(),
-                destination,
+                message,
+                destinations,
                 channel: mesh_channel,
                 wait_ack: *ack,
                 timeout_secs: *timeout,
                 private: *private,
+                reply_id,
+                reaction: react.is_some(),
+                part_delay_secs: *part_delay,
                 json,
             }))
         }
-        Commands::Ping { dest, to, timeout } => {
-            let destination = parse_dest_spec(dest, to)?;
-            Ok(Box::new(ping::PingCommand {
-                destination,
-                timeout_secs: *timeout,
+        Commands::Ping {
+            dest,
+            to,
+            count,
+            interval,
+            channel,
+            timeout,
+        } => {
+            let targets = parse_dest_targets(dest, to)?;
+            let mesh_channel = MeshChannel::new(*channel)
+                .map_err(|e| CliError::InvalidArgument(format!("Invalid channel index: {}", e)))?;
+            Ok(per_target(targets, json, |destination| {
+                Box::new(ping::PingCommand {
+                    destination,
+                    count: *count,
+                    interval_secs: *interval,
+                    channel: mesh_channel,
+                    timeout_secs: *timeout,
+                    json,
+                })
+            }))
+        }
+        Commands::Topology {
+            duration,
+            dest,
+            to,
+            delay,
+            format,
+            ..
+        } => {
+            let duration = parsers::parse_duration(duration)
+                .map_err(|e| CliError::InvalidArgument(e.to_string()))?;
+            let targets = if dest.is_empty() && to.is_empty() {
+                Vec::new()
+            } else {
+                destination::parse_dest_specs(dest, to)?
+            };
+            Ok(Box::new(topology::TopologyCommand {
+                duration,
+                targets,
+                delay_secs: *delay,
+                format: format.into(),
                 json,
             }))
         }
-        Commands::Traceroute { dest, to, timeout } => {
-            let destination = parse_dest_spec(dest, to)?;
-            Ok(Box::new(traceroute::TracerouteCommand {
-                destination,
+        Commands::Traceroute {
+            all: true,
+            heard_within,
+            delay,
+            timeout,
+            ..
+        } => {
+            let filter =
+                node_filter::NodeFilter::parse(&[], heard_within.as_deref(), None, false, false)?;
+            Ok(Box::new(survey::TracerouteSurveyCommand {
+                filter,
+                delay_secs: *delay,
                 timeout_secs: *timeout,
                 json,
             }))
         }
+        Commands::Traceroute {
+            dest,
+            to,
+            heard_within,
+            count,
+            interval,
+            timeout,
+            ..
+        } => {
+            if heard_within.is_some() {
+                return Err(CliError::InvalidArgument(
+                    "--heard-within can only be used with --all".into(),
+                ));
+            }
+            let targets = parse_dest_targets(dest, to)?;
+            Ok(per_target(targets, json, |destination| {
+                Box::new(traceroute::TracerouteCommand {
+                    destination,
+                    timeout_secs: *timeout,
+                    count: *count,
+                    interval_secs: *interval,
+                    json,
+                })
+            }))
+        }
         Commands::Node { action } => match action {
             NodeAction::SetOwner { name, short } => Ok(Box::new(node::SetOwnerCommand {
                 long_name: name.clone(),
@@ -184,6 +440,28 @@ pub fn create_command(command: &Commands, json: bool) -> Result<Box<dyn Command
                 let destination = parse_dest_spec(dest, to)?;
                 Ok(Box::new(node::RemoveNodeCommand { destination }))
             }
+            NodeAction::Prune {
+                older_than,
+                keep_favorites,
+                role,
+                dry_run,
+                yes,
+                delay,
+            } => {
+                let older_than = parsers::parse_duration(older_than)
+                    .map_err(|e| CliError::InvalidArgument(e.to_string()))?;
+                let filter =
+                    node_filter::NodeFilter::parse(&[], None, role.as_deref(), false, false)?;
+                Ok(Box::new(node::PruneNodesCommand {
+                    older_than_secs: older_than.as_secs(),
+                    keep_favorites: *keep_favorites,
+                    filter,
+                    dry_run: *dry_run,
+                    yes: *yes,
+                    delay_secs: *delay,
+                    json,
+                }))
+            }
             NodeAction::SetFavorite { dest, to } => {
                 let destination = parse_dest_spec(dest, to)?;
                 Ok(Box::new(node::SetFavoriteCommand { destination }))
@@ -233,28 +511,40 @@ pub fn create_command(command: &Commands, json: bool) -> Result<Box<dyn Command
                 timeout,
                 r#type,
             } => {
-                let destination = parse_dest_spec(dest, to)?;
-                Ok(Box::new(request::RequestTelemetryCommand {
-                    destination,
-                    timeout_secs: *timeout,
-                    telemetry_type: r#type.into(),
-                    json,
+                let targets = parse_dest_targets(dest, to)?;
+                Ok(per_target(targets, json, |destination| {
+                    Box::new(request::RequestTelemetryCommand {
+                        destination,
+                        timeout_secs: *timeout,
+                        telemetry_type: r#type.into(),
+                        json,
+                    })
                 }))
             }
-            RequestAction::Position { dest, to, timeout } => {
-                let destination = parse_dest_spec(dest, to)?;
-                Ok(Box::new(request::RequestPositionCommand {
-                    destination,
-                    timeout_secs: *timeout,
-                    json,
+            RequestAction::Position {
+                dest,
+                to,
+                timeout,
+                origin,
+            } => {
+                let targets = parse_dest_targets(dest, to)?;
+                Ok(per_target(targets, json, |destination| {
+                    Box::new(request::RequestPositionCommand {
+                        destination,
+                        timeout_secs: *timeout,
+                        from: origin.point(),
+                        json,
+                    })
                 }))
             }
             RequestAction::Metadata { dest, to, timeout } => {
-                let destination = parse_dest_spec(dest, to)?;
-                Ok(Box::new(request::RequestMetadataCommand {
-                    destination,
-                    timeout_secs: *timeout,
-                    json,
+                let targets = parse_dest_targets(dest, to)?;
+                Ok(per_target(targets, json, |destination| {
+                    Box::new(request::RequestMetadataCommand {
+                        destination,
+                        timeout_secs: *timeout,
+                        json,
+                    })
                 }))
             }
         },
@@ -416,10 +706,28 @@ pub fn create_command(command: &Commands, json: bool) -> Result<Box<dyn Command
                 json,
             })),
         },
-        Commands::Completions { .. } | Commands::ConfigFile { .. } => {
-            Err(CliError::InvalidArgument(
-                "This command should be handled before create_command is called".into(),
-            ))
+        Commands::Lora { action } => match action {
+            LoraAction::Calc { payload, .. } => Ok(Box::new(lora::LoraCalcCommand {
+                payload: *payload,
+                overrides: lora::overrides_from_action(action),
+                json,
+            })),
+        },
+        Commands::Completions { .. }
+        | Commands::Nodes {
+            action:
+                Some(
+                    NodesAction::History { .. }
+                    | NodesAction::Snapshot {
+                        action: SnapshotAction::Diff { .. },
+                    },
+                ),
+            ..
         }
+        | Commands::ConfigFile { .. }
+        | Commands::Log { .. }
+        | Commands::Messages { .. } => Err(CliError::InvalidArgument(
+            "This command should be handled before create_command is called".into(),
+        )),
     }
 }
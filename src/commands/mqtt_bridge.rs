@@ -4,11 +4,14 @@ use async_trait::async_trait;
 use colored::Colorize;
 use meshtastic::protobufs::from_radio::PayloadVariant;
 use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
-use meshtastic::protobufs::telemetry::Variant as TelemetryVariant;
 use meshtastic::protobufs::{PortNum, Position, Telemetry};
 use meshtastic::Message;
 use rumqttc::{AsyncClient, MqttOptions, QoS};
 
+use super::decode::telemetry_to_json;
+use super::dedup::PacketHistory;
+use super::filter::PacketFilter;
+use super::history::SightingRecorder;
 use super::{Command, CommandContext};
 
 pub struct MqttBridgeCommand {
@@ -17,12 +20,16 @@ pub struct MqttBridgeCommand {
     pub topic_prefix: String,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub filter: PacketFilter,
     pub json: bool,
 }
 
 #[async_trait]
 impl Command for MqttBridgeCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let filter = self.filter.resolve(&ctx.node_db)?;
+        let mut history = PacketHistory::new();
+        let mut sightings = SightingRecorder::new();
         let client_id = format!("mttctl-{}", rand_u16());
 
         let mut mqtt_opts = MqttOptions::new(&client_id, &self.broker, self.port);
@@ -76,6 +83,14 @@ impl Command for MqttBridgeCommand {
                     let Some(PayloadVariant::Packet(mesh_pkt)) = from_radio.payload_variant else {
                         continue;
                     };
+                    sightings.record(&mesh_pkt, &ctx.node_db);
+                    if !filter.matches(&mesh_pkt) {
+                        continue;
+                    }
+                    // Publish each packet once, however many relays repeat it
+                    if history.record(&mesh_pkt).is_some() {
+                        continue;
+                    }
                     let Some(MeshPayload::Decoded(ref data)) = mesh_pkt.payload_variant else {
                         continue;
                     };
@@ -237,33 +252,6 @@ struct MqttSendMessage {
     channel: Option<u32>,
 }
 
-fn telemetry_to_json(telemetry: &Telemetry) -> serde_json::Value {
-    match &telemetry.variant {
-        Some(TelemetryVariant::DeviceMetrics(m)) => serde_json::json!({
-            "type": "device",
-            "battery_level": m.battery_level,
-            "voltage": m.voltage,
-            "channel_utilization": m.channel_utilization,
-            "air_util_tx": m.air_util_tx,
-            "uptime_seconds": m.uptime_seconds,
-        }),
-        Some(TelemetryVariant::EnvironmentMetrics(m)) => serde_json::json!({
-            "type": "environment",
-            "temperature": m.temperature,
-            "relative_humidity": m.relative_humidity,
-            "barometric_pressure": m.barometric_pressure,
-        }),
-        Some(TelemetryVariant::PowerMetrics(m)) => serde_json::json!({
-            "type": "power",
-            "ch1_voltage": m.ch1_voltage,
-            "ch1_current": m.ch1_current,
-            "ch2_voltage": m.ch2_voltage,
-            "ch2_current": m.ch2_current,
-        }),
-        _ => serde_json::json!({ "type": "unknown" }),
-    }
-}
-
 fn rand_u16() -> u16 {
     use std::time::SystemTime;
     let seed = SystemTime::now()
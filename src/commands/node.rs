@@ -1,9 +1,17 @@
+use std::io::Write;
+use std::time::Duration;
+
 use anyhow::bail;
 use async_trait::async_trait;
+use chrono::Utc;
 use colored::Colorize;
-use meshtastic::protobufs::{self, admin_message};
+use meshtastic::protobufs::{self, admin_message, NodeInfo};
+use serde::Serialize;
 
 use super::admin::send_admin_message;
+use super::node_filter::NodeFilter;
+use super::nodes::{get_field_value, FieldContext};
+use super::parsers::format_uptime;
 use super::{resolve_destination, Command, CommandContext, DestinationSpec};
 
 // ── SetOwnerCommand ───────────────────────────────────────────────
@@ -103,6 +111,196 @@ impl Command for RemoveNodeCommand {
     }
 }
 
+// ── PruneNodesCommand ─────────────────────────────────────────────
+
+pub struct PruneNodesCommand {
+    pub older_than_secs: u64,
+    pub keep_favorites: bool,
+    /// Only the `--role` condition is used
+    pub filter: NodeFilter,
+    pub dry_run: bool,
+    pub yes: bool,
+    pub delay_secs: u64,
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct PrunedNodeJson {
+    id: String,
+    name: String,
+    role: String,
+    last_heard: u32,
+}
+
+#[async_trait]
+impl Command for PruneNodesCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        if self.json && !self.dry_run && !self.yes {
+            bail!("--json needs --yes or --dry-run, as there is no confirmation prompt");
+        }
+
+        let stale = self.select(ctx);
+        let fctx = FieldContext {
+            origin: None,
+            units: ctx.units,
+        };
+        let now = Utc::now().timestamp() as u32;
+
+        if !self.json {
+            if stale.is_empty() {
+                println!(
+                    "{} No nodes older than {} to remove.",
+                    "->".cyan(),
+                    format_uptime(self.older_than_secs as u32, false)
+                );
+                return Ok(());
+            }
+            println!(
+                "{:<12} {:<20} {:<14} {}",
+                "ID".bold(),
+                "Name".bold(),
+                "Role".bold(),
+                "Last Heard".bold()
+            );
+            for node in &stale {
+                let last_heard = if node.last_heard == 0 {
+                    "never".to_string()
+                } else {
+                    format!(
+                        "{} ago",
+                        format_uptime(now.saturating_sub(node.last_heard), false)
+                    )
+                };
+                println!(
+                    "{:<12} {:<20} {:<14} {}",
+                    get_field_value(node, "id", &fctx),
+                    get_field_value(node, "name", &fctx),
+                    get_field_value(node, "role", &fctx),
+                    last_heard
+                );
+            }
+            println!();
+        }
+
+        let selected: Vec<PrunedNodeJson> = stale
+            .iter()
+            .map(|n| PrunedNodeJson {
+                id: get_field_value(n, "id", &fctx),
+                name: get_field_value(n, "name", &fctx),
+                role: get_field_value(n, "role", &fctx),
+                last_heard: n.last_heard,
+            })
+            .collect();
+        let nums: Vec<u32> = stale.iter().map(|n| n.num).collect();
+
+        if self.dry_run {
+            if self.json {
+                let result = serde_json::json!({ "dry_run": true, "selected": selected });
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                println!(
+                    "{} Dry run: {} nodes would be removed.",
+                    "->".cyan(),
+                    nums.len()
+                );
+            }
+            return Ok(());
+        }
+
+        if !self.yes && !confirm(&format!("Remove {} nodes from the NodeDB?", nums.len()))? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+
+        let my_id = ctx.node_db.my_node_num();
+        let mut removed = 0;
+        let mut failed = Vec::new();
+        for (i, &num) in nums.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(Duration::from_secs(self.delay_secs)).await;
+            }
+            let label = format_node_label(num, ctx);
+            match send_admin_message(
+                ctx,
+                my_id,
+                admin_message::PayloadVariant::RemoveByNodenum(num),
+            )
+            .await
+            {
+                Ok(()) => {
+                    removed += 1;
+                    if !self.json {
+                        println!("{} Removed {}", "ok".green(), label);
+                    }
+                }
+                Err(e) => {
+                    if !self.json {
+                        println!("{} Failed to remove {}: {}", "x".red(), label, e);
+                    }
+                    failed.push(format!("!{:08x}", num));
+                }
+            }
+        }
+
+        if self.json {
+            let result = serde_json::json!({
+                "dry_run": false,
+                "selected": selected,
+                "removed": removed,
+                "failed": failed,
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        } else {
+            println!(
+                "\n{} Removed {} of {} nodes.",
+                "->".cyan(),
+                removed,
+                nums.len()
+            );
+        }
+        if !failed.is_empty() {
+            bail!("{} nodes could not be removed", failed.len());
+        }
+        Ok(())
+    }
+}
+
+impl PruneNodesCommand {
+    /// Nodes not heard within `older_than_secs` that pass the filters, least
+    /// recently heard first. The local node and ignored nodes are never
+    /// selected: removing an ignored node would forget that it is ignored.
+    fn select<'a>(&self, ctx: &'a CommandContext) -> Vec<&'a NodeInfo> {
+        let now = Utc::now().timestamp() as u32;
+        let fctx = FieldContext {
+            origin: None,
+            units: ctx.units,
+        };
+        let my_id = ctx.node_db.my_node_num();
+        let mut stale: Vec<&NodeInfo> = ctx
+            .node_db
+            .nodes()
+            .values()
+            .filter(|n| n.num != my_id && !n.is_ignored)
+            .filter(|n| !(self.keep_favorites && n.is_favorite))
+            .filter(|n| {
+                n.last_heard == 0 || now.saturating_sub(n.last_heard) as u64 > self.older_than_secs
+            })
+            .filter(|n| self.filter.matches(n, now, &fctx))
+            .collect();
+        stale.sort_by_key(|n| (n.last_heard, n.num));
+        stale
+    }
+}
+
+/// Asks a yes/no question on the terminal; anything but "y" or "yes" is no.
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    print!("{} {} [y/N] ", "?".yellow(), prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 // ── SetFavoriteCommand ────────────────────────────────────────────
 
 pub struct SetFavoriteCommand {
@@ -270,7 +468,7 @@ impl Command for SetUnmessageableCommand {
 
 fn resolve_node_num(destination: &DestinationSpec, ctx: &CommandContext) -> anyhow::Result<u32> {
     match destination {
-        DestinationSpec::NodeId(id) => Ok(*id),
+        DestinationSpec::NodeId(id) | DestinationSpec::Alias { id, .. } => Ok(*id),
         DestinationSpec::NodeName(name) => {
             let (_, _) = resolve_destination(destination, &ctx.node_db)?;
             let matches = ctx.node_db.find_by_name(name);
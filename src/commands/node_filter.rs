@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use meshtastic::protobufs::config::device_config::Role;
+use meshtastic::protobufs::NodeInfo;
+
+use super::geo::{node_lat_lon, FEET_PER_METER, METERS_PER_MILE};
+use super::nodes::{field_number, get_field_value, FieldContext, ALL_FIELDS};
+use super::parsers::{parse_duration, parse_time};
+use crate::error::CliError;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    Contains,
+}
+
+/// The number in a `--where` value.
+#[derive(Clone, Copy)]
+enum Threshold {
+    /// In the unit `field_number` reports the field in
+    Plain(f64),
+    /// A length written with its own unit (`500m`, `2mi`), in meters
+    Meters(f64),
+}
+
+/// One `--where` expression: `<field><op><value>`.
+struct Condition {
+    field: String,
+    op: Op,
+    value: String,
+    /// `value` as a number, for fields that have one (a Unix time for `last_heard`)
+    number: Option<Threshold>,
+}
+
+impl Condition {
+    fn parse(expr: &str) -> Result<Self, CliError> {
+        // Longest operators first so "<=" is not read as "<"
+        const OPS: &[(&str, Op)] = &[
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("!=", Op::Ne),
+            ("==", Op::Eq),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+            ("=", Op::Eq),
+            ("~", Op::Contains),
+        ];
+        let found = OPS
+            .iter()
+            .filter_map(|(token, op)| expr.find(token).map(|pos| (pos, *token, *op)))
+            .min_by_key(|(pos, token, _)| (*pos, std::cmp::Reverse(token.len())));
+        let Some((pos, token, op)) = found else {
+            return Err(CliError::InvalidArgument(format!(
+                "Invalid --where '{}'. Expected <field><op><value>, e.g. battery<20, with one of < <= > >= = != ~",
+                expr
+            )));
+        };
+
+        let field = expr[..pos].trim().to_lowercase();
+        let value = expr[pos + token.len()..].trim().to_string();
+        if !ALL_FIELDS.contains(&field.as_str()) {
+            return Err(CliError::InvalidArgument(format!(
+                "Unknown field '{}' in --where. Valid fields: {}",
+                field,
+                ALL_FIELDS.join(", ")
+            )));
+        }
+
+        let number = match field.as_str() {
+            "last_heard" => parse_time(&value).ok().map(|t| Threshold::Plain(t as f64)),
+            _ if op == Op::Contains || !NUMERIC_FIELDS.contains(&field.as_str()) => {
+                value.parse().ok().map(Threshold::Plain)
+            }
+            // Allow a unit after the number: 20%, 5km, 90°
+            _ => match split_unit(&value) {
+                Some((n, unit)) if unit.is_empty() => Some(Threshold::Plain(n)),
+                Some((n, unit)) => Some(with_unit(&field, n, &unit).ok_or_else(|| {
+                    CliError::InvalidArgument(format!(
+                        "--where '{}': '{}' is not a unit for '{}' (use {})",
+                        expr,
+                        unit,
+                        field,
+                        units_for(&field)
+                    ))
+                })?),
+                None => None,
+            },
+        };
+        if matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) && number.is_none() {
+            return Err(CliError::InvalidArgument(format!(
+                "--where '{}' compares '{}' with '{}', which is not a number{}",
+                expr,
+                field,
+                value,
+                if field == "last_heard" {
+                    " or a time (2h, 2026-10-01)"
+                } else {
+                    ""
+                }
+            )));
+        }
+
+        Ok(Self {
+            field,
+            op,
+            value,
+            number,
+        })
+    }
+
+    fn matches(&self, node: &NodeInfo, fctx: &FieldContext) -> bool {
+        let actual = field_number(node, &self.field, fctx);
+        let threshold = self.number.map(|n| match n {
+            Threshold::Plain(n) => n,
+            // Into the unit of the field's value: km/mi or m/ft
+            Threshold::Meters(m) if self.field == "distance" => fctx.units.long_distance(m),
+            Threshold::Meters(m) => fctx.units.height(m),
+        });
+        if let (Some(a), Some(b)) = (actual, threshold) {
+            return match self.op {
+                Op::Lt => a < b,
+                Op::Le => a <= b,
+                Op::Gt => a > b,
+                Op::Ge => a >= b,
+                Op::Eq => a == b,
+                Op::Ne => a != b,
+                Op::Contains => contains(&get_field_value(node, &self.field, fctx), &self.value),
+            };
+        }
+
+        let text = get_field_value(node, &self.field, fctx);
+        match self.op {
+            Op::Eq => same_text(&text, &self.value),
+            Op::Ne => !same_text(&text, &self.value),
+            Op::Contains => contains(&text, &self.value),
+            // A missing number never satisfies an ordering
+            Op::Lt | Op::Le | Op::Gt | Op::Ge => false,
+        }
+    }
+}
+
+/// Fields `field_number` has a number for.
+const NUMERIC_FIELDS: &[&str] = &[
+    "battery",
+    "snr",
+    "hops",
+    "last_heard",
+    "distance",
+    "bearing",
+    "altitude_diff",
+];
+
+/// Splits "5km" into 5 and "km"; the unit is lowercased and may be empty.
+fn split_unit(value: &str) -> Option<(f64, String)> {
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(value.len());
+    let number = value[..end].parse().ok()?;
+    Some((number, value[end..].trim().to_lowercase()))
+}
+
+/// Applies a unit written after a `--where` number, if `field` takes it.
+fn with_unit(field: &str, n: f64, unit: &str) -> Option<Threshold> {
+    match (field, unit) {
+        ("battery", "%") | ("snr", "db") | ("bearing", "°" | "deg") => Some(Threshold::Plain(n)),
+        ("distance" | "altitude_diff", _) => {
+            let meters = match unit {
+                "m" => 1.0,
+                "km" => 1000.0,
+                "mi" => METERS_PER_MILE,
+                "ft" => 1.0 / FEET_PER_METER,
+                _ => return None,
+            };
+            Some(Threshold::Meters(n * meters))
+        }
+        _ => None,
+    }
+}
+
+fn units_for(field: &str) -> &'static str {
+    match field {
+        "battery" => "%",
+        "snr" => "dB",
+        "bearing" => "° or deg",
+        "distance" | "altitude_diff" => "m, km, mi or ft",
+        _ => "a plain number",
+    }
+}
+
+/// A device role by its protobuf name (`ROUTER_CLIENT`) or as displayed
+/// (`RouterClient`), case-insensitive.
+fn parse_role(value: &str) -> Result<Role, CliError> {
+    let roles = || (0..).map_while(|i| Role::try_from(i).ok());
+    Role::from_str_name(&value.to_uppercase())
+        .or_else(|| roles().find(|r| same_text(r.as_str_name(), value)))
+        .ok_or_else(|| {
+            let names: Vec<_> = roles().map(|r| r.as_str_name()).collect();
+            CliError::InvalidArgument(format!(
+                "Unknown role '{}'. Valid roles: {}",
+                value,
+                names.join(", ")
+            ))
+        })
+}
+
+/// Case-insensitive, and `ROUTER_CLIENT` equals the displayed `RouterClient`.
+fn same_text(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| s.replace('_', "").to_lowercase();
+    normalize(a) == normalize(b)
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Node selection for `nodes`: `--where` expressions plus the shortcut flags,
+/// all of which must match.
+#[derive(Default)]
+pub struct NodeFilter {
+    conditions: Vec<Condition>,
+    heard_within: Option<Duration>,
+    favorites: bool,
+    has_position: bool,
+}
+
+impl NodeFilter {
+    pub fn parse(
+        expressions: &[String],
+        heard_within: Option<&str>,
+        role: Option<&str>,
+        favorites: bool,
+        has_position: bool,
+    ) -> Result<Self, CliError> {
+        let mut conditions = expressions
+            .iter()
+            .map(|e| Condition::parse(e))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(role) = role {
+            let role = parse_role(role)?;
+            conditions.push(Condition::parse(&format!("role={}", role.as_str_name()))?);
+        }
+        let heard_within = heard_within
+            .map(parse_duration)
+            .transpose()
+            .map_err(|e| CliError::InvalidArgument(e.to_string()))?;
+
+        Ok(Self {
+            conditions,
+            heard_within,
+            favorites,
+            has_position,
+        })
+    }
+
+    pub(super) fn matches(&self, node: &NodeInfo, now: u32, fctx: &FieldContext) -> bool {
+        if self.favorites && !node.is_favorite {
+            return false;
+        }
+        if self.has_position && node_lat_lon(node).is_none() {
+            return false;
+        }
+        if let Some(window) = self.heard_within {
+            if node.last_heard == 0 || now.saturating_sub(node.last_heard) as u64 > window.as_secs()
+            {
+                return false;
+            }
+        }
+        self.conditions.iter().all(|c| c.matches(node, fctx))
+    }
+}
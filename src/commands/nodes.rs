@@ -4,10 +4,15 @@ use colored::Colorize;
 use meshtastic::protobufs::{self, HardwareModel, NodeInfo};
 use serde::Serialize;
 
+use super::geo::{node_lat_lon, Origin, Units};
+use super::node_filter::NodeFilter;
+use super::packet_log::csv_field;
 use super::{Command, CommandContext};
+use crate::cli::{NodeSortArg, NodesFormatArg};
+use crate::node_db::NodeDb;
 
 const DEFAULT_FIELDS: &[&str] = &["id", "name", "battery", "snr", "hops", "last_heard"];
-const ALL_FIELDS: &[&str] = &[
+pub(super) const ALL_FIELDS: &[&str] = &[
     "id",
     "name",
     "battery",
@@ -17,13 +22,67 @@ const ALL_FIELDS: &[&str] = &[
     "hw_model",
     "role",
     "position",
+    "distance",
+    "bearing",
+    "altitude_diff",
 ];
 
 pub struct NodesCommand {
     pub fields: Option<Vec<String>>,
+    /// Reference point for distances instead of the local node's position
+    pub from: Option<(f64, f64)>,
+    pub format: NodesFormat,
+    pub filter: NodeFilter,
+    pub sort: Option<NodeSort>,
+    pub desc: bool,
+    pub limit: Option<usize>,
     pub json: bool,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum NodesFormat {
+    Table,
+    Csv,
+    Tsv,
+    Markdown,
+    GeoJson,
+}
+
+impl From<&NodesFormatArg> for NodesFormat {
+    fn from(arg: &NodesFormatArg) -> Self {
+        match arg {
+            NodesFormatArg::Table => NodesFormat::Table,
+            NodesFormatArg::Csv => NodesFormat::Csv,
+            NodesFormatArg::Tsv => NodesFormat::Tsv,
+            NodesFormatArg::Markdown => NodesFormat::Markdown,
+            NodesFormatArg::Geojson => NodesFormat::GeoJson,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum NodeSort {
+    LastHeard,
+    Snr,
+    Battery,
+    Hops,
+    Name,
+    Distance,
+}
+
+impl From<&NodeSortArg> for NodeSort {
+    fn from(arg: &NodeSortArg) -> Self {
+        match arg {
+            NodeSortArg::LastHeard => NodeSort::LastHeard,
+            NodeSortArg::Snr => NodeSort::Snr,
+            NodeSortArg::Battery => NodeSort::Battery,
+            NodeSortArg::Hops => NodeSort::Hops,
+            NodeSortArg::Name => NodeSort::Name,
+            NodeSortArg::Distance => NodeSort::Distance,
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct NodeJson {
     id: String,
@@ -42,11 +101,25 @@ struct NodeJson {
     latitude: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     longitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_m: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bearing_deg: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    altitude_diff_m: Option<i32>,
     is_local: bool,
 }
 
-fn node_to_json(node: &NodeInfo, is_local: bool) -> NodeJson {
+/// What computed fields are relative to, and how they are shown.
+#[derive(Clone, Copy, Default)]
+pub(super) struct FieldContext {
+    pub origin: Option<Origin>,
+    pub units: Units,
+}
+
+fn node_to_json(node: &NodeInfo, is_local: bool, fctx: &FieldContext) -> NodeJson {
     let user = node.user.as_ref();
+    let relative = fctx.origin.and_then(|o| o.relative_to_node(node));
     let pos = node.position.as_ref();
 
     let (lat, lon) = pos
@@ -88,6 +161,9 @@ fn node_to_json(node: &NodeInfo, is_local: bool) -> NodeJson {
         last_heard: node.last_heard,
         latitude: lat,
         longitude: lon,
+        distance_m: relative.map(|r| (r.distance_m * 10.0).round() / 10.0),
+        bearing_deg: relative.map(|r| (r.bearing_deg * 10.0).round() / 10.0),
+        altitude_diff_m: relative.and_then(|r| r.altitude_diff_m),
         is_local,
     }
 }
@@ -95,10 +171,18 @@ fn node_to_json(node: &NodeInfo, is_local: bool) -> NodeJson {
 #[async_trait]
 impl Command for NodesCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
-        let local_node_num = ctx.node_db.my_node_num();
-        let nodes = ctx.node_db.nodes();
+        self.show(&ctx.node_db, ctx.units)
+    }
+}
+
+impl NodesCommand {
+    /// Prints the nodes of `node_db`, the device's or one loaded from a
+    /// snapshot file.
+    pub fn show(&self, node_db: &NodeDb, units: Units) -> anyhow::Result<()> {
+        let local_node_num = node_db.my_node_num();
+        let nodes = node_db.nodes();
 
-        if nodes.is_empty() {
+        if nodes.is_empty() && self.format == NodesFormat::Table {
             if self.json {
                 println!("[]");
             } else {
@@ -107,13 +191,33 @@ impl Command for NodesCommand {
             return Ok(());
         }
 
-        let mut sorted_nodes: Vec<_> = nodes.values().collect();
+        let fctx = FieldContext {
+            origin: Origin::resolve(self.from, nodes.get(&local_node_num)),
+            units,
+        };
+        let now = Utc::now().timestamp() as u32;
+        let mut sorted_nodes: Vec<_> = nodes
+            .values()
+            .filter(|n| self.filter.matches(n, now, &fctx))
+            .collect();
         sorted_nodes.sort_by_key(|n| n.num);
+        if let Some(sort) = self.sort {
+            self.sort_nodes(&mut sorted_nodes, sort, &fctx)?;
+        }
+        if let Some(limit) = self.limit {
+            sorted_nodes.truncate(limit);
+        }
+
+        if self.format == NodesFormat::GeoJson {
+            let geojson = nodes_geojson(&sorted_nodes, local_node_num, &fctx)?;
+            println!("{}", serde_json::to_string_pretty(&geojson)?);
+            return Ok(());
+        }
 
-        if self.json {
+        if self.json && self.format == NodesFormat::Table {
             let json_nodes: Vec<NodeJson> = sorted_nodes
                 .iter()
-                .map(|n| node_to_json(n, n.num == local_node_num))
+                .map(|n| node_to_json(n, n.num == local_node_num, &fctx))
                 .collect();
             println!("{}", serde_json::to_string_pretty(&json_nodes)?);
             return Ok(());
@@ -121,6 +225,9 @@ impl Command for NodesCommand {
 
         let fields: Vec<&str> = match &self.fields {
             Some(f) => f.iter().map(|s| s.as_str()).collect(),
+            None if matches!(self.format, NodesFormat::Csv | NodesFormat::Tsv) => {
+                ALL_FIELDS.to_vec()
+            }
             None => DEFAULT_FIELDS.to_vec(),
         };
 
@@ -134,22 +241,185 @@ impl Command for NodesCommand {
             }
         }
 
-        let header = build_header(&fields);
-        println!("{header}");
-        let sep_len: usize = fields.iter().map(|f| field_width(f) + 1).sum();
-        println!("{}", "-".repeat(sep_len));
+        match self.format {
+            NodesFormat::Csv => print_delimited(&sorted_nodes, &fields, &fctx, ','),
+            NodesFormat::Tsv => print_delimited(&sorted_nodes, &fields, &fctx, '\t'),
+            NodesFormat::Markdown => print_markdown(&sorted_nodes, &fields, &fctx),
+            NodesFormat::Table | NodesFormat::GeoJson => {
+                print_table(&sorted_nodes, &fields, &fctx, local_node_num)
+            }
+        }
 
-        for node in sorted_nodes {
-            let is_local = node.num == local_node_num;
-            let line = build_row(node, &fields);
+        Ok(())
+    }
+}
 
-            if is_local {
-                println!("{}", line.green());
-            } else {
-                println!("{}", line);
+fn print_table(nodes: &[&NodeInfo], fields: &[&str], fctx: &FieldContext, local_node_num: u32) {
+    let header = build_header(fields);
+    println!("{header}");
+    let sep_len: usize = fields.iter().map(|f| field_width(f) + 1).sum();
+    println!("{}", "-".repeat(sep_len));
+
+    for node in nodes {
+        let is_local = node.num == local_node_num;
+        let line = build_row(node, fields, fctx);
+
+        if is_local {
+            println!("{}", line.green());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+// ── Export formats ──────────────────────────────────────────────────
+
+/// Cell text for the export formats: missing values are left empty rather
+/// than shown as "N/A" or "Never".
+fn export_value(node: &NodeInfo, field: &str, fctx: &FieldContext) -> String {
+    let value = get_field_value(node, field, fctx);
+    if value == "N/A" || (field == "last_heard" && value == "Never") {
+        String::new()
+    } else {
+        value
+    }
+}
+
+/// CSV or TSV with a header row of field names. CSV cells are quoted when
+/// needed; TSV has no quoting, so tabs and newlines become spaces.
+fn print_delimited(nodes: &[&NodeInfo], fields: &[&str], fctx: &FieldContext, sep: char) {
+    let cell = |value: &str| {
+        if sep == ',' {
+            csv_field(value)
+        } else {
+            value.replace(['\t', '\n', '\r'], " ")
+        }
+    };
+    let sep = sep.to_string();
+
+    println!("{}", fields.join(&sep));
+    for node in nodes {
+        let row: Vec<String> = fields
+            .iter()
+            .map(|f| cell(&export_value(node, f, fctx)))
+            .collect();
+        println!("{}", row.join(&sep));
+    }
+}
+
+fn print_markdown(nodes: &[&NodeInfo], fields: &[&str], fctx: &FieldContext) {
+    let cell = |value: &str| value.replace('|', "\\|").replace(['\n', '\r'], " ");
+    let labels: Vec<&str> = fields.iter().map(|f| field_label(f)).collect();
+
+    println!("| {} |", labels.join(" | "));
+    println!("|{}", "---|".repeat(fields.len()));
+    for node in nodes {
+        let row: Vec<String> = fields
+            .iter()
+            .map(|f| cell(&export_value(node, f, fctx)))
+            .collect();
+        println!("| {} |", row.join(" | "));
+    }
+}
+
+/// A GeoJSON FeatureCollection with one Point feature per node that has a
+/// position. Properties are the `--json` fields plus the device metrics.
+fn nodes_geojson(
+    nodes: &[&NodeInfo],
+    local_node_num: u32,
+    fctx: &FieldContext,
+) -> anyhow::Result<serde_json::Value> {
+    let mut features = Vec::new();
+    for node in nodes {
+        let Some((lat, lon)) = node_lat_lon(node) else {
+            continue;
+        };
+        let mut coordinates = vec![serde_json::json!(lon), serde_json::json!(lat)];
+        if let Some(alt) = node.position.as_ref().and_then(|p| p.altitude) {
+            coordinates.push(serde_json::json!(alt));
+        }
+
+        let mut properties =
+            serde_json::to_value(node_to_json(node, node.num == local_node_num, fctx))?;
+        if let (Some(props), Some(metrics)) =
+            (properties.as_object_mut(), node.device_metrics.as_ref())
+        {
+            let round = |v: Option<f32>| v.map(|v| (v as f64 * 100.0).round() / 100.0);
+            props.insert("voltage".into(), serde_json::json!(round(metrics.voltage)));
+            props.insert(
+                "channel_utilization".into(),
+                serde_json::json!(round(metrics.channel_utilization)),
+            );
+            props.insert(
+                "air_util_tx".into(),
+                serde_json::json!(round(metrics.air_util_tx)),
+            );
+            props.insert(
+                "uptime_seconds".into(),
+                serde_json::json!(metrics.uptime_seconds),
+            );
+        }
+
+        features.push(serde_json::json!({
+            "type": "Feature",
+            "id": format!("!{:08x}", node.num),
+            "geometry": { "type": "Point", "coordinates": coordinates },
+            "properties": properties,
+        }));
+    }
+
+    Ok(serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+impl NodesCommand {
+    /// Sorts by `sort`, ascending unless `--desc`. Nodes without a value for
+    /// the column always come last; ties keep node number order.
+    fn sort_nodes(
+        &self,
+        nodes: &mut [&NodeInfo],
+        sort: NodeSort,
+        fctx: &FieldContext,
+    ) -> anyhow::Result<()> {
+        if sort == NodeSort::Name {
+            let name = |n: &NodeInfo| get_field_value(n, "name", fctx).to_lowercase();
+            nodes.sort_by_cached_key(|n| name(n));
+            if self.desc {
+                nodes.reverse();
             }
+            return Ok(());
+        }
+
+        if sort == NodeSort::Distance && fctx.origin.is_none() {
+            anyhow::bail!(
+                "Sorting by distance needs the local node's position, or --from-lat/--from-lon"
+            );
         }
 
+        let field = match sort {
+            NodeSort::LastHeard => "last_heard",
+            NodeSort::Snr => "snr",
+            NodeSort::Battery => "battery",
+            NodeSort::Hops => "hops",
+            NodeSort::Distance => "distance",
+            NodeSort::Name => unreachable!(),
+        };
+        let key = |n: &NodeInfo| field_number(n, field, fctx);
+        nodes.sort_by(|a, b| match (key(a), key(b)) {
+            (Some(x), Some(y)) => {
+                let order = x.total_cmp(&y);
+                if self.desc {
+                    order.reverse()
+                } else {
+                    order
+                }
+            }
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
         Ok(())
     }
 }
@@ -165,44 +435,76 @@ fn field_width(field: &str) -> usize {
         "hw_model" => 20,
         "role" => 14,
         "position" => 28,
+        "distance" => 10,
+        "bearing" => 10,
+        "altitude_diff" => 10,
         _ => 12,
     }
 }
 
+fn field_label(field: &str) -> &str {
+    match field {
+        "id" => "ID",
+        "name" => "Name",
+        "battery" => "Battery",
+        "snr" => "SNR",
+        "hops" => "Hops",
+        "last_heard" => "Last Heard",
+        "hw_model" => "Hardware",
+        "role" => "Role",
+        "position" => "Position",
+        "distance" => "Distance",
+        "bearing" => "Bearing",
+        "altitude_diff" => "Alt Diff",
+        _ => field,
+    }
+}
+
 fn build_header(fields: &[&str]) -> String {
     fields
         .iter()
-        .map(|f| {
-            let label = match *f {
-                "id" => "ID",
-                "name" => "Name",
-                "battery" => "Battery",
-                "snr" => "SNR",
-                "hops" => "Hops",
-                "last_heard" => "Last Heard",
-                "hw_model" => "Hardware",
-                "role" => "Role",
-                "position" => "Position",
-                _ => *f,
-            };
-            format!("{:<width$}", label, width = field_width(f))
-        })
+        .map(|f| format!("{:<width$}", field_label(f), width = field_width(f)))
         .collect::<Vec<_>>()
         .join(" ")
 }
 
-fn build_row(node: &NodeInfo, fields: &[&str]) -> String {
+fn build_row(node: &NodeInfo, fields: &[&str], fctx: &FieldContext) -> String {
     fields
         .iter()
         .map(|f| {
-            let value = get_field_value(node, f);
+            let value = get_field_value(node, f, fctx);
             format!("{:<width$}", value, width = field_width(f))
         })
         .collect::<Vec<_>>()
         .join(" ")
 }
 
-fn get_field_value(node: &NodeInfo, field: &str) -> String {
+/// The numeric value behind a field, for sorting and `--where` comparisons:
+/// battery percent, SNR in dB, hop count, `last_heard` as a Unix time,
+/// distance in km or miles, bearing in degrees, or altitude difference in
+/// meters or feet. `None` for text fields and missing values.
+pub(super) fn field_number(node: &NodeInfo, field: &str, fctx: &FieldContext) -> Option<f64> {
+    let relative = || fctx.origin.and_then(|o| o.relative_to_node(node));
+    match field {
+        "battery" => node
+            .device_metrics
+            .as_ref()
+            .and_then(|m| m.battery_level)
+            .map(f64::from),
+        "snr" => (node.snr != 0.0).then_some(node.snr as f64),
+        "hops" => node.hops_away.map(f64::from),
+        "last_heard" => (node.last_heard > 0).then_some(node.last_heard as f64),
+        "distance" => relative().map(|r| fctx.units.long_distance(r.distance_m)),
+        "bearing" => relative().map(|r| r.bearing_deg),
+        "altitude_diff" => relative()
+            .and_then(|r| r.altitude_diff_m)
+            .map(|d| fctx.units.height(d as f64)),
+        _ => None,
+    }
+}
+
+pub(super) fn get_field_value(node: &NodeInfo, field: &str, fctx: &FieldContext) -> String {
+    let relative = || fctx.origin.and_then(|o| o.relative_to_node(node));
     match field {
         "id" => format!("!{:08x}", node.num),
         "name" => node
@@ -267,6 +569,16 @@ fn get_field_value(node: &NodeInfo, field: &str) -> String {
                 }
             })
             .unwrap_or_else(|| "N/A".into()),
+        "distance" => relative()
+            .map(|r| fctx.units.format_distance(r.distance_m))
+            .unwrap_or_else(|| "N/A".into()),
+        "bearing" => relative()
+            .map(|r| r.format_bearing())
+            .unwrap_or_else(|| "N/A".into()),
+        "altitude_diff" => relative()
+            .and_then(|r| r.altitude_diff_m)
+            .map(|d| fctx.units.format_height_diff(d as f64))
+            .unwrap_or_else(|| "N/A".into()),
         _ => "N/A".into(),
     }
 }
@@ -0,0 +1,875 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveDate};
+use colored::Colorize;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use meshtastic::protobufs::mesh_packet::{PayloadVariant as MeshPayload, Priority};
+use meshtastic::protobufs::{Data, MeshPacket, PortNum};
+use serde::{Deserialize, Serialize};
+
+use super::channel_hash::KnownChannel;
+use super::decode::{format_payload, format_text, payload_to_json};
+use super::filter::PacketFilter;
+use super::listen::{hops_away, print_packet, print_packet_json, priority_name, RecentTexts};
+use super::parsers::{
+    format_time, hex_decode, hex_encode, parse_duration, parse_node_id, parse_size, parse_time,
+    sparkline,
+};
+use crate::cli::{LogAction, LogFormatArg};
+use crate::error::CliError;
+use crate::node_db::NodeDb;
+
+const BROADCAST_ADDR: u32 = 0xFFFFFFFF;
+
+/// Candidate timeline bucket sizes in seconds, smallest first.
+const BUCKET_SIZES: &[u64] = &[60, 300, 900, 3600, 6 * 3600, 86400, 7 * 86400];
+/// Automatic bucket sizing aims for at most this many buckets.
+const MAX_AUTO_BUCKETS: u64 = 24;
+
+/// One line of a `listen --log` JSON Lines file, also used for `listen --json`.
+///
+/// `raw` holds the payload bytes as hex so `log show` can decode the packet
+/// again exactly as `listen` did.
+#[derive(Serialize, Deserialize)]
+pub struct PacketJson {
+    from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_name: Option<String>,
+    to: String,
+    port: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    portnum: Option<i32>,
+    channel: u32,
+    /// Name and index of the local channel an encrypted packet's hash matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel_index: Option<i32>,
+    rx_time: u32,
+    #[serde(default)]
+    id: u32,
+    /// A further copy of a packet already logged, repeated by a relay
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    duplicate: bool,
+    #[serde(default)]
+    rx_snr: f32,
+    #[serde(default)]
+    rx_rssi: i32,
+    #[serde(default)]
+    hop_start: u32,
+    #[serde(default)]
+    hop_limit: u32,
+    #[serde(default)]
+    hops_away: u32,
+    #[serde(default)]
+    want_ack: bool,
+    #[serde(default)]
+    via_mqtt: bool,
+    #[serde(default)]
+    relay_node: u32,
+    #[serde(default)]
+    next_hop: u32,
+    #[serde(default)]
+    priority: String,
+    #[serde(default)]
+    pki_encrypted: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reply_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    emoji: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decoded: Option<serde_json::Value>,
+}
+
+pub fn build_packet_json(
+    packet: &MeshPacket,
+    node_db: &NodeDb,
+    channels: &[KnownChannel],
+    duplicate: bool,
+) -> PacketJson {
+    let mut json = PacketJson {
+        from: format!("!{:08x}", packet.from),
+        from_name: node_db.node_name(packet.from).map(|s| s.to_string()),
+        to: if packet.to == BROADCAST_ADDR {
+            "broadcast".to_string()
+        } else {
+            format!("!{:08x}", packet.to)
+        },
+        port: "encrypted".to_string(),
+        portnum: None,
+        channel: packet.channel,
+        channel_name: None,
+        channel_index: None,
+        rx_time: packet.rx_time,
+        id: packet.id,
+        duplicate,
+        rx_snr: packet.rx_snr,
+        rx_rssi: packet.rx_rssi,
+        hop_start: packet.hop_start,
+        hop_limit: packet.hop_limit,
+        hops_away: hops_away(packet),
+        want_ack: packet.want_ack,
+        via_mqtt: packet.via_mqtt,
+        relay_node: packet.relay_node,
+        next_hop: packet.next_hop,
+        priority: priority_name(packet.priority),
+        pki_encrypted: packet.pki_encrypted,
+        public_key: (!packet.public_key.is_empty()).then(|| hex_encode(&packet.public_key)),
+        reply_id: None,
+        emoji: false,
+        payload: None,
+        payload_size: None,
+        raw: None,
+        decoded: None,
+    };
+
+    match &packet.payload_variant {
+        Some(MeshPayload::Decoded(data)) => {
+            let port = PortNum::try_from(data.portnum).unwrap_or(PortNum::UnknownApp);
+            json.port = format!("{:?}", port);
+            json.portnum = Some(data.portnum);
+            json.reply_id = (data.reply_id != 0).then_some(data.reply_id);
+            json.emoji = data.emoji != 0;
+            if port == PortNum::TextMessageApp {
+                json.payload = Some(format_text(&data.payload));
+            }
+            json.payload_size = Some(data.payload.len());
+            json.raw = Some(hex_encode(&data.payload));
+            json.decoded = payload_to_json(&port, &data.payload);
+        }
+        Some(MeshPayload::Encrypted(bytes)) => {
+            json.raw = Some(hex_encode(bytes));
+            if let Some(known) = channels.iter().find(|c| c.hash as u32 == packet.channel) {
+                json.channel_name = Some(known.name.clone());
+                json.channel_index = Some(known.index);
+            }
+        }
+        None => {}
+    }
+    json
+}
+
+// ── Writing ───────────────────────────────────────────────────────
+
+const CSV_HEADER: &str = "time,rx_time,from,from_name,to,port,channel,id,rx_snr,rx_rssi,hop_start,hop_limit,hops_away,want_ack,via_mqtt,relay_node,next_hop,priority,pki_encrypted,payload_size,payload,raw,duplicate";
+
+#[derive(Clone)]
+pub enum LogFormat {
+    Jsonl,
+    Csv,
+}
+
+impl From<&LogFormatArg> for LogFormat {
+    fn from(arg: &LogFormatArg) -> Self {
+        match arg {
+            LogFormatArg::Jsonl => LogFormat::Jsonl,
+            LogFormatArg::Csv => LogFormat::Csv,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum LogRotation {
+    Size(u64),
+    Daily,
+}
+
+impl LogRotation {
+    pub fn parse(value: &str) -> Result<Self, CliError> {
+        if value.eq_ignore_ascii_case("daily") {
+            return Ok(LogRotation::Daily);
+        }
+        parse_size(value)
+            .map(LogRotation::Size)
+            .map_err(|e| CliError::InvalidArgument(format!("--log-rotate: {}", e)))
+    }
+}
+
+#[derive(Clone)]
+pub struct LogOptions {
+    pub path: PathBuf,
+    pub format: LogFormat,
+    pub rotation: Option<LogRotation>,
+    pub keep: u32,
+    pub gzip: bool,
+}
+
+/// Appends packets to a log file, rotating it by size or by day.
+///
+/// Rotated files are shifted logrotate-style: `packets.jsonl.1` is the most
+/// recent, up to `packets.jsonl.<keep>` (with `.gz` when compressed).
+pub struct PacketLogWriter {
+    options: LogOptions,
+    writer: BufWriter<File>,
+    size: u64,
+    day: NaiveDate,
+}
+
+impl PacketLogWriter {
+    pub fn open(options: LogOptions) -> anyhow::Result<Self> {
+        let (writer, size, day) = open_append(&options.path, &options.format)?;
+        Ok(Self {
+            options,
+            writer,
+            size,
+            day,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.options.path
+    }
+
+    /// Appends one packet; `duplicate` marks a copy of a packet already
+    /// written.
+    pub fn write(
+        &mut self,
+        packet: &MeshPacket,
+        node_db: &NodeDb,
+        channels: &[KnownChannel],
+        duplicate: bool,
+    ) -> anyhow::Result<()> {
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+        let record = build_packet_json(packet, node_db, channels, duplicate);
+        let line = match self.options.format {
+            LogFormat::Jsonl => serde_json::to_string(&record)?,
+            LogFormat::Csv => csv_row(packet, record),
+        };
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn should_rotate(&self) -> bool {
+        match self.options.rotation {
+            Some(LogRotation::Size(max)) => self.size >= max,
+            Some(LogRotation::Daily) => self.size > 0 && Local::now().date_naive() != self.day,
+            None => false,
+        }
+    }
+
+    fn rotate(&mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        let path = &self.options.path;
+        let gzip = self.options.gzip;
+
+        let _ = fs::remove_file(rotated_path(path, self.options.keep, gzip));
+        for n in (1..self.options.keep).rev() {
+            let from = rotated_path(path, n, gzip);
+            if from.exists() {
+                fs::rename(&from, rotated_path(path, n + 1, gzip))?;
+            }
+        }
+
+        let first = rotated_path(path, 1, false);
+        fs::rename(path, &first)
+            .with_context(|| format!("Failed to rotate log file: {}", path.display()))?;
+        if gzip {
+            gzip_file(&first)?;
+        }
+
+        let (writer, size, day) = open_append(path, &self.options.format)?;
+        self.writer = writer;
+        self.size = size;
+        self.day = day;
+        Ok(())
+    }
+}
+
+fn open_append(
+    path: &Path,
+    format: &LogFormat,
+) -> anyhow::Result<(BufWriter<File>, u64, NaiveDate)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+    let metadata = file.metadata()?;
+    let mut size = metadata.len();
+    let day = metadata
+        .modified()
+        .ok()
+        .filter(|_| size > 0)
+        .map(|t| DateTime::<Local>::from(t).date_naive())
+        .unwrap_or_else(|| Local::now().date_naive());
+
+    let mut writer = BufWriter::new(file);
+    if size == 0 {
+        if let LogFormat::Csv = format {
+            writeln!(writer, "{}", CSV_HEADER)?;
+            writer.flush()?;
+            size = CSV_HEADER.len() as u64 + 1;
+        }
+    }
+    Ok((writer, size, day))
+}
+
+fn rotated_path(path: &Path, n: u32, gzip: bool) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    if gzip {
+        name.push(".gz");
+    }
+    PathBuf::from(name)
+}
+
+fn gzip_file(path: &Path) -> anyhow::Result<()> {
+    let mut gz_name = path.as_os_str().to_owned();
+    gz_name.push(".gz");
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(
+        File::create(PathBuf::from(gz_name))?,
+        Compression::default(),
+    );
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+pub(super) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(packet: &MeshPacket, record: PacketJson) -> String {
+    let time = DateTime::from_timestamp(record.rx_time as i64, 0)
+        .filter(|_| record.rx_time != 0)
+        .map(|dt| dt.with_timezone(&Local).to_rfc3339())
+        .unwrap_or_default();
+    let summary = match &packet.payload_variant {
+        Some(MeshPayload::Decoded(data)) => format_payload(
+            &PortNum::try_from(data.portnum).unwrap_or(PortNum::UnknownApp),
+            &data.payload,
+        ),
+        _ => String::new(),
+    };
+    let opt = |v: Option<usize>| v.map(|n| n.to_string()).unwrap_or_default();
+
+    [
+        time,
+        record.rx_time.to_string(),
+        record.from,
+        record.from_name.unwrap_or_default(),
+        record.to,
+        record.port,
+        record.channel.to_string(),
+        record.id.to_string(),
+        record.rx_snr.to_string(),
+        record.rx_rssi.to_string(),
+        record.hop_start.to_string(),
+        record.hop_limit.to_string(),
+        record.hops_away.to_string(),
+        record.want_ack.to_string(),
+        record.via_mqtt.to_string(),
+        record.relay_node.to_string(),
+        record.next_hop.to_string(),
+        record.priority,
+        record.pki_encrypted.to_string(),
+        opt(record.payload_size),
+        summary,
+        record.raw.unwrap_or_default(),
+        record.duplicate.to_string(),
+    ]
+    .iter()
+    .map(|v| csv_field(v))
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+impl PacketJson {
+    /// Rebuilds the packet as it was received. Logs written before `raw` was
+    /// recorded only carry the text of text messages.
+    fn to_mesh_packet(&self) -> Option<MeshPacket> {
+        let from = parse_node_id(&self.from)?;
+        let to = if self.to == "broadcast" {
+            BROADCAST_ADDR
+        } else {
+            parse_node_id(&self.to)?
+        };
+        let bytes = match &self.raw {
+            Some(hex) => hex_decode(hex).ok()?,
+            None => self.payload.clone().unwrap_or_default().into_bytes(),
+        };
+        let payload_variant = if self.port == "encrypted" {
+            MeshPayload::Encrypted(bytes)
+        } else {
+            let portnum = self
+                .portnum
+                .unwrap_or_else(|| port_from_debug_name(&self.port) as i32);
+            MeshPayload::Decoded(Data {
+                portnum,
+                payload: bytes,
+                reply_id: self.reply_id.unwrap_or(0),
+                emoji: self.emoji as u32,
+                ..Default::default()
+            })
+        };
+
+        Some(MeshPacket {
+            from,
+            to,
+            channel: self.channel,
+            id: self.id,
+            rx_time: self.rx_time,
+            rx_snr: self.rx_snr,
+            rx_rssi: self.rx_rssi,
+            hop_start: self.hop_start,
+            hop_limit: self.hop_limit,
+            want_ack: self.want_ack,
+            via_mqtt: self.via_mqtt,
+            relay_node: self.relay_node,
+            next_hop: self.next_hop,
+            priority: Priority::from_str_name(&self.priority)
+                .map(|p| p as i32)
+                .or_else(|| self.priority.parse().ok())
+                .unwrap_or_default(),
+            pki_encrypted: self.pki_encrypted,
+            public_key: self
+                .public_key
+                .as_deref()
+                .and_then(|hex| hex_decode(hex).ok())
+                .unwrap_or_default(),
+            payload_variant: Some(payload_variant),
+            ..Default::default()
+        })
+    }
+}
+
+/// Maps the `{:?}` port name stored in `port` (e.g. `TextMessageApp`) back to a `PortNum`.
+fn port_from_debug_name(name: &str) -> PortNum {
+    let mut upper = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if i > 0 && c.is_ascii_uppercase() {
+            upper.push('_');
+        }
+        upper.push(c.to_ascii_uppercase());
+    }
+    PortNum::from_str_name(&upper).unwrap_or(PortNum::UnknownApp)
+}
+
+// ── Reading ───────────────────────────────────────────────────────
+
+struct LogRecords {
+    records: Vec<PacketJson>,
+    skipped: usize,
+}
+
+fn read_log(path: &Path) -> anyhow::Result<LogRecords> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+    // Rotated logs may have been compressed with --log-gzip
+    let reader: Box<dyn BufRead> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for line in reader.lines() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<PacketJson>(&line) {
+            Ok(record) => records.push(record),
+            Err(_) => skipped += 1,
+        }
+    }
+    Ok(LogRecords { records, skipped })
+}
+
+/// A node DB holding only the names recorded in the log, so the live
+/// formatter and `--from <name>` work without a device.
+fn node_db_from_records(records: &[PacketJson]) -> NodeDb {
+    NodeDb::from_names(
+        records
+            .iter()
+            .filter_map(|r| Some((parse_node_id(&r.from)?, r.from_name.clone()?))),
+    )
+}
+
+/// The channels that encrypted packets in the log were matched to when
+/// they were recorded.
+fn channels_from_records(records: &[PacketJson]) -> Vec<KnownChannel> {
+    let mut channels: Vec<KnownChannel> = Vec::new();
+    for record in records {
+        let (Some(name), Some(index)) = (&record.channel_name, record.channel_index) else {
+            continue;
+        };
+        let hash = record.channel as u8;
+        if !channels.iter().any(|c| c.hash == hash) {
+            channels.push(KnownChannel {
+                index,
+                name: name.clone(),
+                hash,
+            });
+        }
+    }
+    channels
+}
+
+/// Packets loaded from a log, with what the log recorded about nodes and
+/// channels.
+pub(super) struct LoadedLog {
+    pub packets: Vec<MeshPacket>,
+    /// Whether each packet was logged as a copy repeated by a relay
+    pub duplicate: Vec<bool>,
+    pub node_db: NodeDb,
+    pub channels: Vec<KnownChannel>,
+}
+
+/// Loads every packet in a log together with the names and channels it
+/// recorded.
+pub(super) fn load_log(file: &str, json: bool) -> anyhow::Result<LoadedLog> {
+    let log = read_log(Path::new(file))?;
+    if log.skipped > 0 && !json {
+        eprintln!(
+            "{} Skipped {} malformed line(s) in {}",
+            "!".yellow(),
+            log.skipped,
+            file
+        );
+    }
+
+    let (packets, duplicate) = log
+        .records
+        .iter()
+        .filter_map(|r| Some((r.to_mesh_packet()?, r.duplicate)))
+        .unzip();
+    Ok(LoadedLog {
+        packets,
+        duplicate,
+        node_db: node_db_from_records(&log.records),
+        channels: channels_from_records(&log.records),
+    })
+}
+
+/// Loads a log and returns the packets that pass the filters and time range.
+/// Copies logged as duplicates are dropped unless `duplicates` is set.
+fn load_packets(
+    file: &str,
+    filter: &PacketFilter,
+    since: &Option<String>,
+    until: &Option<String>,
+    duplicates: bool,
+    json: bool,
+) -> anyhow::Result<LoadedLog> {
+    let since = since.as_deref().map(parse_time).transpose()?;
+    let until = until.as_deref().map(parse_time).transpose()?;
+
+    let log = load_log(file, json)?;
+    let active = filter.resolve(&log.node_db)?;
+    let in_range = |rx_time: u32| {
+        let t = rx_time as i64;
+        (since.is_none() && until.is_none())
+            || (rx_time != 0 && since.is_none_or(|s| t >= s) && until.is_none_or(|u| t <= u))
+    };
+
+    let (packets, duplicate) = log
+        .packets
+        .into_iter()
+        .zip(log.duplicate)
+        .filter(|(p, dup)| (duplicates || !dup) && in_range(p.rx_time) && active.matches(p))
+        .unzip();
+    Ok(LoadedLog {
+        packets,
+        duplicate,
+        ..log
+    })
+}
+
+// ── Commands ──────────────────────────────────────────────────────
+
+pub fn handle_log(action: &LogAction, json: bool) -> anyhow::Result<()> {
+    match action {
+        LogAction::Show {
+            file,
+            port,
+            filter,
+            since,
+            until,
+            meta,
+            show_duplicates,
+        } => {
+            let filter = PacketFilter::from_args(filter, port)?;
+            let log = load_packets(file, &filter, since, until, *show_duplicates, json)?;
+            let mut recent = RecentTexts::default();
+            for (packet, &duplicate) in log.packets.iter().zip(&log.duplicate) {
+                if json {
+                    print_packet_json(packet, &log.node_db, &log.channels, duplicate);
+                } else {
+                    print_packet(packet, &log.node_db, &log.channels, &recent, *meta);
+                    recent.remember(packet);
+                }
+            }
+            if !json {
+                println!("\n{} {} packets", "->".cyan(), log.packets.len());
+            }
+            Ok(())
+        }
+        LogAction::Stats {
+            file,
+            port,
+            filter,
+            since,
+            until,
+            bucket,
+        } => {
+            let filter = PacketFilter::from_args(filter, port)?;
+            let bucket_secs = bucket
+                .as_deref()
+                .map(parse_duration)
+                .transpose()?
+                .map(|d| d.as_secs().max(1));
+            let log = load_packets(file, &filter, since, until, false, json)?;
+            let report = build_stats(&log.packets, &log.node_db, bucket_secs);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_stats(file, &report);
+            }
+            Ok(())
+        }
+    }
+}
+
+// ── Stats ─────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct LogStatsJson {
+    packets: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_rx_time: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_rx_time: Option<u32>,
+    bucket_secs: u64,
+    ports: BTreeMap<String, u64>,
+    nodes: Vec<LogNodeJson>,
+    timeline: Vec<TimelineBucketJson>,
+}
+
+#[derive(Serialize)]
+struct LogNodeJson {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    packets: u64,
+    ports: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_seen: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_seen: Option<u32>,
+    /// Packets per timeline bucket, aligned with the top-level `timeline`.
+    timeline: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct TimelineBucketJson {
+    start: u32,
+    packets: u64,
+}
+
+#[derive(Default)]
+struct NodeCounts {
+    packets: u64,
+    ports: BTreeMap<String, u64>,
+    first_seen: Option<u32>,
+    last_seen: Option<u32>,
+}
+
+fn port_name(packet: &MeshPacket) -> String {
+    match &packet.payload_variant {
+        Some(MeshPayload::Decoded(data)) => PortNum::try_from(data.portnum)
+            .map(|p| p.as_str_name().to_string())
+            .unwrap_or_else(|_| data.portnum.to_string()),
+        _ => "ENCRYPTED".to_string(),
+    }
+}
+
+fn auto_bucket(span: u64) -> u64 {
+    BUCKET_SIZES
+        .iter()
+        .copied()
+        .find(|size| span / size < MAX_AUTO_BUCKETS)
+        .unwrap_or(BUCKET_SIZES[BUCKET_SIZES.len() - 1])
+}
+
+fn build_stats(packets: &[MeshPacket], node_db: &NodeDb, bucket: Option<u64>) -> LogStatsJson {
+    let times = packets.iter().map(|p| p.rx_time).filter(|&t| t != 0);
+    let first = times.clone().min();
+    let last = times.max();
+    let bucket_secs = bucket.unwrap_or_else(|| {
+        auto_bucket(last.unwrap_or(0).saturating_sub(first.unwrap_or(0)) as u64)
+    });
+
+    let start = first.map(|f| f as u64 / bucket_secs * bucket_secs);
+    let num_buckets = match (start, last) {
+        (Some(s), Some(l)) => ((l as u64 - s) / bucket_secs + 1) as usize,
+        _ => 0,
+    };
+    let bucket_index = |rx_time: u32| -> Option<usize> {
+        let s = start?;
+        (rx_time != 0).then(|| ((rx_time as u64 - s) / bucket_secs) as usize)
+    };
+
+    let mut ports: BTreeMap<String, u64> = BTreeMap::new();
+    let mut nodes: HashMap<u32, NodeCounts> = HashMap::new();
+    let mut node_timelines: HashMap<u32, Vec<u64>> = HashMap::new();
+    let mut timeline = vec![0u64; num_buckets];
+
+    for packet in packets {
+        let port = port_name(packet);
+        *ports.entry(port.clone()).or_default() += 1;
+
+        let node = nodes.entry(packet.from).or_default();
+        node.packets += 1;
+        *node.ports.entry(port).or_default() += 1;
+        if packet.rx_time != 0 {
+            node.first_seen = Some(
+                node.first_seen
+                    .map_or(packet.rx_time, |t| t.min(packet.rx_time)),
+            );
+            node.last_seen = Some(
+                node.last_seen
+                    .map_or(packet.rx_time, |t| t.max(packet.rx_time)),
+            );
+        }
+
+        if let Some(idx) = bucket_index(packet.rx_time) {
+            timeline[idx] += 1;
+            node_timelines
+                .entry(packet.from)
+                .or_insert_with(|| vec![0; num_buckets])[idx] += 1;
+        }
+    }
+
+    let mut node_list: Vec<_> = nodes.into_iter().collect();
+    node_list.sort_by(|a, b| b.1.packets.cmp(&a.1.packets).then(a.0.cmp(&b.0)));
+
+    LogStatsJson {
+        packets: packets.len(),
+        first_rx_time: first,
+        last_rx_time: last,
+        bucket_secs,
+        ports,
+        nodes: node_list
+            .into_iter()
+            .map(|(num, counts)| LogNodeJson {
+                id: format!("!{:08x}", num),
+                name: node_db.node_name(num).map(|s| s.to_string()),
+                packets: counts.packets,
+                ports: counts.ports,
+                first_seen: counts.first_seen,
+                last_seen: counts.last_seen,
+                timeline: node_timelines
+                    .remove(&num)
+                    .unwrap_or_else(|| vec![0; num_buckets]),
+            })
+            .collect(),
+        timeline: timeline
+            .into_iter()
+            .enumerate()
+            .map(|(i, packets)| TimelineBucketJson {
+                start: (start.unwrap_or(0) + i as u64 * bucket_secs) as u32,
+                packets,
+            })
+            .collect(),
+    }
+}
+
+fn format_rx_time(rx_time: Option<u32>) -> String {
+    rx_time.map_or_else(|| "N/A".to_string(), |t| format_time(t as i64))
+}
+
+fn print_stats(file: &str, report: &LogStatsJson) {
+    println!("{}", "Log Summary".bold().underline());
+    println!("  {:<12} {}", "file:".dimmed(), file);
+    println!("  {:<12} {}", "packets:".dimmed(), report.packets);
+    println!("  {:<12} {}", "nodes:".dimmed(), report.nodes.len());
+    if let (Some(first), Some(last)) = (report.first_rx_time, report.last_rx_time) {
+        println!(
+            "  {:<12} {} -> {} ({})",
+            "span:".dimmed(),
+            format_time(first as i64),
+            format_time(last as i64),
+            super::parsers::format_uptime(last - first, false)
+        );
+    }
+
+    println!("\n{}", "Nodes".bold());
+    println!(
+        "  {:<12} {:<20} {:<8} {:<18} {:<18} {}",
+        "ID".bold(),
+        "Name".bold(),
+        "Packets".bold(),
+        "First seen".bold(),
+        "Last seen".bold(),
+        "Activity".bold()
+    );
+    for node in &report.nodes {
+        let timeline: Vec<f64> = node.timeline.iter().map(|&c| c as f64).collect();
+        println!(
+            "  {:<12} {:<20} {:<8} {:<18} {:<18} {}",
+            node.id,
+            node.name.as_deref().unwrap_or("Unknown"),
+            node.packets,
+            format_rx_time(node.first_seen),
+            format_rx_time(node.last_seen),
+            sparkline(&timeline, true).cyan()
+        );
+    }
+
+    println!("\n{}", "Ports".bold());
+    let mut ports: Vec<_> = report.ports.iter().collect();
+    ports.sort_by(|a, b| b.1.cmp(a.1));
+    for (port, count) in ports {
+        println!("  {:<24} {}", port, count);
+    }
+
+    if report.timeline.is_empty() {
+        return;
+    }
+    println!(
+        "\n{} ({} buckets)",
+        "Timeline".bold(),
+        super::parsers::format_uptime(report.bucket_secs as u32, false)
+    );
+    let max = report
+        .timeline
+        .iter()
+        .map(|b| b.packets)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for bucket in &report.timeline {
+        let width = (bucket.packets * 40).div_ceil(max) as usize;
+        println!(
+            "  {:<18} {:<40} {}",
+            format_time(bucket.start as i64),
+            "█".repeat(width).green(),
+            bucket.packets
+        );
+    }
+}
@@ -35,6 +35,29 @@ pub fn parse_u64(value: &str) -> anyhow::Result<u64> {
         .map_err(|_| anyhow::anyhow!("Invalid u64 value '{}'", value))
 }
 
+/// Parses a packet ID as shown by `listen --meta` and `messages list`:
+/// hex, with an optional `#` or `0x` prefix.
+pub fn parse_packet_id(value: &str) -> anyhow::Result<u32> {
+    let trimmed = value.trim();
+    let hex = trimmed
+        .strip_prefix('#')
+        .or_else(|| trimmed.strip_prefix("0x"))
+        .unwrap_or(trimmed);
+    match u32::from_str_radix(hex, 16) {
+        Ok(id) if id != 0 => Ok(id),
+        _ => bail!(
+            "Invalid packet ID '{}'. Expected hex like 5f3a91c2 (see listen --meta or messages list).",
+            value
+        ),
+    }
+}
+
+/// Parses a node ID as stored in logs and local stores: 8 hex digits with
+/// an optional `!` prefix.
+pub fn parse_node_id(value: &str) -> Option<u32> {
+    u32::from_str_radix(value.strip_prefix('!').unwrap_or(value), 16).ok()
+}
+
 pub fn format_uptime(seconds: u32, include_seconds: bool) -> String {
     let days = seconds / 86400;
     let hours = (seconds % 86400) / 3600;
@@ -60,6 +83,54 @@ pub fn format_uptime(seconds: u32, include_seconds: bool) -> String {
     }
 }
 
+/// Shortens `s` to at most `max` characters, marking the cut with "…".
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() > max {
+        let cut: String = s.chars().take(max.saturating_sub(1)).collect();
+        format!("{}…", cut)
+    } else {
+        s.to_string()
+    }
+}
+
+/// Formats a Unix timestamp as local `YYYY-MM-DD HH:MM`.
+pub fn format_time(time: i64) -> String {
+    chrono::DateTime::from_timestamp(time, 0)
+        .map(|dt| {
+            dt.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|| time.to_string())
+}
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One block character per value. Counts (`from_zero`) scale from zero to
+/// the largest, with zeros left blank; other values scale from the smallest
+/// to the largest.
+pub fn sparkline(values: &[f64], from_zero: bool) -> String {
+    let min = if from_zero {
+        0.0
+    } else {
+        values.iter().copied().fold(f64::INFINITY, f64::min)
+    };
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|&v| {
+            if from_zero && v <= 0.0 {
+                ' '
+            } else if max <= min {
+                SPARK_CHARS[SPARK_CHARS.len() / 2]
+            } else {
+                let level = ((v - min) / (max - min) * (SPARK_CHARS.len() - 1) as f64).round();
+                SPARK_CHARS[level as usize]
+            }
+        })
+        .collect()
+}
+
 pub fn hex_decode(hex: &str) -> anyhow::Result<Vec<u8>> {
     if hex.is_empty() {
         return Ok(vec![]);
@@ -76,6 +147,10 @@ pub fn hex_decode(hex: &str) -> anyhow::Result<Vec<u8>> {
         .collect()
 }
 
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 pub fn base64_url_encode(bytes: &[u8]) -> String {
     const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
 
@@ -195,3 +270,101 @@ pub fn parse_enum_i32(value: &str, variants: &[(&str, i32)]) -> anyhow::Result<i
         names.join(", ")
     )
 }
+
+/// Parses a duration like `90s`, `30m`, `2h`, `14d` or `1w`. A bare number is seconds.
+pub fn parse_duration(value: &str) -> anyhow::Result<std::time::Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let Ok(n) = number.parse::<u64>() else {
+        bail!(
+            "Invalid duration '{}'. Use a number with a unit, e.g. 30m, 2h or 14d.",
+            value
+        );
+    };
+    let unit_secs: u64 = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        _ => bail!(
+            "Invalid duration unit '{}' in '{}'. Use s, m, h, d or w.",
+            unit,
+            value
+        ),
+    };
+    let Some(secs) = n.checked_mul(unit_secs) else {
+        bail!("Invalid duration '{}': too long.", value);
+    };
+    Ok(std::time::Duration::from_secs(secs))
+}
+
+/// Parses a point in time as a Unix timestamp: either a duration ago (`2h`),
+/// a Unix timestamp (9 or more digits), RFC 3339 (`2026-10-18T12:00:00Z`),
+/// or local `YYYY-MM-DD[ HH:MM[:SS]]`.
+pub fn parse_time(value: &str) -> anyhow::Result<i64> {
+    use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    let value = value.trim();
+    // 9 digits of seconds ago would be over 30 years: read it as a timestamp
+    if value.len() >= 9 && value.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(value.parse()?);
+    }
+    if let Ok(ago) = parse_duration(value) {
+        let Some(time) = i64::try_from(ago.as_secs())
+            .ok()
+            .and_then(|secs| Local::now().timestamp().checked_sub(secs))
+        else {
+            bail!("Invalid time '{}': too far back.", value);
+        };
+        return Ok(time);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.timestamp());
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        });
+    match naive.and_then(|n| Local.from_local_datetime(&n).earliest()) {
+        Some(dt) => Ok(dt.timestamp()),
+        None => bail!(
+            "Invalid time '{}'. Use a duration ago (2h, 3d), a Unix timestamp, RFC 3339, or YYYY-MM-DD [HH:MM].",
+            value
+        ),
+    }
+}
+
+/// Parses a byte size like `500K`, `10M` or `1G` (1024-based). A bare number is bytes.
+pub fn parse_size(value: &str) -> anyhow::Result<u64> {
+    let upper = value.trim().to_uppercase();
+    let upper = upper.strip_suffix('B').unwrap_or(&upper);
+    let split = upper
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(upper.len());
+    let (number, unit) = upper.split_at(split);
+    let multiplier = match unit {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => bail!(
+            "Invalid size '{}'. Use a number with K, M or G, e.g. 10M.",
+            value
+        ),
+    };
+    match number.parse::<u64>().map(|n| n.checked_mul(multiplier)) {
+        Ok(Some(size)) if size > 0 => Ok(size),
+        _ => bail!(
+            "Invalid size '{}'. Use a number with K, M or G, e.g. 10M.",
+            value
+        ),
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
+use meshtastic::protobufs::{MeshPacket, PortNum};
+
+/// Largest text payload sent in one packet. The firmware limit is 233 bytes;
+/// this leaves room for the PKI and MQTT overheads some paths add.
+pub const MAX_TEXT_BYTES: usize = 200;
+
+/// Most parts one message is split into. A received `(k/n)` prefix with a
+/// larger `n` is treated as plain text, so a sender cannot make us hold an
+/// arbitrary number of slots.
+pub const MAX_PARTS: usize = 99;
+
+/// Incomplete part sets are given up after this long.
+const PART_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Splits `text` into parts of at most `max_bytes` bytes each, prefixed with
+/// `(k/n) `. Splits after whitespace when possible and never inside a UTF-8
+/// character. Text that fits in one packet is returned unchanged.
+///
+/// The whitespace a part ends on is kept, so concatenating the part bodies
+/// gives back the original text exactly.
+pub fn split_message(text: &str, max_bytes: usize) -> Vec<String> {
+    if text.len() <= max_bytes {
+        return vec![text.to_string()];
+    }
+
+    // The prefix grows with the number of parts, so retry with wider counts
+    let mut digits = 1;
+    loop {
+        let budget = max_bytes.saturating_sub(2 * digits + 4).max(4);
+        let chunks = chunk_text(text, budget);
+        let total = chunks.len();
+        if total.to_string().len() <= digits {
+            return chunks
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| format!("({}/{}) {}", i + 1, total, chunk))
+                .collect();
+        }
+        digits += 1;
+    }
+}
+
+fn chunk_text(text: &str, budget: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.len() > budget {
+        let mut end = budget;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        // Prefer to end on whitespace, unless that leaves a tiny part
+        if let Some((pos, c)) = rest[..end]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+        {
+            if pos + c.len_utf8() > budget / 2 {
+                end = pos + c.len_utf8();
+            }
+        }
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+/// Parses a `(k/n) ` part prefix, returning `(k, n, body)`.
+pub fn parse_part(text: &str) -> Option<(usize, usize, &str)> {
+    let rest = text.strip_prefix('(')?;
+    let (numbers, body) = rest.split_once(") ")?;
+    let (k, n) = numbers.split_once('/')?;
+    let (k, n) = (k.parse().ok()?, n.parse().ok()?);
+    (n <= MAX_PARTS && (1..=n).contains(&k)).then_some((k, n, body))
+}
+
+/// Outcome of feeding a packet to a [`PartBuffer`].
+pub enum Reassembly {
+    /// Not a numbered text part; handle the packet as usual.
+    Single,
+    /// Held until the remaining parts arrive.
+    Held,
+    /// The last missing part arrived: one packet carrying the joined text.
+    Complete(Box<MeshPacket>),
+}
+
+struct PendingParts {
+    started: Instant,
+    parts: Vec<Option<MeshPacket>>,
+}
+
+/// Joins numbered text parts from the same sender back into one message.
+#[derive(Default)]
+pub struct PartBuffer {
+    pending: HashMap<(u32, u32, u32, usize), PendingParts>,
+    /// Parts of sets cut short by a new message with the same key
+    superseded: Vec<MeshPacket>,
+}
+
+impl PartBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, packet: &MeshPacket) -> Reassembly {
+        let Some(MeshPayload::Decoded(ref data)) = packet.payload_variant else {
+            return Reassembly::Single;
+        };
+        if data.portnum != PortNum::TextMessageApp as i32 {
+            return Reassembly::Single;
+        }
+        let text = String::from_utf8_lossy(&data.payload);
+        let Some((k, n, _)) = parse_part(&text) else {
+            return Reassembly::Single;
+        };
+        if n < 2 {
+            return Reassembly::Single;
+        }
+
+        let key = (packet.from, packet.to, packet.channel, n);
+        // A part we already hold means the sender started another message
+        // with the same number of parts
+        if self
+            .pending
+            .get(&key)
+            .is_some_and(|e| e.parts[k - 1].is_some())
+        {
+            if let Some(old) = self.pending.remove(&key) {
+                self.superseded.extend(old.parts.into_iter().flatten());
+            }
+        }
+        let entry = self.pending.entry(key).or_insert_with(|| PendingParts {
+            started: Instant::now(),
+            parts: vec![None; n],
+        });
+        entry.parts[k - 1] = Some(packet.clone());
+        if entry.parts.iter().any(|p| p.is_none()) {
+            return Reassembly::Held;
+        }
+
+        let parts = self
+            .pending
+            .remove(&key)
+            .map(|e| e.parts)
+            .unwrap_or_default();
+        let mut joined = String::new();
+        for part in parts.iter().flatten() {
+            if let Some(MeshPayload::Decoded(ref data)) = part.payload_variant {
+                let text = String::from_utf8_lossy(&data.payload);
+                joined.push_str(parse_part(&text).map(|(_, _, body)| body).unwrap_or(&text));
+            }
+        }
+
+        // The first part carries the packet ID and any reply_id
+        let mut packet = parts[0].clone().unwrap_or_else(|| packet.clone());
+        if let Some(MeshPayload::Decoded(ref mut data)) = packet.payload_variant {
+            data.payload = joined.into_bytes();
+        }
+        Reassembly::Complete(Box::new(packet))
+    }
+
+    /// Removes part sets still incomplete after the timeout (all of them when
+    /// `all` is set) and returns their parts, along with those of superseded
+    /// sets, so they can be shown as they are.
+    pub fn expire(&mut self, all: bool) -> Vec<MeshPacket> {
+        let expired: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| all || p.started.elapsed() > PART_TIMEOUT)
+            .map(|(key, _)| *key)
+            .collect();
+        let mut stale = std::mem::take(&mut self.superseded);
+        stale.extend(
+            expired
+                .into_iter()
+                .filter_map(|key| self.pending.remove(&key))
+                .flat_map(|p| p.parts.into_iter().flatten()),
+        );
+        stale
+    }
+}
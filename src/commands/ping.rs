@@ -6,6 +6,7 @@ use colored::Colorize;
 use meshtastic::protobufs::from_radio::PayloadVariant;
 use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
 use meshtastic::protobufs::{self, routing, Data, MeshPacket, PortNum, Routing};
+use meshtastic::types::MeshChannel;
 use meshtastic::utils::generate_rand_id;
 use meshtastic::Message;
 use serde::Serialize;
@@ -13,44 +14,112 @@ use serde::Serialize;
 use super::{resolve_destination, Command, CommandContext, DestinationSpec};
 
 #[derive(Serialize)]
-struct PingJson {
-    dest: String,
-    status: String,
+struct PingReplyJson {
+    seq: u32,
+    status: &'static str,
     #[serde(skip_serializing_if = "Option::is_none")]
     rtt_ms: Option<u128>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
 }
 
+#[derive(Serialize)]
+struct RttJson {
+    min: u128,
+    avg: u128,
+    max: u128,
+    stddev: u128,
+}
+
+#[derive(Serialize)]
+struct PingJson {
+    dest: String,
+    channel: u32,
+    sent: u32,
+    received: u32,
+    loss_pct: f64,
+    rtt_ms: Option<RttJson>,
+    pings: Vec<PingReplyJson>,
+}
+
+/// How one ping ended.
+enum Outcome {
+    /// The destination acknowledged the packet
+    Ack(Duration),
+    /// Our node gave up, with the routing error it reported
+    Nak(String, Duration),
+    Timeout,
+}
+
+impl Outcome {
+    fn to_json(&self, seq: u32, timeout_secs: u64) -> PingReplyJson {
+        let (status, rtt, error) = match self {
+            Outcome::Ack(rtt) => ("ack", Some(rtt), None),
+            Outcome::Nak(reason, rtt) => ("nak", Some(rtt), Some(reason.clone())),
+            Outcome::Timeout => (
+                "timeout",
+                None,
+                Some(format!("Timeout after {}s", timeout_secs)),
+            ),
+        };
+        PingReplyJson {
+            seq,
+            status,
+            rtt_ms: rtt.map(Duration::as_millis),
+            error,
+        }
+    }
+}
+
+/// min/avg/max/stddev of the round-trip times, in milliseconds.
+fn rtt_stats(outcomes: &[Outcome]) -> Option<RttJson> {
+    let rtts: Vec<f64> = outcomes
+        .iter()
+        .filter_map(|o| match o {
+            Outcome::Ack(rtt) => Some(rtt.as_secs_f64() * 1000.0),
+            _ => None,
+        })
+        .collect();
+    if rtts.is_empty() {
+        return None;
+    }
+    let n = rtts.len() as f64;
+    let avg = rtts.iter().sum::<f64>() / n;
+    let variance = rtts.iter().map(|r| (r - avg).powi(2)).sum::<f64>() / n;
+    Some(RttJson {
+        min: rtts.iter().copied().fold(f64::INFINITY, f64::min).round() as u128,
+        avg: avg.round() as u128,
+        max: rtts.iter().copied().fold(0.0, f64::max).round() as u128,
+        stddev: variance.sqrt().round() as u128,
+    })
+}
+
 pub struct PingCommand {
     pub destination: DestinationSpec,
+    pub count: u32,
+    pub interval_secs: u64,
+    pub channel: MeshChannel,
     pub timeout_secs: u64,
     pub json: bool,
 }
 
-#[async_trait]
-impl Command for PingCommand {
-    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
-        let (packet_dest, dest_label) = resolve_destination(&self.destination, &ctx.node_db)?;
-
-        let target_node_id = match packet_dest {
-            meshtastic::packet::PacketDestination::Node(node_id) => node_id.id(),
-            _ => bail!("Ping requires a specific node destination"),
-        };
-
+impl PingCommand {
+    /// Sends one empty PRIVATE_APP packet with `want_ack` and waits for the
+    /// routing ACK. The destination's router acknowledges it without any
+    /// module acting on it, so nothing shows up on the node's screen.
+    async fn ping_once(&self, ctx: &mut CommandContext, target: u32) -> anyhow::Result<Outcome> {
         let packet_id: u32 = generate_rand_id();
-        let my_node_num = ctx.node_db.my_node_num();
 
         let mesh_packet = MeshPacket {
-            from: my_node_num,
-            to: target_node_id,
+            from: ctx.node_db.my_node_num(),
+            to: target,
             id: packet_id,
             want_ack: true,
-            channel: 0,
+            channel: self.channel.channel(),
             hop_limit: 3,
             payload_variant: Some(MeshPayload::Decoded(Data {
-                portnum: PortNum::TextMessageApp as i32,
-                payload: "ping".to_string().into_bytes(),
+                portnum: PortNum::PrivateApp as i32,
+                payload: Vec::new(),
                 ..Default::default()
             })),
             ..Default::default()
@@ -58,132 +127,170 @@ impl Command for PingCommand {
 
         let payload_variant = Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet));
 
-        println!(
-            "{} Pinging {} (packet id: {:08x})...",
-            "→".cyan(),
-            dest_label.bold(),
-            packet_id
-        );
-
         let start = Instant::now();
-
         ctx.api.send_to_radio_packet(payload_variant).await?;
-
         let timeout = Duration::from_secs(self.timeout_secs);
 
         loop {
-            let packet =
-                tokio::time::timeout(timeout - start.elapsed(), ctx.packet_receiver.recv()).await;
-
-            match packet {
-                Err(_) => {
-                    if self.json {
-                        let result = PingJson {
-                            dest: dest_label.to_string(),
-                            status: "timeout".to_string(),
-                            rtt_ms: None,
-                            error: Some(format!("Timeout after {}s", self.timeout_secs)),
-                        };
-                        println!("{}", serde_json::to_string_pretty(&result)?);
-                    } else {
-                        println!(
-                            "{} Timeout after {}s — no ACK from {}",
-                            "✗".red(),
-                            self.timeout_secs,
-                            dest_label
-                        );
-                    }
-                    return Ok(());
+            let remaining = timeout.saturating_sub(start.elapsed());
+            let packet = match tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await {
+                Err(_) => return Ok(Outcome::Timeout),
+                Ok(None) => bail!("Disconnected while waiting for ACK"),
+                Ok(Some(packet)) => packet,
+            };
+            let Some(PayloadVariant::Packet(mesh_pkt)) = packet.payload_variant else {
+                continue;
+            };
+            let Some(MeshPayload::Decoded(ref data)) = mesh_pkt.payload_variant else {
+                continue;
+            };
+            if data.portnum != PortNum::RoutingApp as i32 || data.request_id != packet_id {
+                continue;
+            }
+            let Ok(routing_msg) = Routing::decode(data.payload.as_slice()) else {
+                continue;
+            };
+
+            let rtt = start.elapsed();
+            match routing_msg.variant {
+                // Our own node may report an implicit ACK on hearing a relay
+                // repeat the packet; only the destination's ACK counts
+                Some(routing::Variant::ErrorReason(0)) if mesh_pkt.from == target => {
+                    return Ok(Outcome::Ack(rtt));
+                }
+                Some(routing::Variant::ErrorReason(code)) if code != 0 => {
+                    let reason = routing::Error::try_from(code)
+                        .map(|e| format!("{:?}", e))
+                        .unwrap_or_else(|_| format!("code {}", code));
+                    return Ok(Outcome::Nak(reason, rtt));
                 }
-                Ok(None) => {
-                    bail!("Disconnected while waiting for ACK");
+                _ => continue,
+            }
+        }
+    }
+
+    fn print_outcome(&self, outcome: &Outcome, seq: u32, dest_label: &str) {
+        match outcome {
+            Outcome::Ack(rtt) => println!(
+                "{} ACK from {}: seq={} time={:.1}s",
+                "✓".green(),
+                dest_label.bold(),
+                seq,
+                rtt.as_secs_f64()
+            ),
+            Outcome::Nak(reason, rtt) => println!(
+                "{} NAK for {}: seq={} {} ({:.1}s)",
+                "✗".red(),
+                dest_label,
+                seq,
+                reason,
+                rtt.as_secs_f64()
+            ),
+            Outcome::Timeout => println!(
+                "{} Timeout after {}s — no ACK from {}: seq={}",
+                "✗".red(),
+                self.timeout_secs,
+                dest_label,
+                seq
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for PingCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let (packet_dest, dest_label) = resolve_destination(&self.destination, &ctx.node_db)?;
+
+        let target_node_id = match packet_dest {
+            meshtastic::packet::PacketDestination::Node(node_id) => node_id.id(),
+            _ => bail!("Ping requires a specific node destination"),
+        };
+
+        if !self.json {
+            println!(
+                "{} Pinging {} on channel {}{}...",
+                "→".cyan(),
+                dest_label.bold(),
+                self.channel.channel(),
+                if self.count > 1 {
+                    format!(", {} times every {}s", self.count, self.interval_secs)
+                } else {
+                    String::new()
                 }
-                Ok(Some(from_radio)) => {
-                    let Some(PayloadVariant::Packet(mesh_pkt)) = from_radio.payload_variant else {
-                        continue;
-                    };
-
-                    let Some(MeshPayload::Decoded(ref data)) = mesh_pkt.payload_variant else {
-                        continue;
-                    };
-
-                    if data.portnum != PortNum::RoutingApp as i32 || data.request_id != packet_id {
-                        continue;
-                    }
-
-                    let rtt = start.elapsed();
-
-                    let Ok(routing_msg) = Routing::decode(data.payload.as_slice()) else {
-                        println!(
-                            "{} Received routing response but failed to decode",
-                            "?".yellow()
-                        );
-                        return Ok(());
-                    };
-
-                    if self.json {
-                        let result = match routing_msg.variant {
-                            Some(routing::Variant::ErrorReason(0)) => PingJson {
-                                dest: dest_label.to_string(),
-                                status: "ack".to_string(),
-                                rtt_ms: Some(rtt.as_millis()),
-                                error: None,
-                            },
-                            Some(routing::Variant::ErrorReason(code)) => {
-                                let reason = routing::Error::try_from(code)
-                                    .map(|e| format!("{:?}", e))
-                                    .unwrap_or_else(|_| format!("code {}", code));
-                                PingJson {
-                                    dest: dest_label.to_string(),
-                                    status: "nak".to_string(),
-                                    rtt_ms: Some(rtt.as_millis()),
-                                    error: Some(reason),
-                                }
-                            }
-                            _ => PingJson {
-                                dest: dest_label.to_string(),
-                                status: "unknown".to_string(),
-                                rtt_ms: Some(rtt.as_millis()),
-                                error: None,
-                            },
-                        };
-                        println!("{}", serde_json::to_string_pretty(&result)?);
-                    } else {
-                        match routing_msg.variant {
-                            Some(routing::Variant::ErrorReason(0)) => {
-                                println!(
-                                    "{} ACK from {} in {:.1}s",
-                                    "✓".green(),
-                                    dest_label.bold(),
-                                    rtt.as_secs_f64()
-                                );
-                            }
-                            Some(routing::Variant::ErrorReason(code)) => {
-                                let reason = routing::Error::try_from(code)
-                                    .map(|e| format!("{:?}", e))
-                                    .unwrap_or_else(|_| format!("code {}", code));
-                                println!(
-                                    "{} NAK from {}: {} ({:.1}s)",
-                                    "✗".red(),
-                                    dest_label,
-                                    reason,
-                                    rtt.as_secs_f64()
-                                );
-                            }
-                            _ => {
-                                println!(
-                                    "{} Unexpected routing response from {} ({:.1}s)",
-                                    "?".yellow(),
-                                    dest_label,
-                                    rtt.as_secs_f64()
-                                );
-                            }
-                        }
-                    }
-
-                    return Ok(());
+            );
+        }
+
+        let interval = Duration::from_secs(self.interval_secs);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        let mut outcomes = Vec::new();
+        for seq in 1..=self.count {
+            let sent = Instant::now();
+            let outcome = tokio::select! {
+                outcome = self.ping_once(ctx, target_node_id) => outcome?,
+                _ = &mut ctrl_c => break,
+            };
+            if !self.json {
+                self.print_outcome(&outcome, seq, &dest_label);
+            }
+            outcomes.push(outcome);
+
+            // Pings go out one at a time, at most one per interval
+            if seq < self.count {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval.saturating_sub(sent.elapsed())) => {}
+                    _ = &mut ctrl_c => break,
                 }
             }
         }
+
+        let sent = outcomes.len() as u32;
+        let received = outcomes
+            .iter()
+            .filter(|o| matches!(o, Outcome::Ack(_)))
+            .count() as u32;
+        let loss_pct = if sent == 0 {
+            0.0
+        } else {
+            (sent - received) as f64 * 100.0 / sent as f64
+        };
+        let rtt = rtt_stats(&outcomes);
+
+        if self.json {
+            let result = PingJson {
+                dest: dest_label,
+                channel: self.channel.channel(),
+                sent,
+                received,
+                loss_pct: (loss_pct * 10.0).round() / 10.0,
+                rtt_ms: rtt,
+                pings: outcomes
+                    .iter()
+                    .zip(1..)
+                    .map(|(o, seq)| o.to_json(seq, self.timeout_secs))
+                    .collect(),
+            };
+            ctx.print_json(&result)?;
+            return Ok(());
+        }
+
+        println!("\n--- {} ping statistics ---", dest_label);
+        println!(
+            "{} sent, {} received, {:.0}% loss",
+            sent, received, loss_pct
+        );
+        if let Some(rtt) = rtt {
+            let secs = |ms: u128| ms as f64 / 1000.0;
+            println!(
+                "rtt min/avg/max/stddev = {:.2}/{:.2}/{:.2}/{:.2} s",
+                secs(rtt.min),
+                secs(rtt.avg),
+                secs(rtt.max),
+                secs(rtt.stddev)
+            );
+        }
+        Ok(())
     }
 }
@@ -6,6 +6,9 @@ use meshtastic::protobufs::{self, Data, MeshPacket, PortNum};
 use meshtastic::utils::generate_rand_id;
 use serde::Serialize;
 
+use super::dedup::PacketHistory;
+use super::filter::PacketFilter;
+use super::listen::hops_away;
 use super::{Command, CommandContext};
 
 #[derive(Serialize)]
@@ -29,6 +32,7 @@ struct ReplyEventJson {
 const BROADCAST_ADDR: u32 = 0xFFFFFFFF;
 
 pub struct ReplyCommand {
+    pub filter: PacketFilter,
     pub json: bool,
 }
 
@@ -37,6 +41,8 @@ impl Command for ReplyCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
         let my_node = ctx.node_db.my_node_num();
         let json = self.json;
+        let filter = self.filter.resolve(&ctx.node_db)?;
+        let mut history = PacketHistory::new();
 
         if !json {
             println!(
@@ -55,7 +61,7 @@ impl Command for ReplyCommand {
                 continue;
             };
 
-            if data.portnum != PortNum::TextMessageApp as i32 {
+            if data.portnum != PortNum::TextMessageApp as i32 || !filter.matches(mesh_packet) {
                 continue;
             }
 
@@ -69,6 +75,11 @@ impl Command for ReplyCommand {
                 continue;
             }
 
+            // Reply once per message, not once per relayed copy
+            if history.record(mesh_packet).is_some() {
+                continue;
+            }
+
             let text = String::from_utf8(data.payload.clone())
                 .unwrap_or_else(|_| "<invalid UTF-8>".to_string());
 
@@ -76,7 +87,7 @@ impl Command for ReplyCommand {
 
             let snr = mesh_packet.rx_snr;
             let rssi = mesh_packet.rx_rssi;
-            let hops = mesh_packet.hop_start.saturating_sub(mesh_packet.hop_limit);
+            let hops = hops_away(mesh_packet);
 
             let reply_text = format!("ACK: SNR {:.1}dB, RSSI {}dBm, {} hop(s)", snr, rssi, hops);
 
@@ -14,6 +14,8 @@ use meshtastic::Message;
 
 use serde_json::json;
 
+use super::decode::telemetry_to_json;
+use super::geo::{Origin, Relative, Units};
 use super::{resolve_destination, Command, CommandContext, DestinationSpec};
 
 // ── Telemetry type selection ──────────────────────────────────────
@@ -97,11 +99,13 @@ impl Command for RequestTelemetryCommand {
             ..Default::default()
         };
 
-        println!(
-            "{} Requesting telemetry from {}...",
-            "->".cyan(),
-            dest_label.bold()
-        );
+        if !self.json {
+            println!(
+                "{} Requesting telemetry from {}...",
+                "->".cyan(),
+                dest_label.bold()
+            );
+        }
 
         ctx.api
             .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
@@ -115,26 +119,20 @@ impl Command for RequestTelemetryCommand {
         loop {
             let remaining = timeout.saturating_sub(start.elapsed());
             if remaining.is_zero() {
-                println!(
-                    "{} Timeout after {}s — no telemetry from {}",
-                    "x".red(),
-                    self.timeout_secs,
-                    dest_label
-                );
-                return Ok(());
+                return report_timeout(ctx, "telemetry", &dest_label, self.timeout_secs, self.json);
             }
 
             let packet = tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await;
 
             match packet {
                 Err(_) => {
-                    println!(
-                        "{} Timeout after {}s — no telemetry from {}",
-                        "x".red(),
+                    return report_timeout(
+                        ctx,
+                        "telemetry",
+                        &dest_label,
                         self.timeout_secs,
-                        dest_label
+                        self.json,
                     );
-                    return Ok(());
                 }
                 Ok(None) => bail!("Packet receiver closed unexpectedly"),
                 Ok(Some(envelope)) => {
@@ -151,8 +149,12 @@ impl Command for RequestTelemetryCommand {
                     if let Ok(telem) = Telemetry::decode(data.payload.as_slice()) {
                         let elapsed = start.elapsed().as_secs_f64();
                         if self.json {
-                            let val = telemetry_to_json(&telem, &dest_label, elapsed);
-                            println!("{}", serde_json::to_string_pretty(&val)?);
+                            let val = json!({
+                                "source": dest_label,
+                                "rtt_s": elapsed,
+                                "telemetry": telemetry_to_json(&telem),
+                            });
+                            ctx.print_json(&val)?;
                         } else {
                             println!(
                                 "{} Telemetry from {} (in {:.1}s):",
@@ -175,6 +177,8 @@ impl Command for RequestTelemetryCommand {
 pub struct RequestPositionCommand {
     pub destination: DestinationSpec,
     pub timeout_secs: u64,
+    /// Reference point for distances instead of the local node's position
+    pub from: Option<(f64, f64)>,
     pub json: bool,
 }
 
@@ -209,11 +213,13 @@ impl Command for RequestPositionCommand {
             ..Default::default()
         };
 
-        println!(
-            "{} Requesting position from {}...",
-            "->".cyan(),
-            dest_label.bold()
-        );
+        if !self.json {
+            println!(
+                "{} Requesting position from {}...",
+                "->".cyan(),
+                dest_label.bold()
+            );
+        }
 
         ctx.api
             .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
@@ -227,26 +233,20 @@ impl Command for RequestPositionCommand {
         loop {
             let remaining = timeout.saturating_sub(start.elapsed());
             if remaining.is_zero() {
-                println!(
-                    "{} Timeout after {}s — no position from {}",
-                    "x".red(),
-                    self.timeout_secs,
-                    dest_label
-                );
-                return Ok(());
+                return report_timeout(ctx, "position", &dest_label, self.timeout_secs, self.json);
             }
 
             let packet = tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await;
 
             match packet {
                 Err(_) => {
-                    println!(
-                        "{} Timeout after {}s — no position from {}",
-                        "x".red(),
+                    return report_timeout(
+                        ctx,
+                        "position",
+                        &dest_label,
                         self.timeout_secs,
-                        dest_label
+                        self.json,
                     );
-                    return Ok(());
                 }
                 Ok(None) => bail!("Packet receiver closed unexpectedly"),
                 Ok(Some(envelope)) => {
@@ -262,6 +262,9 @@ impl Command for RequestPositionCommand {
 
                     if let Ok(pos) = Position::decode(data.payload.as_slice()) {
                         let elapsed = start.elapsed().as_secs_f64();
+                        let relative =
+                            Origin::resolve(self.from, ctx.node_db.nodes().get(&my_node))
+                                .and_then(|o| o.relative(&pos));
                         if self.json {
                             let lat = pos.latitude_i.unwrap_or(0) as f64 / 1e7;
                             let lon = pos.longitude_i.unwrap_or(0) as f64 / 1e7;
@@ -272,8 +275,11 @@ impl Command for RequestPositionCommand {
                                 "longitude": lon,
                                 "altitude": pos.altitude.unwrap_or(0),
                                 "sats_in_view": pos.sats_in_view,
+                                "distance_m": relative.map(|r| (r.distance_m * 10.0).round() / 10.0),
+                                "bearing_deg": relative.map(|r| (r.bearing_deg * 10.0).round() / 10.0),
+                                "altitude_diff_m": relative.and_then(|r| r.altitude_diff_m),
                             });
-                            println!("{}", serde_json::to_string_pretty(&val)?);
+                            ctx.print_json(&val)?;
                         } else {
                             println!(
                                 "{} Position from {} (in {:.1}s):",
@@ -282,6 +288,9 @@ impl Command for RequestPositionCommand {
                                 elapsed
                             );
                             print_position(&pos);
+                            if let Some(r) = relative {
+                                print_relative(&r, ctx.units);
+                            }
                         }
                         return Ok(());
                     }
@@ -335,11 +344,13 @@ impl Command for RequestMetadataCommand {
             ..Default::default()
         };
 
-        println!(
-            "{} Requesting metadata from {}...",
-            "->".cyan(),
-            dest_label.bold()
-        );
+        if !self.json {
+            println!(
+                "{} Requesting metadata from {}...",
+                "->".cyan(),
+                dest_label.bold()
+            );
+        }
 
         ctx.api
             .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
@@ -353,26 +364,20 @@ impl Command for RequestMetadataCommand {
         loop {
             let remaining = timeout.saturating_sub(start.elapsed());
             if remaining.is_zero() {
-                println!(
-                    "{} Timeout after {}s — no metadata from {}",
-                    "x".red(),
-                    self.timeout_secs,
-                    dest_label
-                );
-                return Ok(());
+                return report_timeout(ctx, "metadata", &dest_label, self.timeout_secs, self.json);
             }
 
             let packet = tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await;
 
             match packet {
                 Err(_) => {
-                    println!(
-                        "{} Timeout after {}s — no metadata from {}",
-                        "x".red(),
+                    return report_timeout(
+                        ctx,
+                        "metadata",
+                        &dest_label,
                         self.timeout_secs,
-                        dest_label
+                        self.json,
                     );
-                    return Ok(());
                 }
                 Ok(None) => bail!("Packet receiver closed unexpectedly"),
                 Ok(Some(envelope)) => {
@@ -414,7 +419,7 @@ impl Command for RequestMetadataCommand {
                                     "has_remote_hardware": meta.has_remote_hardware,
                                     "has_pkc": meta.has_pkc,
                                 });
-                                println!("{}", serde_json::to_string_pretty(&val)?);
+                                ctx.print_json(&val)?;
                             } else {
                                 println!(
                                     "{} Metadata from {} (in {:.1}s):",
@@ -652,6 +657,26 @@ fn print_position(pos: &Position) {
     }
 }
 
+fn print_relative(relative: &Relative, units: Units) {
+    println!(
+        "  {:<20} {}",
+        "distance:".dimmed(),
+        units.format_distance(relative.distance_m)
+    );
+    println!(
+        "  {:<20} {}",
+        "bearing:".dimmed(),
+        relative.format_bearing()
+    );
+    if let Some(diff) = relative.altitude_diff_m {
+        println!(
+            "  {:<20} {}",
+            "altitude_diff:".dimmed(),
+            units.format_height_diff(diff as f64)
+        );
+    }
+}
+
 fn print_metadata(meta: &protobufs::DeviceMetadata) {
     let hw_model = HardwareModel::try_from(meta.hw_model)
         .map(|m| m.as_str_name().to_string())
@@ -685,65 +710,28 @@ fn print_metadata(meta: &protobufs::DeviceMetadata) {
     println!("  {:<28} {}", "has_pkc:".dimmed(), meta.has_pkc);
 }
 
-fn telemetry_to_json(telem: &Telemetry, source: &str, rtt_s: f64) -> serde_json::Value {
-    let data = match &telem.variant {
-        Some(telemetry::Variant::DeviceMetrics(m)) => json!({
-            "type": "device",
-            "battery_level": m.battery_level,
-            "voltage": m.voltage,
-            "channel_utilization": m.channel_utilization,
-            "air_util_tx": m.air_util_tx,
-            "uptime_seconds": m.uptime_seconds,
-        }),
-        Some(telemetry::Variant::EnvironmentMetrics(m)) => json!({
-            "type": "environment",
-            "temperature": m.temperature,
-            "relative_humidity": m.relative_humidity,
-            "barometric_pressure": m.barometric_pressure,
-        }),
-        Some(telemetry::Variant::PowerMetrics(m)) => json!({
-            "type": "power",
-            "ch1_voltage": m.ch1_voltage,
-            "ch1_current": m.ch1_current,
-            "ch2_voltage": m.ch2_voltage,
-            "ch2_current": m.ch2_current,
-            "ch3_voltage": m.ch3_voltage,
-            "ch3_current": m.ch3_current,
-        }),
-        Some(telemetry::Variant::AirQualityMetrics(m)) => json!({
-            "type": "air_quality",
-            "pm10_standard": m.pm10_standard,
-            "pm25_standard": m.pm25_standard,
-            "pm100_standard": m.pm100_standard,
-            "co2": m.co2,
-        }),
-        Some(telemetry::Variant::LocalStats(m)) => json!({
-            "type": "local_stats",
-            "uptime_seconds": m.uptime_seconds,
-            "channel_utilization": m.channel_utilization,
-            "air_util_tx": m.air_util_tx,
-            "packets_tx": m.num_packets_tx,
-            "packets_rx": m.num_packets_rx,
-            "packets_rx_bad": m.num_packets_rx_bad,
-            "online_nodes": m.num_online_nodes,
-            "total_nodes": m.num_total_nodes,
-        }),
-        Some(telemetry::Variant::HealthMetrics(m)) => json!({
-            "type": "health",
-            "heart_bpm": m.heart_bpm,
-            "sp_o2": m.sp_o2,
-        }),
-        Some(telemetry::Variant::HostMetrics(m)) => json!({
-            "type": "host",
-            "uptime_seconds": m.uptime_seconds,
-            "freemem_bytes": m.freemem_bytes,
-            "diskfree1_bytes": m.diskfree1_bytes,
-        }),
-        None => json!({"type": "empty"}),
-    };
-    json!({
-        "source": source,
-        "rtt_s": rtt_s,
-        "telemetry": data,
-    })
+/// Reports a request that got no answer in time. With `--json` this is a
+/// JSON object, so fanned-out results stay one valid array.
+fn report_timeout(
+    ctx: &mut CommandContext,
+    what: &str,
+    dest_label: &str,
+    timeout_secs: u64,
+    json: bool,
+) -> anyhow::Result<()> {
+    if json {
+        return ctx.print_json(&json!({
+            "source": dest_label,
+            "timeout": true,
+            "timeout_secs": timeout_secs,
+        }));
+    }
+    println!(
+        "{} Timeout after {}s — no {} from {}",
+        "x".red(),
+        timeout_secs,
+        what,
+        dest_label
+    );
+    Ok(())
 }
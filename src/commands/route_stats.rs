@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use colored::Colorize;
+use crossterm::{cursor, execute, terminal};
+use serde::Serialize;
+
+use super::parsers::parse_node_id;
+use super::survey::Route;
+use crate::node_db::NodeDb;
+
+// ── Statistics ────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct SnrStats {
+    count: u32,
+    min: f32,
+    max: f32,
+    sum: f32,
+}
+
+#[derive(Serialize)]
+struct SnrJson {
+    min: f32,
+    avg: f32,
+    max: f32,
+}
+
+impl SnrStats {
+    fn add(&mut self, snr: f32) {
+        if self.count == 0 {
+            self.min = snr;
+            self.max = snr;
+        } else {
+            self.min = self.min.min(snr);
+            self.max = self.max.max(snr);
+        }
+        self.sum += snr;
+        self.count += 1;
+    }
+
+    fn to_json(&self) -> Option<SnrJson> {
+        let round = |v: f32| (v * 100.0).round() / 100.0;
+        (self.count > 0).then(|| SnrJson {
+            min: round(self.min),
+            avg: round(self.sum / self.count as f32),
+            max: round(self.max),
+        })
+    }
+
+    fn format(&self) -> String {
+        match self.to_json() {
+            Some(s) => format!("{:.1} / {:.1} / {:.1}", s.min, s.avg, s.max),
+            None => "-".into(),
+        }
+    }
+}
+
+/// What was seen of one node over all answered traces.
+#[derive(Default)]
+struct HopStats {
+    /// Traces whose forward path went through (or ended at) this node
+    towards: u32,
+    /// Traces whose return path went through (or ended at) this node
+    back: u32,
+    /// Sum of the node's position on each path, for ordering
+    towards_index: usize,
+    back_index: usize,
+    /// SNR at which this node received the request
+    snr_towards: SnrStats,
+    /// SNR at which this node received the answer
+    snr_back: SnrStats,
+}
+
+/// Per-hop statistics over repeated traces to one node.
+pub struct RouteStats {
+    target: String,
+    local: String,
+    pub sent: u32,
+    pub received: u32,
+    rtt_ms: Vec<u64>,
+    hops: BTreeMap<String, HopStats>,
+    /// How often each forward relay sequence was taken
+    routes: BTreeMap<Vec<String>, u32>,
+}
+
+impl RouteStats {
+    pub fn new(target: u32, local: u32) -> Self {
+        Self {
+            target: format!("!{:08x}", target),
+            local: format!("!{:08x}", local),
+            sent: 0,
+            received: 0,
+            rtt_ms: Vec::new(),
+            hops: BTreeMap::new(),
+            routes: BTreeMap::new(),
+        }
+    }
+
+    /// Records one trace; `None` when it timed out.
+    pub fn record(&mut self, route: Option<&Route>) {
+        self.sent += 1;
+        let Some(route) = route else {
+            return;
+        };
+        self.received += 1;
+        self.rtt_ms.push(route.rtt_ms);
+        *self.routes.entry(route.towards.clone()).or_default() += 1;
+
+        let towards = route.towards.iter().chain([&self.target]);
+        for (i, node) in towards.enumerate() {
+            let hop = self.hops.entry(node.clone()).or_default();
+            hop.towards += 1;
+            hop.towards_index += i;
+            if let Some(Some(snr)) = route.snr_towards.get(i) {
+                hop.snr_towards.add(*snr);
+            }
+        }
+        let Some(back) = &route.back else {
+            return;
+        };
+        for (i, node) in back.iter().chain([&self.local]).enumerate() {
+            let hop = self.hops.entry(node.clone()).or_default();
+            hop.back += 1;
+            hop.back_index += i;
+            if let Some(Some(snr)) = route.snr_back.get(i) {
+                hop.snr_back.add(*snr);
+            }
+        }
+    }
+
+    pub fn loss_pct(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        (self.sent - self.received) as f64 * 100.0 / self.sent as f64
+    }
+
+    /// Forward relays by their usual position, then the target, then relays
+    /// only seen on the way back, then our own node.
+    fn ordered(&self) -> Vec<(&String, &HopStats)> {
+        let mut hops: Vec<_> = self.hops.iter().collect();
+        let key = |(node, hop): &(&String, &HopStats)| {
+            let mean = |sum: usize, n: u32| sum as f64 / n.max(1) as f64;
+            if **node == self.local {
+                (3, 0.0)
+            } else if **node == self.target {
+                (1, 0.0)
+            } else if hop.towards > 0 {
+                (0, mean(hop.towards_index, hop.towards))
+            } else {
+                (2, mean(hop.back_index, hop.back))
+            }
+        };
+        hops.sort_by(|a, b| {
+            let (ka, kb) = (key(a), key(b));
+            ka.0.cmp(&kb.0).then(ka.1.total_cmp(&kb.1))
+        });
+        hops
+    }
+
+    fn rtt_summary(&self) -> Option<(u64, u64, u64)> {
+        let min = *self.rtt_ms.iter().min()?;
+        let max = *self.rtt_ms.iter().max()?;
+        let avg = self.rtt_ms.iter().sum::<u64>() / self.rtt_ms.len() as u64;
+        Some((min, avg, max))
+    }
+}
+
+// ── Output ────────────────────────────────────────────────────────
+
+fn node_label(node_db: &NodeDb, id: &str) -> String {
+    let num = parse_node_id(id);
+    match num.and_then(|n| node_db.node_name(n)) {
+        Some(name) if !name.is_empty() => format!("{} ({})", id, name),
+        _ => id.to_string(),
+    }
+}
+
+fn pct(count: u32, of: u32) -> String {
+    if count == 0 {
+        return "-".into();
+    }
+    format!("{}%", count * 100 / of.max(1))
+}
+
+/// Redraws the statistics table in place, like `mtr`.
+pub fn render(
+    stats: &RouteStats,
+    node_db: &NodeDb,
+    dest_label: &str,
+    count: u32,
+) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout();
+    execute!(
+        stdout,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+
+    writeln!(
+        stdout,
+        "{} {}  {} of {} sent, {} answered, {:.0}% loss",
+        "Traceroute to".bold(),
+        dest_label.bold(),
+        stats.sent,
+        count,
+        stats.received,
+        stats.loss_pct()
+    )?;
+    if let Some((min, avg, max)) = stats.rtt_summary() {
+        writeln!(
+            stdout,
+            "RTT min/avg/max {:.1} / {:.1} / {:.1} s",
+            min as f64 / 1000.0,
+            avg as f64 / 1000.0,
+            max as f64 / 1000.0
+        )?;
+    }
+    writeln!(stdout)?;
+
+    let header = format!(
+        "{:<36} {:>5} {:>5}  {:>22}  {:>22}",
+        "NODE", "FWD", "BACK", "SNR TOWARDS min/avg/max", "SNR BACK min/avg/max"
+    );
+    writeln!(stdout, "{}", header.bold())?;
+    writeln!(stdout, "{}", "-".repeat(header.chars().count()))?;
+    for (node, hop) in stats.ordered() {
+        writeln!(
+            stdout,
+            "{:<36} {:>5} {:>5}  {:>22}  {:>22}",
+            node_label(node_db, node),
+            pct(hop.towards, stats.received),
+            pct(hop.back, stats.received),
+            hop.snr_towards.format(),
+            hop.snr_back.format()
+        )?;
+    }
+
+    if stats.routes.len() > 1 {
+        writeln!(stdout, "\n{}", "Forward routes:".bold())?;
+        let mut routes: Vec<_> = stats.routes.iter().collect();
+        routes.sort_by(|a, b| b.1.cmp(a.1));
+        for (relays, times) in routes {
+            let path = if relays.is_empty() {
+                "direct".to_string()
+            } else {
+                relays
+                    .iter()
+                    .map(|r| node_label(node_db, r))
+                    .collect::<Vec<_>>()
+                    .join(" → ")
+            };
+            writeln!(stdout, "  {:>3}×  {}", times, path)?;
+        }
+    }
+
+    writeln!(stdout)?;
+    writeln!(
+        stdout,
+        "{}",
+        "FWD/BACK: share of answered traces through the node | Press Ctrl+C to stop".dimmed()
+    )?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct RttJson {
+    min: u64,
+    avg: u64,
+    max: u64,
+}
+
+#[derive(Serialize)]
+struct HopJson {
+    node: String,
+    name: Option<String>,
+    towards: u32,
+    back: u32,
+    snr_towards: Option<SnrJson>,
+    snr_back: Option<SnrJson>,
+}
+
+#[derive(Serialize)]
+struct RouteCountJson<'a> {
+    route: &'a [String],
+    count: u32,
+}
+
+#[derive(Serialize)]
+pub struct StatsJson<'a> {
+    dest: &'a str,
+    sent: u32,
+    received: u32,
+    loss_pct: f64,
+    rtt_ms: Option<RttJson>,
+    hops: Vec<HopJson>,
+    routes: Vec<RouteCountJson<'a>>,
+}
+
+pub fn json_report<'a>(
+    stats: &'a RouteStats,
+    node_db: &NodeDb,
+    dest_label: &'a str,
+) -> StatsJson<'a> {
+    StatsJson {
+        dest: dest_label,
+        sent: stats.sent,
+        received: stats.received,
+        loss_pct: (stats.loss_pct() * 10.0).round() / 10.0,
+        rtt_ms: stats
+            .rtt_summary()
+            .map(|(min, avg, max)| RttJson { min, avg, max }),
+        hops: stats
+            .ordered()
+            .into_iter()
+            .map(|(node, hop)| HopJson {
+                node: node.clone(),
+                name: parse_node_id(node)
+                    .and_then(|n| node_db.node_name(n))
+                    .map(String::from),
+                towards: hop.towards,
+                back: hop.back,
+                snr_towards: hop.snr_towards.to_json(),
+                snr_back: hop.snr_back.to_json(),
+            })
+            .collect(),
+        routes: stats
+            .routes
+            .iter()
+            .map(|(route, &count)| RouteCountJson { route, count })
+            .collect(),
+    }
+}
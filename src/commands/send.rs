@@ -3,6 +3,7 @@ use std::time::{Duration, Instant};
 use anyhow::bail;
 use async_trait::async_trait;
 use colored::Colorize;
+use meshtastic::packet::PacketDestination;
 use meshtastic::protobufs::from_radio::PayloadVariant;
 use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
 use meshtastic::protobufs::{self, routing, Data, MeshPacket, PortNum, Routing};
@@ -11,12 +12,17 @@ use meshtastic::utils::generate_rand_id;
 use meshtastic::Message;
 use serde::Serialize;
 
+use super::messages::{store_or_warn, AckStatus, StoredMessage};
+use super::parts::{split_message, MAX_PARTS, MAX_TEXT_BYTES};
 use super::{resolve_destination, Command, CommandContext, DestinationSpec};
+use crate::error::CliError;
 
 #[derive(Serialize)]
 struct SendResultJson {
     dest: String,
     channel: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    part: Option<String>,
     status: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     rtt_ms: Option<u128>,
@@ -26,118 +32,288 @@ struct SendResultJson {
 
 pub struct SendCommand {
     pub message: String,
-    pub destination: DestinationSpec,
+    /// One entry per recipient; a single `Broadcast` when none was given
+    pub destinations: Vec<DestinationSpec>,
     pub channel: MeshChannel,
     pub wait_ack: bool,
     pub timeout_secs: u64,
     pub private: bool,
+    /// Packet ID of the message this one answers
+    pub reply_id: Option<u32>,
+    /// `message` is an emoji reaction to `reply_id` rather than text
+    pub reaction: bool,
+    pub part_delay_secs: u64,
     pub json: bool,
 }
 
 #[async_trait]
 impl Command for SendCommand {
     async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
-        let (packet_dest, dest_label) = resolve_destination(&self.destination, &ctx.node_db)?;
-
-        if self.wait_ack && matches!(self.destination, DestinationSpec::Broadcast) {
+        if self.wait_ack
+            && self
+                .destinations
+                .iter()
+                .any(|d| matches!(d, DestinationSpec::Broadcast))
+        {
             bail!("--ack requires a specific destination (use --dest or --to)");
         }
 
-        let needs_manual_packet = self.wait_ack || self.private;
-
-        if needs_manual_packet {
+        // Resolve every recipient before sending anything, so a typo in the
+        // last name does not leave the message half delivered
+        let mut recipients: Vec<(u32, String)> = Vec::new();
+        for spec in &self.destinations {
+            let (packet_dest, dest_label) = resolve_destination(spec, &ctx.node_db)?;
             let target_id = match packet_dest {
-                meshtastic::packet::PacketDestination::Node(node_id) => node_id.id(),
+                PacketDestination::Node(node_id) => node_id.id(),
                 _ => 0xFFFFFFFF,
             };
+            if !recipients.iter().any(|(id, _)| *id == target_id) {
+                recipients.push((target_id, dest_label));
+            }
+        }
 
-            let packet_id = generate_rand_id();
-            let my_node = ctx.node_db.my_node_num();
-
-            let portnum = if self.private {
-                PortNum::PrivateApp
-            } else {
-                PortNum::TextMessageApp
-            };
+        // Only text messages are split; other payloads are sent as they are
+        let parts = if self.private || self.reaction {
+            if self.message.len() > MAX_TEXT_BYTES {
+                bail!(
+                    "Message is {} bytes; payloads sent with --private or --react are limited to {} bytes",
+                    self.message.len(),
+                    MAX_TEXT_BYTES
+                );
+            }
+            vec![self.message.clone()]
+        } else {
+            split_message(&self.message, MAX_TEXT_BYTES)
+        };
+        let total = parts.len();
+        if total > MAX_PARTS {
+            bail!(
+                "Message is {} bytes and would take {} parts; at most {} parts are sent",
+                self.message.len(),
+                total,
+                MAX_PARTS
+            );
+        }
+        if total > 1 && !self.json {
+            println!(
+                "{} Message is {} bytes, sending it in {} parts",
+                "->".cyan(),
+                self.message.len(),
+                total
+            );
+        }
 
-            let mesh_packet = MeshPacket {
-                from: my_node,
-                to: target_id,
-                id: packet_id,
-                want_ack: true,
-                channel: self.channel.channel(),
-                hop_limit: 3,
-                payload_variant: Some(MeshPayload::Decoded(Data {
-                    portnum: portnum as i32,
-                    payload: self.message.clone().into_bytes(),
-                    ..Default::default()
-                })),
-                ..Default::default()
-            };
+        let mut results = Vec::new();
+        let mut delivered_to = Vec::new();
+        for (r, (target_id, dest_label)) in recipients.iter().enumerate() {
+            let mut delivered = true;
+            for (i, text) in parts.iter().enumerate() {
+                if r > 0 || i > 0 {
+                    tokio::time::sleep(Duration::from_secs(self.part_delay_secs)).await;
+                }
+                let part = (total > 1).then(|| format!("{}/{}", i + 1, total));
+                // Only the first part answers the --reply-to message
+                let reply_id = if i == 0 { self.reply_id } else { None };
 
-            ctx.api
-                .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
-                    mesh_packet,
-                )))
-                .await?;
+                let result = self
+                    .send_part(ctx, text, reply_id, *target_id, dest_label, part)
+                    .await?;
+                delivered = !self.wait_ack || result.status == "ack";
+                results.push(result);
 
-            if !self.json {
-                let port_label = if self.private { " (private)" } else { "" };
-                println!(
-                    "{} Message{} sent to {} on channel {}",
-                    "ok".green(),
-                    port_label,
-                    dest_label.bold(),
-                    self.channel.channel()
-                );
+                if !delivered && i + 1 < total {
+                    if !self.json {
+                        println!(
+                            "{} Part {}/{} was not acknowledged by {}; not sending the remaining {}",
+                            "x".red(),
+                            i + 1,
+                            total,
+                            dest_label,
+                            total - i - 1
+                        );
+                    }
+                    break;
+                }
             }
+            delivered_to.push(delivered);
+        }
 
-            if self.wait_ack {
-                if !self.json {
-                    println!("{} Waiting for ACK...", "->".cyan());
+        if self.json {
+            // Always an array, so scripts need not check for one recipient
+            println!("{}", serde_json::to_string_pretty(&results)?);
+        } else if self.wait_ack && recipients.len() > 1 {
+            let acked = delivered_to.iter().filter(|&&d| d).count();
+            println!(
+                "\n{} Delivered to {} of {} recipients",
+                "->".cyan(),
+                acked,
+                recipients.len()
+            );
+            for ((_, dest_label), delivered) in recipients.iter().zip(&delivered_to) {
+                if !delivered {
+                    println!("  {} {}", "x".red(), dest_label);
                 }
-                wait_for_ack(ctx, packet_id, self.timeout_secs, &dest_label, self.json).await?;
-            } else if self.json {
-                let result = SendResultJson {
-                    dest: dest_label.to_string(),
-                    channel: self.channel.channel(),
-                    status: "sent".to_string(),
-                    rtt_ms: None,
-                    error: None,
-                };
-                println!("{}", serde_json::to_string_pretty(&result)?);
             }
+        }
+
+        Ok(())
+    }
+}
+
+impl SendCommand {
+    /// Sends one packet, waits for its ACK when `--ack` is set, and records
+    /// it in the message store.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_part(
+        &self,
+        ctx: &mut CommandContext,
+        text: &str,
+        reply_id: Option<u32>,
+        target_id: u32,
+        dest_label: &str,
+        part: Option<String>,
+    ) -> anyhow::Result<SendResultJson> {
+        let part_label = part
+            .as_ref()
+            .map(|p| format!(" ({})", p))
+            .unwrap_or_default();
+        let mut result = SendResultJson {
+            dest: dest_label.to_string(),
+            channel: self.channel.channel(),
+            part,
+            status: "sent".to_string(),
+            rtt_ms: None,
+            error: None,
+        };
+
+        let packet_id = generate_rand_id();
+        let my_node = ctx.node_db.my_node_num();
+
+        let portnum = if self.private {
+            PortNum::PrivateApp
         } else {
-            ctx.api
-                .send_text(
-                    &mut ctx.router,
-                    self.message.clone(),
-                    packet_dest,
-                    true,
-                    self.channel,
-                )
-                .await?;
-
-            if self.json {
-                let result = SendResultJson {
-                    dest: dest_label.to_string(),
-                    channel: self.channel.channel(),
-                    status: "sent".to_string(),
-                    rtt_ms: None,
-                    error: None,
-                };
-                println!("{}", serde_json::to_string_pretty(&result)?);
+            PortNum::TextMessageApp
+        };
+
+        let mesh_packet = MeshPacket {
+            from: my_node,
+            to: target_id,
+            id: packet_id,
+            want_ack: true,
+            channel: self.channel.channel(),
+            hop_limit: 3,
+            payload_variant: Some(MeshPayload::Decoded(Data {
+                portnum: portnum as i32,
+                payload: text.as_bytes().to_vec(),
+                reply_id: reply_id.unwrap_or(0),
+                emoji: self.reaction as u32,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        ctx.api
+            .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(
+                mesh_packet,
+            )))
+            .await?;
+
+        if !self.json {
+            let kind = if self.reaction {
+                "Reaction"
+            } else if self.reply_id.is_some() {
+                "Reply"
             } else {
-                println!(
-                    "{} Message sent to {} on channel {}",
-                    "ok".green(),
-                    dest_label.bold(),
-                    self.channel.channel()
-                );
+                "Message"
+            };
+            let port_label = if self.private { " (private)" } else { "" };
+            println!(
+                "{} {}{}{} sent to {} on channel {}",
+                "ok".green(),
+                kind,
+                port_label,
+                part_label,
+                dest_label.bold(),
+                self.channel.channel()
+            );
+        }
+
+        let status = if self.wait_ack {
+            if !self.json {
+                println!("{} Waiting for ACK...", "->".cyan());
             }
+            let ack =
+                wait_for_ack(ctx, packet_id, self.timeout_secs, dest_label, self.json).await?;
+            result.status = ack.json_status().to_string();
+            result.rtt_ms = ack.rtt.map(|rtt| rtt.as_millis());
+            result.error = ack.error;
+            ack.status
+        } else {
+            AckStatus::Sent
+        };
+        if !self.private {
+            self.store(ctx, target_id, packet_id, text, reply_id, status);
         }
 
-        Ok(())
+        Ok(result)
+    }
+
+    fn store(
+        &self,
+        ctx: &CommandContext,
+        to: u32,
+        id: u32,
+        text: &str,
+        reply_id: Option<u32>,
+        status: AckStatus,
+    ) {
+        store_or_warn(&StoredMessage {
+            reply_id,
+            emoji: self.reaction,
+            ..StoredMessage::sent(&ctx.node_db, to, self.channel.channel(), id, text, status)
+        });
+    }
+}
+
+/// Returns the message text: read from `file`, from stdin when `message` is
+/// `-`, or `message` itself. One trailing newline is dropped from read text.
+pub fn read_message_text(message: Option<&str>, file: Option<&str>) -> Result<String, CliError> {
+    let read = match (message, file) {
+        (_, Some(path)) => std::fs::read_to_string(path).map_err(|e| {
+            CliError::InvalidArgument(format!("Cannot read message file '{}': {}", path, e))
+        })?,
+        (Some("-"), None) => std::io::read_to_string(std::io::stdin()).map_err(|e| {
+            CliError::InvalidArgument(format!("Cannot read message from stdin: {}", e))
+        })?,
+        (Some(text), None) => return Ok(text.to_string()),
+        (None, None) => String::new(),
+    };
+
+    let text = read
+        .strip_suffix('\n')
+        .map(|t| t.strip_suffix('\r').unwrap_or(t))
+        .unwrap_or(&read);
+    if text.trim().is_empty() {
+        return Err(CliError::InvalidArgument("Message is empty".into()));
+    }
+    Ok(text.to_string())
+}
+
+/// What came back for one packet sent with `want_ack`.
+struct AckResult {
+    status: AckStatus,
+    rtt: Option<Duration>,
+    error: Option<String>,
+}
+
+impl AckResult {
+    fn json_status(&self) -> &'static str {
+        match self.status {
+            AckStatus::Acked => "ack",
+            AckStatus::Nak => "nak",
+            AckStatus::Timeout => "timeout",
+            AckStatus::Sent => "unknown",
+        }
     }
 }
 
@@ -147,37 +323,34 @@ async fn wait_for_ack(
     timeout_secs: u64,
     dest_label: &str,
     json: bool,
-) -> anyhow::Result<()> {
-    let channel = 0u32; // ACK tracking doesn't know channel; use 0
+) -> anyhow::Result<AckResult> {
     let start = Instant::now();
     let timeout = Duration::from_secs(timeout_secs);
+    let timed_out = AckResult {
+        status: AckStatus::Timeout,
+        rtt: None,
+        error: Some(format!("Timeout after {}s", timeout_secs)),
+    };
 
     loop {
         let remaining = timeout.saturating_sub(start.elapsed());
         if remaining.is_zero() {
-            println!(
-                "{} Timeout after {}s — no ACK from {}",
-                "x".red(),
-                timeout_secs,
-                dest_label
-            );
-            return Ok(());
+            if !json {
+                println!(
+                    "{} Timeout after {}s — no ACK from {}",
+                    "x".red(),
+                    timeout_secs,
+                    dest_label
+                );
+            }
+            return Ok(timed_out);
         }
 
         let packet = tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await;
 
         match packet {
             Err(_) => {
-                if json {
-                    let result = SendResultJson {
-                        dest: dest_label.to_string(),
-                        channel,
-                        status: "timeout".to_string(),
-                        rtt_ms: None,
-                        error: Some(format!("Timeout after {}s", timeout_secs)),
-                    };
-                    println!("{}", serde_json::to_string_pretty(&result)?);
-                } else {
+                if !json {
                     println!(
                         "{} Timeout after {}s — no ACK from {}",
                         "x".red(),
@@ -185,7 +358,7 @@ async fn wait_for_ack(
                         dest_label
                     );
                 }
-                return Ok(());
+                return Ok(timed_out);
             }
             Ok(None) => bail!("Disconnected while waiting for ACK"),
             Ok(Some(from_radio)) => {
@@ -201,46 +374,44 @@ async fn wait_for_ack(
 
                 let rtt = start.elapsed();
                 let Ok(routing_msg) = Routing::decode(data.payload.as_slice()) else {
-                    println!(
-                        "{} Received routing response but failed to decode",
-                        "?".yellow()
-                    );
-                    return Ok(());
+                    if !json {
+                        println!(
+                            "{} Received routing response but failed to decode",
+                            "?".yellow()
+                        );
+                    }
+                    return Ok(AckResult {
+                        status: AckStatus::Sent,
+                        rtt: Some(rtt),
+                        error: Some("Failed to decode routing response".to_string()),
+                    });
                 };
 
-                if json {
-                    let result = match routing_msg.variant {
-                        Some(routing::Variant::ErrorReason(0)) => SendResultJson {
-                            dest: dest_label.to_string(),
-                            channel,
-                            status: "ack".to_string(),
-                            rtt_ms: Some(rtt.as_millis()),
-                            error: None,
-                        },
-                        Some(routing::Variant::ErrorReason(code)) => {
-                            let reason = routing::Error::try_from(code)
+                let result = match routing_msg.variant {
+                    Some(routing::Variant::ErrorReason(0)) => AckResult {
+                        status: AckStatus::Acked,
+                        rtt: Some(rtt),
+                        error: None,
+                    },
+                    Some(routing::Variant::ErrorReason(code)) => AckResult {
+                        status: AckStatus::Nak,
+                        rtt: Some(rtt),
+                        error: Some(
+                            routing::Error::try_from(code)
                                 .map(|e| format!("{:?}", e))
-                                .unwrap_or_else(|_| format!("code {}", code));
-                            SendResultJson {
-                                dest: dest_label.to_string(),
-                                channel,
-                                status: "nak".to_string(),
-                                rtt_ms: Some(rtt.as_millis()),
-                                error: Some(reason),
-                            }
-                        }
-                        _ => SendResultJson {
-                            dest: dest_label.to_string(),
-                            channel,
-                            status: "unknown".to_string(),
-                            rtt_ms: Some(rtt.as_millis()),
-                            error: None,
-                        },
-                    };
-                    println!("{}", serde_json::to_string_pretty(&result)?);
-                } else {
-                    match routing_msg.variant {
-                        Some(routing::Variant::ErrorReason(0)) => {
+                                .unwrap_or_else(|_| format!("code {}", code)),
+                        ),
+                    },
+                    _ => AckResult {
+                        status: AckStatus::Sent,
+                        rtt: Some(rtt),
+                        error: None,
+                    },
+                };
+
+                if !json {
+                    match result.status {
+                        AckStatus::Acked => {
                             println!(
                                 "{} ACK from {} in {:.1}s",
                                 "ok".green(),
@@ -248,15 +419,12 @@ async fn wait_for_ack(
                                 rtt.as_secs_f64()
                             );
                         }
-                        Some(routing::Variant::ErrorReason(code)) => {
-                            let reason = routing::Error::try_from(code)
-                                .map(|e| format!("{:?}", e))
-                                .unwrap_or_else(|_| format!("code {}", code));
+                        AckStatus::Nak => {
                             println!(
                                 "{} NAK from {}: {} ({:.1}s)",
                                 "x".red(),
                                 dest_label,
-                                reason,
+                                result.error.as_deref().unwrap_or_default(),
                                 rtt.as_secs_f64()
                             );
                         }
@@ -270,7 +438,7 @@ async fn wait_for_ack(
                     }
                 }
 
-                return Ok(());
+                return Ok(result);
             }
         }
     }
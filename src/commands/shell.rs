@@ -6,11 +6,17 @@ use rustyline::completion::{Completer, Pair};
 use rustyline::error::ReadlineError;
 use rustyline::highlight::Highlighter;
 use rustyline::hint::Hinter;
+use rustyline::history::History;
 use rustyline::validate::Validator;
 use rustyline::{Context, Editor, Helper};
 
-use super::{Command, CommandContext};
-use crate::cli::Commands;
+use super::destination::AmbiguousNode;
+use super::history::{handle_node_history, HistoryOptions};
+use super::messages::{handle_messages, store_pending, MessageRecorder};
+use super::snapshot::handle_snapshot_diff;
+use super::topology::handle_topology_log;
+use super::{handle_from_snapshot, Command, CommandContext};
+use crate::cli::{Commands, NodesAction, SnapshotAction};
 use crate::commands::create_command;
 
 pub struct ShellCommand;
@@ -93,10 +99,22 @@ impl Command for ShellCommand {
         let mut rl = Editor::new()?;
         rl.set_helper(Some(helper));
         let _ = rl.load_history(&history_path);
+        let mut recorder = MessageRecorder::new();
 
         loop {
             match rl.readline("mesh> ") {
                 Ok(line) => {
+                    // Keep messages that arrived while waiting at the prompt
+                    let stored = store_pending(ctx, &mut recorder);
+                    if stored > 0 {
+                        println!(
+                            "{} {} new message(s), see {}",
+                            "->".cyan(),
+                            stored,
+                            "messages unread".bold()
+                        );
+                    }
+
                     let trimmed = line.trim();
                     if trimmed.is_empty() {
                         continue;
@@ -140,20 +158,68 @@ impl Command for ShellCommand {
                     let mut full_args = vec!["mttctl".to_string()];
                     full_args.extend(args);
 
-                    match parse_shell_command(&full_args) {
-                        Ok(cmd_enum) => match create_command(&cmd_enum, false) {
-                            Ok(command) => {
-                                if let Err(e) = command.execute(ctx).await {
-                                    println!("{} {}", "Error:".red(), e);
-                                }
+                    // When a node name is ambiguous, ask which node was meant
+                    // and run the command again with its ID
+                    loop {
+                        let result = match parse_shell_command(&full_args) {
+                            Ok(Commands::Messages { action, reassemble }) => {
+                                handle_messages(&action, reassemble, false)
+                            }
+                            Ok(Commands::Nodes {
+                                action:
+                                    Some(NodesAction::History {
+                                        node,
+                                        since,
+                                        gap,
+                                        last,
+                                    }),
+                                ..
+                            }) => {
+                                let options = HistoryOptions {
+                                    node: node.as_deref(),
+                                    since: since.as_deref(),
+                                    gap: &gap,
+                                    last,
+                                };
+                                handle_node_history(&options, ctx.units, false)
                             }
-                            Err(e) => {
-                                println!("{} {}", "Error:".red(), e);
+                            Ok(Commands::Nodes {
+                                action:
+                                    Some(NodesAction::Snapshot {
+                                        action: SnapshotAction::Diff { old, new },
+                                    }),
+                                ..
+                            }) => handle_snapshot_diff(&old, &new, false),
+                            Ok(Commands::Topology {
+                                from_log: Some(file),
+                                format,
+                                ..
+                            }) => handle_topology_log(&file, &(&format).into(), false),
+                            Ok(cmd_enum) => match handle_from_snapshot(&cmd_enum, false, ctx.units)
+                            {
+                                Ok(true) => Ok(()),
+                                Ok(false) => match create_command(&cmd_enum, false) {
+                                    Ok(command) => command.execute(ctx).await,
+                                    Err(e) => Err(e.into()),
+                                },
+                                Err(e) => Err(e),
+                            },
+                            Err(msg) => {
+                                println!("{}", msg);
+                                break;
+                            }
+                        };
+                        let Err(e) = result else {
+                            break;
+                        };
+                        if let Some(ambiguous) = e.downcast_ref::<AmbiguousNode>() {
+                            if let Some(num) = pick_node(&mut rl, ambiguous) {
+                                full_args = replace_node_arg(&full_args, &ambiguous.name, num);
+                                continue;
                             }
-                        },
-                        Err(msg) => {
-                            println!("{}", msg);
                         }
+                        println!("{} {}", "Error:".red(), e);
+                        break;
                     }
 
                     println!();
@@ -173,11 +239,68 @@ impl Command for ShellCommand {
             }
         }
 
+        store_pending(ctx, &mut recorder);
         let _ = rl.save_history(&history_path);
         Ok(())
     }
 }
 
+/// Lists the candidates of an ambiguous node name and reads a choice.
+/// Returns `None` when the user cancels.
+fn pick_node<H: Helper, I: History>(
+    rl: &mut Editor<H, I>,
+    ambiguous: &AmbiguousNode,
+) -> Option<u32> {
+    println!("{} Several nodes match '{}':", "?".yellow(), ambiguous.name);
+    for (i, (num, label)) in ambiguous.candidates.iter().enumerate() {
+        println!("  {:>2}  !{:08x}  {}", i + 1, num, label);
+    }
+
+    let prompt = format!(
+        "Pick a node [1-{}], Enter to cancel: ",
+        ambiguous.candidates.len()
+    );
+    loop {
+        let line = rl.readline(&prompt).ok()?;
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        match line.parse::<usize>() {
+            Ok(n) if (1..=ambiguous.candidates.len()).contains(&n) => {
+                return Some(ambiguous.candidates[n - 1].0);
+            }
+            _ => println!("{} Enter a number from the list.", "x".red()),
+        }
+    }
+}
+
+/// Replaces the ambiguous `name` with the picked node ID: `--to name` becomes
+/// `--dest !id`, and other flags given the name (`--from`, `--with`) get `!id`.
+fn replace_node_arg(args: &[String], name: &str, num: u32) -> Vec<String> {
+    let id = format!("!{:08x}", num);
+    let mut out = Vec::with_capacity(args.len());
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        if arg == "--to" && iter.peek().is_some_and(|v| *v == name) {
+            iter.next();
+            out.push("--dest".to_string());
+            out.push(id.clone());
+        } else if arg.strip_prefix("--to=") == Some(name) {
+            out.push(format!("--dest={}", id));
+        } else if arg == name
+            && out
+                .last()
+                .is_some_and(|flag: &String| flag == "--from" || flag == "--with")
+        {
+            out.push(id.clone());
+        } else {
+            out.push(arg.clone());
+        }
+    }
+    out
+}
+
 fn parse_shell_command(args: &[String]) -> Result<Commands, String> {
     use clap::Parser;
 
@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::Local;
+use colored::Colorize;
+use meshtastic::protobufs::{self, channel, NodeInfo};
+use serde::{Deserialize, Serialize};
+
+use super::info::{format_hardware, format_role};
+use super::parsers::format_time;
+use super::{Command, CommandContext};
+use crate::node_db::NodeDb;
+
+/// Bumped when the file layout changes incompatibly.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// A saved copy of the node DB received in the connection handshake.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    /// Unix time the snapshot was taken
+    saved_at: i64,
+    my_node_info: protobufs::MyNodeInfo,
+    /// Sorted by node number
+    nodes: Vec<NodeInfo>,
+    channels: Vec<protobufs::Channel>,
+    #[serde(default)]
+    metadata: Option<protobufs::DeviceMetadata>,
+    local_config: protobufs::LocalConfig,
+    local_module_config: protobufs::LocalModuleConfig,
+}
+
+impl Snapshot {
+    /// Copies `node_db`, leaving out the device's private key.
+    fn from_node_db(node_db: &NodeDb) -> Self {
+        let mut nodes: Vec<NodeInfo> = node_db.nodes().values().cloned().collect();
+        nodes.sort_by_key(|n| n.num);
+        let mut local_config = node_db.local_config().clone();
+        if let Some(ref mut security) = local_config.security {
+            security.private_key.clear();
+        }
+        Self {
+            version: SNAPSHOT_VERSION,
+            saved_at: Local::now().timestamp(),
+            my_node_info: node_db.my_node_info().clone(),
+            nodes,
+            channels: node_db.channels().to_vec(),
+            metadata: node_db.metadata().cloned(),
+            local_config,
+            local_module_config: node_db.local_module_config().clone(),
+        }
+    }
+
+    fn read(path: &str) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read snapshot: {}", path))?;
+        let snapshot: Self = serde_json::from_str(&content)
+            .with_context(|| format!("{} is not a node DB snapshot", path))?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            anyhow::bail!(
+                "{} was saved by a newer mttctl (snapshot version {})",
+                path,
+                snapshot.version
+            );
+        }
+        Ok(snapshot)
+    }
+
+    fn into_node_db(self) -> NodeDb {
+        NodeDb::new(
+            self.my_node_info,
+            self.nodes.into_iter().map(|n| (n.num, n)).collect(),
+            self.channels,
+            self.metadata,
+            self.local_config,
+            self.local_module_config,
+        )
+    }
+}
+
+/// Loads a snapshot file as a node DB, for `--from-snapshot`.
+pub fn load_node_db(path: &str) -> anyhow::Result<NodeDb> {
+    Ok(Snapshot::read(path)?.into_node_db())
+}
+
+// ── nodes snapshot save ───────────────────────────────────────────
+
+pub struct SnapshotSaveCommand {
+    pub file: String,
+    pub json: bool,
+}
+
+#[async_trait]
+impl Command for SnapshotSaveCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let snapshot = Snapshot::from_node_db(&ctx.node_db);
+        std::fs::write(&self.file, serde_json::to_string_pretty(&snapshot)?)
+            .with_context(|| format!("Failed to write snapshot: {}", self.file))?;
+
+        if self.json {
+            let result = serde_json::json!({
+                "file": self.file,
+                "saved_at": snapshot.saved_at,
+                "nodes": snapshot.nodes.len(),
+                "channels": snapshot.channels.len(),
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        } else {
+            println!(
+                "{} Saved {} nodes and {} channels to {}",
+                "ok".green(),
+                snapshot.nodes.len(),
+                snapshot.channels.len(),
+                self.file.bold()
+            );
+        }
+        Ok(())
+    }
+}
+
+// ── nodes snapshot diff ───────────────────────────────────────────
+
+#[derive(Serialize)]
+struct Change {
+    field: &'static str,
+    /// Channel index, for channel changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<i32>,
+    before: String,
+    after: String,
+}
+
+impl Change {
+    fn compare(changes: &mut Vec<Change>, field: &'static str, before: String, after: String) {
+        if before != after {
+            changes.push(Change {
+                field,
+                index: None,
+                before,
+                after,
+            });
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NodeSummary {
+    id: String,
+    name: String,
+    role: String,
+    hw_model: String,
+}
+
+impl NodeSummary {
+    fn of(node: &NodeInfo) -> Self {
+        let user = node.user.as_ref();
+        Self {
+            id: format!("!{:08x}", node.num),
+            name: user
+                .map(|u| u.long_name.clone())
+                .unwrap_or_else(|| "Unknown".into()),
+            role: user.map(|u| format_role(u.role)).unwrap_or_default(),
+            hw_model: user
+                .map(|u| format_hardware(u.hw_model))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NodeChanges {
+    id: String,
+    name: String,
+    changes: Vec<Change>,
+}
+
+#[derive(Serialize)]
+struct SnapshotInfo<'a> {
+    file: &'a str,
+    saved_at: i64,
+}
+
+#[derive(Serialize)]
+struct SnapshotDiff<'a> {
+    old: SnapshotInfo<'a>,
+    new: SnapshotInfo<'a>,
+    device: Vec<Change>,
+    channels: Vec<Change>,
+    appeared: Vec<NodeSummary>,
+    disappeared: Vec<NodeSummary>,
+    changed: Vec<NodeChanges>,
+}
+
+pub fn handle_snapshot_diff(old_path: &str, new_path: &str, json: bool) -> anyhow::Result<()> {
+    let old = Snapshot::read(old_path)?;
+    let new = Snapshot::read(new_path)?;
+
+    let old_nodes: BTreeMap<u32, &NodeInfo> = old.nodes.iter().map(|n| (n.num, n)).collect();
+    let new_nodes: BTreeMap<u32, &NodeInfo> = new.nodes.iter().map(|n| (n.num, n)).collect();
+
+    let appeared = new_nodes
+        .iter()
+        .filter(|(num, _)| !old_nodes.contains_key(num))
+        .map(|(_, n)| NodeSummary::of(n))
+        .collect();
+    let disappeared = old_nodes
+        .iter()
+        .filter(|(num, _)| !new_nodes.contains_key(num))
+        .map(|(_, n)| NodeSummary::of(n))
+        .collect();
+    let changed = new_nodes
+        .iter()
+        .filter_map(|(num, after)| {
+            let changes = node_changes(old_nodes.get(num)?, after);
+            (!changes.is_empty()).then(|| {
+                let summary = NodeSummary::of(after);
+                NodeChanges {
+                    id: summary.id,
+                    name: summary.name,
+                    changes,
+                }
+            })
+        })
+        .collect();
+
+    let diff = SnapshotDiff {
+        old: SnapshotInfo {
+            file: old_path,
+            saved_at: old.saved_at,
+        },
+        new: SnapshotInfo {
+            file: new_path,
+            saved_at: new.saved_at,
+        },
+        device: device_changes(&old, &new),
+        channels: channel_changes(&old.channels, &new.channels),
+        appeared,
+        disappeared,
+        changed,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+    print_diff(&diff);
+    Ok(())
+}
+
+fn node_changes(before: &NodeInfo, after: &NodeInfo) -> Vec<Change> {
+    let default_user = protobufs::User::default();
+    let a = before.user.as_ref().unwrap_or(&default_user);
+    let b = after.user.as_ref().unwrap_or(&default_user);
+
+    let mut changes = Vec::new();
+    Change::compare(
+        &mut changes,
+        "name",
+        a.long_name.clone(),
+        b.long_name.clone(),
+    );
+    Change::compare(
+        &mut changes,
+        "short_name",
+        a.short_name.clone(),
+        b.short_name.clone(),
+    );
+    Change::compare(
+        &mut changes,
+        "role",
+        format_role(a.role),
+        format_role(b.role),
+    );
+    Change::compare(
+        &mut changes,
+        "hw_model",
+        format_hardware(a.hw_model),
+        format_hardware(b.hw_model),
+    );
+    Change::compare(
+        &mut changes,
+        "public_key",
+        hex_key(&a.public_key),
+        hex_key(&b.public_key),
+    );
+    changes
+}
+
+/// Changes to the connected device itself: which node it is and its firmware.
+fn device_changes(old: &Snapshot, new: &Snapshot) -> Vec<Change> {
+    let firmware = |s: &Snapshot| {
+        s.metadata
+            .as_ref()
+            .map(|m| m.firmware_version.clone())
+            .unwrap_or_default()
+    };
+    let mut changes = Vec::new();
+    Change::compare(
+        &mut changes,
+        "node",
+        format!("!{:08x}", old.my_node_info.my_node_num),
+        format!("!{:08x}", new.my_node_info.my_node_num),
+    );
+    Change::compare(&mut changes, "firmware", firmware(old), firmware(new));
+    changes
+}
+
+/// Channel name, role and key changes, by channel index.
+fn channel_changes(old: &[protobufs::Channel], new: &[protobufs::Channel]) -> Vec<Change> {
+    let describe = |channels: &[protobufs::Channel], index: i32| {
+        let Some(ch) = channels.iter().find(|c| c.index == index) else {
+            return ("disabled".to_string(), String::new());
+        };
+        let settings = ch.settings.clone().unwrap_or_default();
+        let role = match channel::Role::try_from(ch.role) {
+            Ok(channel::Role::Primary) => "primary",
+            Ok(channel::Role::Secondary) => "secondary",
+            _ => "disabled",
+        };
+        let label = if role == "disabled" {
+            role.to_string()
+        } else {
+            format!("{} '{}'", role, settings.name)
+        };
+        (label, hex_key(&settings.psk))
+    };
+
+    let mut indices: Vec<i32> = old.iter().chain(new).map(|c| c.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+
+    let mut changes = Vec::new();
+    for index in indices {
+        let (old_label, old_key) = describe(old, index);
+        let (new_label, new_key) = describe(new, index);
+        if old_label != new_label {
+            changes.push(Change {
+                field: "channel",
+                index: Some(index),
+                before: old_label,
+                after: new_label,
+            });
+        } else if old_key != new_key && new_label != "disabled" {
+            changes.push(Change {
+                field: "channel_key",
+                index: Some(index),
+                before: old_key,
+                after: new_key,
+            });
+        }
+    }
+    changes
+}
+
+fn hex_key(key: &[u8]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn print_diff(diff: &SnapshotDiff) {
+    println!(
+        "{} Comparing {} ({}) with {} ({})",
+        "->".cyan(),
+        diff.old.file.bold(),
+        format_time(diff.old.saved_at),
+        diff.new.file.bold(),
+        format_time(diff.new.saved_at)
+    );
+
+    if !diff.device.is_empty() {
+        println!("\n{}", "Device".bold());
+        for change in &diff.device {
+            println!("  {}", describe_change(change));
+        }
+    }
+    if !diff.channels.is_empty() {
+        println!("\n{}", "Channels".bold());
+        for change in &diff.channels {
+            println!("  {}", describe_change(change));
+        }
+    }
+
+    if !diff.appeared.is_empty() {
+        println!("\n{} ({})", "Appeared".bold(), diff.appeared.len());
+        for node in &diff.appeared {
+            println!("  {} {}", "+".green(), summary_line(node));
+        }
+    }
+    if !diff.disappeared.is_empty() {
+        println!("\n{} ({})", "Disappeared".bold(), diff.disappeared.len());
+        for node in &diff.disappeared {
+            println!("  {} {}", "-".red(), summary_line(node));
+        }
+    }
+    if !diff.changed.is_empty() {
+        println!("\n{} ({})", "Changed".bold(), diff.changed.len());
+        for node in &diff.changed {
+            println!("  {} {}  {}", "~".yellow(), node.id, node.name);
+            for change in &node.changes {
+                println!("      {}", describe_change(change));
+            }
+        }
+    }
+
+    println!(
+        "\n{} {} appeared, {} disappeared, {} changed",
+        "->".cyan(),
+        diff.appeared.len(),
+        diff.disappeared.len(),
+        diff.changed.len()
+    );
+}
+
+fn summary_line(node: &NodeSummary) -> String {
+    let details: Vec<&str> = [node.role.as_str(), node.hw_model.as_str()]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect();
+    if details.is_empty() {
+        format!("{}  {}", node.id, node.name)
+    } else {
+        format!("{}  {}  ({})", node.id, node.name, details.join(", "))
+    }
+}
+
+fn describe_change(change: &Change) -> String {
+    let index = change.index.map(|i| format!(" {}", i)).unwrap_or_default();
+    match change.field {
+        "public_key" | "channel_key" => {
+            let what = if change.field == "public_key" {
+                "public key"
+            } else {
+                "key of channel"
+            };
+            let verb = match (change.before.is_empty(), change.after.is_empty()) {
+                (true, _) => "set",
+                (_, true) => "removed",
+                _ => "changed",
+            };
+            format!("{}{} {}", what, index, verb).yellow().to_string()
+        }
+        field => format!(
+            "{}{}: {} → {}",
+            field.replace('_', " "),
+            index,
+            or_none(&change.before),
+            or_none(&change.after)
+        ),
+    }
+}
+
+fn or_none(value: &str) -> &str {
+    if value.is_empty() {
+        "(none)"
+    } else {
+        value
+    }
+}
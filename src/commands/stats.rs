@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::time::Instant;
+
+use colored::Colorize;
+use crossterm::{cursor, execute, terminal};
+use meshtastic::protobufs::config::LoRaConfig;
+use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
+use meshtastic::protobufs::telemetry::Variant as TelemetryVariant;
+use meshtastic::protobufs::{MeshPacket, PortNum, Telemetry};
+use meshtastic::Message;
+use serde::Serialize;
+
+use super::dedup::PacketHistory;
+use super::lora::packet_airtime_ms;
+use super::parsers::truncate;
+use crate::node_db::NodeDb;
+
+#[derive(Default)]
+struct NodeStats {
+    packets: u64,
+    duplicates: u64,
+    relayed: u64,
+    airtime_ms: f64,
+    ports: BTreeMap<String, u64>,
+    channel_utilization: Option<f32>,
+    air_util_tx: Option<f32>,
+}
+
+#[derive(Serialize)]
+pub struct StatsReportJson {
+    duration_s: f64,
+    total_packets: u64,
+    duplicates: u64,
+    duplicate_ratio: f64,
+    relayed: u64,
+    relayed_ratio: f64,
+    airtime_ms: f64,
+    estimated_utilization_percent: f64,
+    ports: BTreeMap<String, u64>,
+    nodes: Vec<NodeStatsJson>,
+}
+
+#[derive(Serialize)]
+struct NodeStatsJson {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    packets: u64,
+    duplicates: u64,
+    relayed: u64,
+    airtime_ms: f64,
+    airtime_percent: f64,
+    ports: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_utilization: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    air_util_tx: Option<f32>,
+}
+
+/// Running per-node and per-port counters for packets heard on the mesh.
+pub struct MeshStats {
+    started: Instant,
+    lora: LoRaConfig,
+    nodes: HashMap<u32, NodeStats>,
+    ports: BTreeMap<String, u64>,
+    history: PacketHistory,
+    total_packets: u64,
+    duplicates: u64,
+    relayed: u64,
+    airtime_ms: f64,
+}
+
+impl MeshStats {
+    pub fn new(lora: LoRaConfig) -> Self {
+        Self {
+            started: Instant::now(),
+            lora,
+            nodes: HashMap::new(),
+            ports: BTreeMap::new(),
+            history: PacketHistory::new(),
+            total_packets: 0,
+            duplicates: 0,
+            relayed: 0,
+            airtime_ms: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, packet: &MeshPacket) {
+        let (port, data_len) = match &packet.payload_variant {
+            Some(MeshPayload::Decoded(data)) => (
+                PortNum::try_from(data.portnum)
+                    .map(|p| p.as_str_name().to_string())
+                    .unwrap_or_else(|_| data.portnum.to_string()),
+                data.encoded_len(),
+            ),
+            Some(MeshPayload::Encrypted(bytes)) => ("ENCRYPTED".to_string(), bytes.len()),
+            None => ("UNKNOWN".to_string(), 0),
+        };
+        let airtime = packet_airtime_ms(&self.lora, data_len);
+
+        let duplicate = self.history.record(packet).is_some();
+        let relayed = packet.hop_start > packet.hop_limit;
+
+        self.total_packets += 1;
+        self.airtime_ms += airtime;
+        *self.ports.entry(port.clone()).or_default() += 1;
+        if duplicate {
+            self.duplicates += 1;
+        }
+        if relayed {
+            self.relayed += 1;
+        }
+
+        let node = self.nodes.entry(packet.from).or_default();
+        node.packets += 1;
+        node.airtime_ms += airtime;
+        *node.ports.entry(port).or_default() += 1;
+        if duplicate {
+            node.duplicates += 1;
+        }
+        if relayed {
+            node.relayed += 1;
+        }
+
+        if let Some(MeshPayload::Decoded(data)) = &packet.payload_variant {
+            if data.portnum == PortNum::TelemetryApp as i32 {
+                if let Ok(Telemetry {
+                    variant: Some(TelemetryVariant::DeviceMetrics(m)),
+                    ..
+                }) = Telemetry::decode(data.payload.as_slice())
+                {
+                    if m.channel_utilization.is_some() {
+                        node.channel_utilization = m.channel_utilization;
+                    }
+                    if m.air_util_tx.is_some() {
+                        node.air_util_tx = m.air_util_tx;
+                    }
+                }
+            }
+        }
+    }
+
+    fn elapsed_ms(&self) -> f64 {
+        (self.started.elapsed().as_secs_f64() * 1000.0).max(1.0)
+    }
+
+    fn sorted_nodes(&self) -> Vec<(&u32, &NodeStats)> {
+        let mut nodes: Vec<_> = self.nodes.iter().collect();
+        nodes.sort_by(|a, b| b.1.airtime_ms.total_cmp(&a.1.airtime_ms));
+        nodes
+    }
+
+    /// Clears the terminal and redraws the summary table, like `watch`.
+    pub fn render(&self, node_db: &NodeDb) -> anyhow::Result<()> {
+        let mut stdout = std::io::stdout();
+        execute!(
+            stdout,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+
+        let elapsed = self.elapsed_ms();
+        writeln!(
+            stdout,
+            "{} packets in {} | {} duplicates ({:.1}%) | {} relayed ({:.1}%) | est. airtime {:.1}s ({:.2}%)",
+            self.total_packets,
+            super::parsers::format_uptime((elapsed / 1000.0) as u32, true),
+            self.duplicates,
+            ratio(self.duplicates, self.total_packets) * 100.0,
+            self.relayed,
+            ratio(self.relayed, self.total_packets) * 100.0,
+            self.airtime_ms / 1000.0,
+            self.airtime_ms / elapsed * 100.0
+        )?;
+        writeln!(stdout)?;
+
+        let header = format!(
+            "{:<12} {:<20} {:<8} {:<6} {:<8} {:<10} {:<7} {:<8} {:<8}",
+            "ID", "Name", "Packets", "Dupes", "Relayed", "Airtime", "Air %", "ChUtil", "AirTx"
+        );
+        writeln!(stdout, "{}", header.bold())?;
+        writeln!(stdout, "{}", "-".repeat(94))?;
+
+        for (&num, node) in self.sorted_nodes() {
+            let name = node_db.node_name(num).unwrap_or("Unknown");
+            writeln!(
+                stdout,
+                "{:<12} {:<20} {:<8} {:<6} {:<8} {:<10} {:<7} {:<8} {:<8}",
+                format!("!{:08x}", num),
+                truncate(name, 19),
+                node.packets,
+                node.duplicates,
+                node.relayed,
+                format!("{:.1}s", node.airtime_ms / 1000.0),
+                format!("{:.2}", node.airtime_ms / elapsed * 100.0),
+                format_percent(node.channel_utilization),
+                format_percent(node.air_util_tx)
+            )?;
+        }
+
+        writeln!(stdout)?;
+        writeln!(stdout, "{}", "Ports".bold())?;
+        let mut ports: Vec<_> = self.ports.iter().collect();
+        ports.sort_by(|a, b| b.1.cmp(a.1));
+        for (port, count) in ports {
+            writeln!(stdout, "  {:<24} {}", port, count)?;
+        }
+
+        writeln!(stdout)?;
+        writeln!(
+            stdout,
+            "{}",
+            format!("{} nodes | Press Ctrl+C to stop", self.nodes.len()).dimmed()
+        )?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    pub fn report(&self, node_db: &NodeDb) -> StatsReportJson {
+        let elapsed = self.elapsed_ms();
+        StatsReportJson {
+            duration_s: elapsed / 1000.0,
+            total_packets: self.total_packets,
+            duplicates: self.duplicates,
+            duplicate_ratio: ratio(self.duplicates, self.total_packets),
+            relayed: self.relayed,
+            relayed_ratio: ratio(self.relayed, self.total_packets),
+            airtime_ms: self.airtime_ms,
+            estimated_utilization_percent: self.airtime_ms / elapsed * 100.0,
+            ports: self.ports.clone(),
+            nodes: self
+                .sorted_nodes()
+                .into_iter()
+                .map(|(&num, node)| NodeStatsJson {
+                    id: format!("!{:08x}", num),
+                    name: node_db.node_name(num).map(|s| s.to_string()),
+                    packets: node.packets,
+                    duplicates: node.duplicates,
+                    relayed: node.relayed,
+                    airtime_ms: node.airtime_ms,
+                    airtime_percent: node.airtime_ms / elapsed * 100.0,
+                    ports: node.ports.clone(),
+                    channel_utilization: node.channel_utilization,
+                    air_util_tx: node.air_util_tx,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn format_percent(value: Option<f32>) -> String {
+    value
+        .map(|v| format!("{:.1}%", v))
+        .unwrap_or_else(|| "N/A".into())
+}
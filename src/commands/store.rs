@@ -0,0 +1,73 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::config_file::config_dir;
+
+/// An append-only JSON Lines file in the config directory, one `T` per
+/// line.
+pub struct JsonlStore<T> {
+    file_name: &'static str,
+    /// What the store holds, for error messages (e.g. "message store")
+    label: &'static str,
+    records: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> JsonlStore<T> {
+    pub const fn new(file_name: &'static str, label: &'static str) -> Self {
+        Self {
+            file_name,
+            label,
+            records: PhantomData,
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        config_dir().join(self.file_name)
+    }
+
+    /// Appends `records`, creating the config directory and file as needed.
+    pub fn append(&self, records: &[T]) -> anyhow::Result<PathBuf> {
+        let dir = config_dir();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create config directory: {}", dir.display()))?;
+
+        let path = self.path();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}: {}", self.label, path.display()))?;
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(record)?);
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())
+            .with_context(|| format!("Failed to write {}: {}", self.label, path.display()))?;
+        Ok(path)
+    }
+
+    /// Reads the whole store, skipping lines that are not valid records.
+    pub fn load(&self) -> anyhow::Result<Vec<T>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open {}: {}", self.label, path.display()))?;
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+            if let Ok(record) = serde_json::from_str(&line) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
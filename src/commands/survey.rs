@@ -0,0 +1,560 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use meshtastic::protobufs::RouteDiscovery;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::node_filter::NodeFilter;
+use super::nodes::FieldContext;
+use super::parsers::parse_node_id;
+use super::store::JsonlStore;
+use super::topology::round_snr;
+use super::traceroute::trace;
+use super::{Command, CommandContext};
+use crate::node_db::NodeDb;
+
+/// RouteDiscovery SNR values are dB × 4; this one means "not measured".
+const SNR_UNKNOWN: i32 = i8::MIN as i32;
+/// A change in weakest-link SNR smaller than this is not reported.
+const SNR_CHANGE_DB: f32 = 3.0;
+const STORE: JsonlStore<Survey> = JsonlStore::new("traceroutes.jsonl", "survey store");
+
+// ── Survey records ────────────────────────────────────────────────
+
+/// A route found by one traceroute, as stored in the survey file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Route {
+    pub rtt_ms: u64,
+    /// Relays on the way to the node
+    pub towards: Vec<String>,
+    /// SNR of each link towards the node, the last one received by the node
+    pub snr_towards: Vec<Option<f32>>,
+    /// Relays on the way back; `None` when the firmware did not report the
+    /// return path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub back: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snr_back: Vec<Option<f32>>,
+}
+
+impl Route {
+    pub fn from_discovery(route: &RouteDiscovery, rtt: Duration) -> Self {
+        let ids = |nodes: &[u32]| nodes.iter().map(|n| format!("!{:08x}", n)).collect();
+        let snrs = |values: &[i32]| {
+            values
+                .iter()
+                .map(|&s| (s != SNR_UNKNOWN).then_some(s as f32 / 4.0))
+                .collect()
+        };
+        // Firmware that fills in the return path always reports its SNR,
+        // even when the answer came back directly
+        let has_back = !route.snr_back.is_empty() || !route.route_back.is_empty();
+        Self {
+            rtt_ms: rtt.as_millis() as u64,
+            towards: ids(&route.route),
+            snr_towards: snrs(&route.snr_towards),
+            back: has_back.then(|| ids(&route.route_back)),
+            snr_back: snrs(&route.snr_back),
+        }
+    }
+
+    pub fn hops(&self) -> usize {
+        self.towards.len() + 1
+    }
+
+    pub fn hops_back(&self) -> Option<usize> {
+        self.back.as_ref().map(|b| b.len() + 1)
+    }
+
+    /// SNR of the weakest link measured in either direction.
+    pub fn weakest_snr(&self) -> Option<f32> {
+        self.snr_towards
+            .iter()
+            .chain(&self.snr_back)
+            .flatten()
+            .copied()
+            .reduce(f32::min)
+    }
+
+    /// Whether the answer came back through different relays. `None` when
+    /// the return path is unknown.
+    pub fn asymmetric(&self) -> Option<bool> {
+        let back = self.back.as_ref()?;
+        Some(!back.iter().eq(self.towards.iter().rev()))
+    }
+}
+
+/// The outcome of tracing one node.
+#[derive(Clone, Serialize, Deserialize)]
+struct TraceResult {
+    node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// `None` when the trace timed out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    route: Option<Route>,
+}
+
+/// One line of the survey store (`traceroutes.jsonl` in the config directory).
+#[derive(Serialize, Deserialize)]
+struct Survey {
+    /// Unix time the survey started
+    time: i64,
+    /// Our node, which sent the traceroutes
+    gateway: String,
+    results: Vec<TraceResult>,
+}
+
+/// The latest survey sent from `gateway`.
+fn load_previous(gateway: &str) -> anyhow::Result<Option<Survey>> {
+    Ok(STORE
+        .load()?
+        .into_iter()
+        .rfind(|survey| survey.gateway == gateway))
+}
+
+// ── Comparison ────────────────────────────────────────────────────
+
+/// A difference for one node between the previous survey and this one.
+struct Change {
+    node: String,
+    kind: &'static str,
+    before: Value,
+    after: Value,
+}
+
+fn compare(previous: &Survey, current: &Survey) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for result in &current.results {
+        let Some(old) = previous.results.iter().find(|r| r.node == result.node) else {
+            continue;
+        };
+        let change = |kind, before, after| Change {
+            node: result.node.clone(),
+            kind,
+            before,
+            after,
+        };
+        match (&old.route, &result.route) {
+            (Some(old), None) => {
+                changes.push(change("unreachable", json!(old.hops()), Value::Null))
+            }
+            (None, Some(new)) => {
+                changes.push(change("reachable", Value::Null, json!(new.hops())));
+            }
+            (Some(old), Some(new)) => {
+                if old.hops() != new.hops() {
+                    changes.push(change("hops", json!(old.hops()), json!(new.hops())));
+                } else if old.towards != new.towards {
+                    changes.push(change("route", json!(old.towards), json!(new.towards)));
+                }
+                if let (Some(before), Some(after)) = (old.weakest_snr(), new.weakest_snr()) {
+                    if (after - before).abs() >= SNR_CHANGE_DB {
+                        changes.push(change("snr", json!(before), json!(after)));
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    changes
+}
+
+// ── Output ────────────────────────────────────────────────────────
+
+fn short_label(node_db: &NodeDb, id: &str) -> String {
+    let num = parse_node_id(id);
+    match num.and_then(|n| node_db.node_name(n)) {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => id.to_string(),
+    }
+}
+
+fn result_label(result: &TraceResult) -> String {
+    match &result.name {
+        Some(name) => format!("{} ({})", result.node, name),
+        None => result.node.clone(),
+    }
+}
+
+fn format_relays(relays: &[String], node_db: &NodeDb) -> String {
+    if relays.is_empty() {
+        return "direct".into();
+    }
+    relays
+        .iter()
+        .map(|r| short_label(node_db, r))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+fn print_table(survey: &Survey, node_db: &NodeDb) {
+    println!(
+        "\n{:<32} {:>4} {:>4} {:>9} {:>6}  {}",
+        "NODE".bold(),
+        "HOPS".bold(),
+        "BACK".bold(),
+        "WEAKEST".bold(),
+        "RTT".bold(),
+        "ROUTE".bold()
+    );
+    for result in &survey.results {
+        let label = result_label(result);
+        let Some(route) = &result.route else {
+            println!(
+                "{:<32} {:>4} {:>4} {:>9} {:>6}  {}",
+                label,
+                "-",
+                "-",
+                "-",
+                "-",
+                "timeout".red()
+            );
+            continue;
+        };
+        let mut path = format_relays(&route.towards, node_db);
+        if let (Some(true), Some(back)) = (route.asymmetric(), &route.back) {
+            path = format!(
+                "{}  {} {}",
+                path,
+                "back:".yellow(),
+                format_relays(back, node_db)
+            );
+        }
+        println!(
+            "{:<32} {:>4} {:>4} {:>9} {:>6}  {}",
+            label,
+            route.hops(),
+            route
+                .hops_back()
+                .map(|h| h.to_string())
+                .unwrap_or_else(|| "?".into()),
+            route
+                .weakest_snr()
+                .map(|s| format!("{:.1} dB", s))
+                .unwrap_or_else(|| "?".into()),
+            format!("{:.1}s", route.rtt_ms as f64 / 1000.0),
+            path
+        );
+    }
+
+    let reached: Vec<&Route> = survey
+        .results
+        .iter()
+        .filter_map(|r| r.route.as_ref())
+        .collect();
+    let asymmetric = reached
+        .iter()
+        .filter(|r| r.asymmetric() == Some(true))
+        .count();
+    println!(
+        "\n{} Reached {} of {} nodes, {} with an asymmetric return path",
+        "ok".green(),
+        reached.len(),
+        survey.results.len(),
+        asymmetric
+    );
+}
+
+fn hops(count: &Value) -> String {
+    match count.as_u64() {
+        Some(1) => "1 hop".into(),
+        _ => format!("{} hops", count),
+    }
+}
+
+fn describe_change(change: &Change, node_db: &NodeDb) -> String {
+    let relays = |v: &Value| -> String {
+        let list: Vec<String> = serde_json::from_value(v.clone()).unwrap_or_default();
+        format_relays(&list, node_db)
+    };
+    match change.kind {
+        "unreachable" => format!("now unreachable (was {})", hops(&change.before)),
+        "reachable" => format!("reachable again ({})", hops(&change.after)),
+        "hops" => format!("{} → {} hops", change.before, change.after),
+        "route" => format!(
+            "route changed: {} → {}",
+            relays(&change.before),
+            relays(&change.after)
+        ),
+        _ => format!(
+            "weakest link {:.1} → {:.1} dB",
+            change.before.as_f64().unwrap_or_default(),
+            change.after.as_f64().unwrap_or_default()
+        ),
+    }
+}
+
+fn print_changes(
+    current: &Survey,
+    previous: Option<&Survey>,
+    changes: &[Change],
+    node_db: &NodeDb,
+) {
+    let Some(previous) = previous else {
+        return;
+    };
+    let when = DateTime::from_timestamp(previous.time, 0)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    if changes.is_empty() {
+        println!("{} No changes since the survey of {}", "ok".green(), when);
+        return;
+    }
+    println!("\n{} {}:", "Changes since the survey of".bold(), when);
+    for change in changes {
+        println!(
+            "  {} {}: {}",
+            "!".yellow(),
+            current
+                .results
+                .iter()
+                .find(|r| r.node == change.node)
+                .map(result_label)
+                .unwrap_or_else(|| change.node.clone()),
+            describe_change(change, node_db)
+        );
+    }
+}
+
+#[derive(Serialize)]
+struct ResultJson<'a> {
+    node: &'a str,
+    name: Option<&'a str>,
+    reached: bool,
+    hops: Option<usize>,
+    hops_back: Option<usize>,
+    weakest_snr: Option<f32>,
+    asymmetric: Option<bool>,
+    rtt_ms: Option<u64>,
+    route: Option<&'a [String]>,
+    route_back: Option<&'a [String]>,
+    snr_towards: Option<&'a [Option<f32>]>,
+    snr_back: Option<&'a [Option<f32>]>,
+}
+
+#[derive(Serialize)]
+struct ChangeJson<'a> {
+    node: &'a str,
+    change: &'a str,
+    before: &'a Value,
+    after: &'a Value,
+}
+
+#[derive(Serialize)]
+struct SurveyJson<'a> {
+    time: i64,
+    gateway: &'a str,
+    results: Vec<ResultJson<'a>>,
+    /// Time of the survey the changes are relative to
+    previous: Option<i64>,
+    changes: Vec<ChangeJson<'a>>,
+}
+
+fn print_json(
+    survey: &Survey,
+    previous: Option<&Survey>,
+    changes: &[Change],
+) -> anyhow::Result<()> {
+    let results = survey
+        .results
+        .iter()
+        .map(|r| {
+            let route = r.route.as_ref();
+            ResultJson {
+                node: &r.node,
+                name: r.name.as_deref(),
+                reached: route.is_some(),
+                hops: route.map(Route::hops),
+                hops_back: route.and_then(Route::hops_back),
+                weakest_snr: round_snr(route.and_then(Route::weakest_snr)),
+                asymmetric: route.and_then(Route::asymmetric),
+                rtt_ms: route.map(|r| r.rtt_ms),
+                route: route.map(|r| r.towards.as_slice()),
+                route_back: route.and_then(|r| r.back.as_deref()),
+                snr_towards: route.map(|r| r.snr_towards.as_slice()),
+                snr_back: route.map(|r| r.snr_back.as_slice()),
+            }
+        })
+        .collect();
+    let out = SurveyJson {
+        time: survey.time,
+        gateway: &survey.gateway,
+        results,
+        previous: previous.map(|p| p.time),
+        changes: changes
+            .iter()
+            .map(|c| ChangeJson {
+                node: &c.node,
+                change: c.kind,
+                before: &c.before,
+                after: &c.after,
+            })
+            .collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&out)?);
+    Ok(())
+}
+
+// ── TracerouteSurveyCommand ───────────────────────────────────────
+
+pub struct TracerouteSurveyCommand {
+    pub filter: NodeFilter,
+    pub delay_secs: u64,
+    pub timeout_secs: u64,
+    pub json: bool,
+}
+
+impl TracerouteSurveyCommand {
+    /// Every node but our own that passes the filter, nearest first.
+    fn targets(&self, ctx: &CommandContext) -> Vec<(u32, Option<String>)> {
+        let now = Utc::now().timestamp() as u32;
+        let fctx = FieldContext {
+            origin: None,
+            units: ctx.units,
+        };
+        let my_id = ctx.node_db.my_node_num();
+        let mut nodes: Vec<_> = ctx
+            .node_db
+            .nodes()
+            .values()
+            .filter(|n| n.num != my_id && !n.is_ignored)
+            .filter(|n| self.filter.matches(n, now, &fctx))
+            .collect();
+        nodes.sort_by_key(|n| {
+            (
+                n.hops_away.unwrap_or(u32::MAX),
+                std::cmp::Reverse(n.last_heard),
+            )
+        });
+        nodes
+            .into_iter()
+            .map(|n| (n.num, n.user.as_ref().map(|u| u.long_name.clone())))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Command for TracerouteSurveyCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let targets = self.targets(ctx);
+        if targets.is_empty() {
+            if self.json {
+                println!("{}", json!({ "results": [] }));
+            } else {
+                println!("No nodes to trace.");
+            }
+            return Ok(());
+        }
+        if !self.json {
+            let minutes = (targets.len() as u64 * self.delay_secs.max(1)).div_ceil(60);
+            println!(
+                "{} Tracing {} nodes, one every {}s at most (about {} min). Press {} to stop.",
+                "->".cyan(),
+                targets.len(),
+                self.delay_secs,
+                minutes,
+                "Ctrl+C".bold()
+            );
+        }
+
+        let mut survey = Survey {
+            time: Utc::now().timestamp(),
+            gateway: format!("!{:08x}", ctx.node_db.my_node_num()),
+            results: Vec::new(),
+        };
+        let spacing = Duration::from_secs(self.delay_secs);
+        let timeout = Duration::from_secs(self.timeout_secs);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        let mut interrupted = false;
+        for (i, (target, name)) in targets.iter().enumerate() {
+            let sent = Instant::now();
+            let result = TraceResult {
+                node: format!("!{:08x}", target),
+                name: name.clone(),
+                route: None,
+            };
+            if !self.json {
+                println!(
+                    "{} [{}/{}] {}",
+                    "->".cyan(),
+                    i + 1,
+                    targets.len(),
+                    result_label(&result)
+                );
+            }
+            let route = tokio::select! {
+                route = trace(ctx, *target, timeout) => route?,
+                _ = &mut ctrl_c => {
+                    interrupted = true;
+                    break;
+                }
+            };
+            if !self.json {
+                match &route {
+                    Some(route) => println!(
+                        "  {} {} hops in {:.1}s",
+                        "ok".green(),
+                        route.hops(),
+                        route.rtt_ms as f64 / 1000.0
+                    ),
+                    None => println!("  {} Timeout after {}s", "x".red(), self.timeout_secs),
+                }
+            }
+            survey.results.push(TraceResult { route, ..result });
+
+            // Space requests out from when each was sent, not when it ended
+            if i + 1 < targets.len() {
+                tokio::select! {
+                    _ = tokio::time::sleep(spacing.saturating_sub(sent.elapsed())) => {}
+                    _ = &mut ctrl_c => {
+                        interrupted = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if interrupted && !self.json {
+            println!(
+                "\n{} Survey stopped after {} of {} nodes",
+                "!".yellow(),
+                survey.results.len(),
+                targets.len()
+            );
+        }
+        if survey.results.is_empty() {
+            return Ok(());
+        }
+
+        let previous = load_previous(&survey.gateway).unwrap_or_else(|e| {
+            eprintln!("{} Could not read previous surveys: {:#}", "!".yellow(), e);
+            None
+        });
+        let changes = previous
+            .as_ref()
+            .map(|p| compare(p, &survey))
+            .unwrap_or_default();
+
+        if self.json {
+            print_json(&survey, previous.as_ref(), &changes)?;
+        } else {
+            print_table(&survey, &ctx.node_db);
+            print_changes(&survey, previous.as_ref(), &changes, &ctx.node_db);
+        }
+
+        match STORE.append(std::slice::from_ref(&survey)) {
+            Ok(path) if !self.json => {
+                println!("{} Saved survey to {}", "ok".green(), path.display());
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("{} Could not save survey: {:#}", "!".yellow(), e),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,687 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use colored::Colorize;
+use meshtastic::packet::PacketDestination;
+use meshtastic::protobufs::from_radio::PayloadVariant;
+use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
+use meshtastic::protobufs::{self, Data, MeshPacket, NeighborInfo, PortNum};
+use meshtastic::utils::generate_rand_id;
+use meshtastic::Message;
+use serde::Serialize;
+
+use super::history::SightingRecorder;
+use super::packet_log::load_log;
+use super::{resolve_destination, Command, CommandContext, DestinationSpec};
+use crate::node_db::NodeDb;
+
+/// Links whose weaker direction is at or above this SNR are drawn green.
+const GOOD_SNR: f32 = 0.0;
+/// Links below this SNR are drawn red; in between they are orange.
+const WEAK_SNR: f32 = -10.0;
+
+pub enum TopologyFormat {
+    Summary,
+    Dot,
+}
+
+impl From<&crate::cli::TopologyFormatArg> for TopologyFormat {
+    fn from(arg: &crate::cli::TopologyFormatArg) -> Self {
+        use crate::cli::TopologyFormatArg;
+        match arg {
+            TopologyFormatArg::Summary => TopologyFormat::Summary,
+            TopologyFormatArg::Dot => TopologyFormat::Dot,
+        }
+    }
+}
+
+// ── Reports ───────────────────────────────────────────────────────
+
+/// A node's own list of the neighbors it hears directly.
+struct Report {
+    /// Neighbor node and the SNR at which the reporter hears it
+    neighbors: Vec<(u32, f32)>,
+    rx_time: u32,
+}
+
+/// The latest NeighborInfo report from each node.
+#[derive(Default)]
+struct Reports(BTreeMap<u32, Report>);
+
+impl Reports {
+    /// Records a NEIGHBORINFO_APP packet and returns the reporting node.
+    fn record(&mut self, packet: &MeshPacket) -> Option<u32> {
+        let Some(MeshPayload::Decoded(data)) = &packet.payload_variant else {
+            return None;
+        };
+        if data.portnum != PortNum::NeighborinfoApp as i32 {
+            return None;
+        }
+        let info = NeighborInfo::decode(data.payload.as_slice()).ok()?;
+        let node = if info.node_id != 0 {
+            info.node_id
+        } else {
+            packet.from
+        };
+        let report = Report {
+            neighbors: info
+                .neighbors
+                .iter()
+                .filter(|n| n.node_id != node && n.node_id != 0)
+                .map(|n| (n.node_id, n.snr))
+                .collect(),
+            rx_time: packet.rx_time,
+        };
+        // Log files are not guaranteed to be in order; keep the newest report
+        if self
+            .0
+            .get(&node)
+            .is_none_or(|existing| existing.rx_time <= report.rx_time)
+        {
+            self.0.insert(node, report);
+        }
+        Some(node)
+    }
+}
+
+// ── Graph ─────────────────────────────────────────────────────────
+
+/// An undirected link between two nodes, `a < b`.
+struct Link {
+    a: u32,
+    b: u32,
+    /// SNR at which `a` hears `b`, if `a` reported it
+    snr_at_a: Option<f32>,
+    /// SNR at which `b` hears `a`, if `b` reported it
+    snr_at_b: Option<f32>,
+}
+
+impl Link {
+    /// SNR of the weaker direction that was reported.
+    fn weakest(&self) -> f32 {
+        match (self.snr_at_a, self.snr_at_b) {
+            (Some(x), Some(y)) => x.min(y),
+            (Some(x), None) | (None, Some(x)) => x,
+            (None, None) => f32::NAN,
+        }
+    }
+
+    /// SNR at which `node` hears the other end, and the other end hears `node`.
+    fn snr_from(&self, node: u32) -> (Option<f32>, Option<f32>) {
+        if node == self.a {
+            (self.snr_at_a, self.snr_at_b)
+        } else {
+            (self.snr_at_b, self.snr_at_a)
+        }
+    }
+
+    fn other(&self, node: u32) -> u32 {
+        if node == self.a {
+            self.b
+        } else {
+            self.a
+        }
+    }
+}
+
+/// A node or link whose loss splits the mesh.
+struct Cut {
+    /// The cut node, or both ends of the cut link
+    at: Vec<u32>,
+    /// Nodes that would lose their path to the rest of the mesh
+    cut_off: Vec<u32>,
+}
+
+struct Topology {
+    nodes: BTreeSet<u32>,
+    reporters: BTreeSet<u32>,
+    links: Vec<Link>,
+    adjacency: BTreeMap<u32, BTreeSet<u32>>,
+    /// Node the rest of the mesh is measured from when it splits, usually
+    /// the local node
+    home: Option<u32>,
+}
+
+impl Topology {
+    fn build(reports: &Reports, home: Option<u32>) -> Self {
+        let mut nodes = BTreeSet::new();
+        let mut links: BTreeMap<(u32, u32), Link> = BTreeMap::new();
+        for (&reporter, report) in &reports.0 {
+            nodes.insert(reporter);
+            for &(neighbor, snr) in &report.neighbors {
+                nodes.insert(neighbor);
+                let (a, b) = (reporter.min(neighbor), reporter.max(neighbor));
+                let link = links.entry((a, b)).or_insert(Link {
+                    a,
+                    b,
+                    snr_at_a: None,
+                    snr_at_b: None,
+                });
+                if reporter == a {
+                    link.snr_at_a = Some(snr);
+                } else {
+                    link.snr_at_b = Some(snr);
+                }
+            }
+        }
+
+        let mut adjacency: BTreeMap<u32, BTreeSet<u32>> =
+            nodes.iter().map(|&n| (n, BTreeSet::new())).collect();
+        for link in links.values() {
+            adjacency.entry(link.a).or_default().insert(link.b);
+            adjacency.entry(link.b).or_default().insert(link.a);
+        }
+
+        Self {
+            home: home.filter(|h| nodes.contains(h)),
+            nodes,
+            reporters: reports.0.keys().copied().collect(),
+            links: links.into_values().collect(),
+            adjacency,
+        }
+    }
+
+    fn links_of(&self, node: u32) -> impl Iterator<Item = &Link> {
+        self.links
+            .iter()
+            .filter(move |l| l.a == node || l.b == node)
+    }
+
+    /// Connected components, ignoring one node or link.
+    fn components(
+        &self,
+        without_node: Option<u32>,
+        without_link: Option<(u32, u32)>,
+    ) -> Vec<BTreeSet<u32>> {
+        let mut seen = BTreeSet::new();
+        let mut components = Vec::new();
+        for &start in &self.nodes {
+            if Some(start) == without_node || seen.contains(&start) {
+                continue;
+            }
+            let mut component = BTreeSet::new();
+            let mut stack = vec![start];
+            seen.insert(start);
+            while let Some(node) = stack.pop() {
+                component.insert(node);
+                for &next in &self.adjacency[&node] {
+                    let cut = without_link
+                        .is_some_and(|(a, b)| (node, next) == (a, b) || (node, next) == (b, a));
+                    if Some(next) == without_node || cut || !seen.insert(next) {
+                        continue;
+                    }
+                    stack.push(next);
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    fn isolated(&self) -> Vec<u32> {
+        self.adjacency
+            .iter()
+            .filter(|(_, neighbors)| neighbors.is_empty())
+            .map(|(&node, _)| node)
+            .collect()
+    }
+
+    /// Of the pieces a component breaks into, everything but the piece
+    /// holding the home node (or else the largest piece) is cut off.
+    fn cut_off(&self, mut pieces: Vec<BTreeSet<u32>>) -> Vec<u32> {
+        let keep = pieces
+            .iter()
+            .position(|p| self.home.is_some_and(|h| p.contains(&h)))
+            .or_else(|| {
+                pieces
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(i, p)| (p.len(), std::cmp::Reverse(*i)))
+                    .map(|(i, _)| i)
+            });
+        if let Some(keep) = keep {
+            pieces.remove(keep);
+        }
+        pieces.into_iter().flatten().collect()
+    }
+
+    /// Nodes whose loss splits their part of the mesh (articulation points).
+    fn cut_nodes(&self) -> Vec<Cut> {
+        let original = self.components(None, None);
+        self.nodes
+            .iter()
+            .filter(|n| self.adjacency[n].len() >= 2)
+            .filter_map(|&node| {
+                let component = original.iter().find(|c| c.contains(&node))?;
+                let pieces: Vec<_> = self
+                    .components(Some(node), None)
+                    .into_iter()
+                    .filter(|c| c.is_subset(component))
+                    .collect();
+                (pieces.len() >= 2).then(|| Cut {
+                    at: vec![node],
+                    cut_off: self.cut_off(pieces),
+                })
+            })
+            .collect()
+    }
+
+    /// Links that are the only path between two parts of the mesh (bridges).
+    fn cut_links(&self) -> Vec<Cut> {
+        self.links
+            .iter()
+            .filter_map(|link| {
+                let pieces: Vec<_> = self
+                    .components(None, Some((link.a, link.b)))
+                    .into_iter()
+                    .filter(|c| c.contains(&link.a) || c.contains(&link.b))
+                    .collect();
+                (pieces.len() >= 2).then(|| Cut {
+                    at: vec![link.a, link.b],
+                    cut_off: self.cut_off(pieces),
+                })
+            })
+            .collect()
+    }
+}
+
+// ── Output ────────────────────────────────────────────────────────
+
+fn node_label(node_db: &NodeDb, node: u32) -> String {
+    match node_db.node_name(node) {
+        Some(name) if !name.is_empty() => format!("!{:08x} ({})", node, name),
+        _ => format!("!{:08x}", node),
+    }
+}
+
+/// Rounds an SNR to 0.01 dB for JSON output.
+pub fn round_snr(snr: Option<f32>) -> Option<f32> {
+    snr.map(|s| (s * 100.0).round() / 100.0)
+}
+
+fn format_snr(snr: Option<f32>) -> String {
+    snr.map(|s| format!("{:.1} dB", s))
+        .unwrap_or_else(|| "-".into())
+}
+
+fn names(node_db: &NodeDb, nodes: &[u32]) -> String {
+    nodes
+        .iter()
+        .map(|&n| node_label(node_db, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_summary(topology: &Topology, node_db: &NodeDb) {
+    let components = topology.components(None, None).len();
+    println!(
+        "{} {} nodes, {} links, {} component{} (reports from {} nodes)\n",
+        "Topology:".bold(),
+        topology.nodes.len(),
+        topology.links.len(),
+        components,
+        if components == 1 { "" } else { "s" },
+        topology.reporters.len()
+    );
+
+    for &node in &topology.nodes {
+        let count = topology.adjacency[&node].len();
+        let note = if topology.reporters.contains(&node) {
+            String::new()
+        } else {
+            " (no report)".dimmed().to_string()
+        };
+        println!(
+            "{}  {} neighbor{}{}",
+            node_label(node_db, node).bold(),
+            count,
+            if count == 1 { "" } else { "s" },
+            note
+        );
+        if count == 0 {
+            continue;
+        }
+        println!("    {:<40} {:>9} {:>9}", "NEIGHBOR", "RX SNR", "TX SNR");
+        for link in topology.links_of(node) {
+            let (rx, tx) = link.snr_from(node);
+            println!(
+                "    {:<40} {:>9} {:>9}",
+                node_label(node_db, link.other(node)),
+                format_snr(rx),
+                format_snr(tx)
+            );
+        }
+    }
+
+    let isolated = topology.isolated();
+    if !isolated.is_empty() {
+        println!("\n{}", "Isolated nodes:".bold());
+        for node in isolated {
+            println!(
+                "  {} {} reported no neighbors",
+                "!".yellow(),
+                node_label(node_db, node)
+            );
+        }
+    }
+
+    let cut_nodes = topology.cut_nodes();
+    let cut_links = topology.cut_links();
+    println!();
+    if cut_nodes.is_empty() && cut_links.is_empty() {
+        println!("{} No single points of failure", "ok".green());
+        return;
+    }
+    println!("{}", "Single points of failure:".bold());
+    for cut in &cut_nodes {
+        println!(
+            "  {} Node {} — losing it cuts off {}",
+            "!".yellow(),
+            node_label(node_db, cut.at[0]),
+            names(node_db, &cut.cut_off)
+        );
+    }
+    for cut in &cut_links {
+        println!(
+            "  {} Link {} ↔ {} — only path to {}",
+            "!".yellow(),
+            node_label(node_db, cut.at[0]),
+            node_label(node_db, cut.at[1]),
+            names(node_db, &cut.cut_off)
+        );
+    }
+}
+
+#[derive(Serialize)]
+struct NodeJson {
+    id: String,
+    name: Option<String>,
+    reported: bool,
+    neighbors: usize,
+}
+
+#[derive(Serialize)]
+struct LinkJson {
+    a: String,
+    b: String,
+    snr_at_a: Option<f32>,
+    snr_at_b: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct CutJson {
+    at: Vec<String>,
+    cuts_off: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TopologyJson {
+    nodes: Vec<NodeJson>,
+    links: Vec<LinkJson>,
+    components: usize,
+    isolated: Vec<String>,
+    cut_nodes: Vec<CutJson>,
+    cut_links: Vec<CutJson>,
+}
+
+fn id(node: u32) -> String {
+    format!("!{:08x}", node)
+}
+
+fn cut_json(cut: &Cut) -> CutJson {
+    CutJson {
+        at: cut.at.iter().copied().map(id).collect(),
+        cuts_off: cut.cut_off.iter().copied().map(id).collect(),
+    }
+}
+
+fn print_json(topology: &Topology, node_db: &NodeDb) -> anyhow::Result<()> {
+    let out = TopologyJson {
+        nodes: topology
+            .nodes
+            .iter()
+            .map(|&n| NodeJson {
+                id: id(n),
+                name: node_db.node_name(n).map(String::from),
+                reported: topology.reporters.contains(&n),
+                neighbors: topology.adjacency[&n].len(),
+            })
+            .collect(),
+        links: topology
+            .links
+            .iter()
+            .map(|l| LinkJson {
+                a: id(l.a),
+                b: id(l.b),
+                snr_at_a: round_snr(l.snr_at_a),
+                snr_at_b: round_snr(l.snr_at_b),
+            })
+            .collect(),
+        components: topology.components(None, None).len(),
+        isolated: topology.isolated().into_iter().map(id).collect(),
+        cut_nodes: topology.cut_nodes().iter().map(cut_json).collect(),
+        cut_links: topology.cut_links().iter().map(cut_json).collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&out)?);
+    Ok(())
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn print_dot(topology: &Topology, node_db: &NodeDb) {
+    let cut_nodes: BTreeSet<u32> = topology.cut_nodes().iter().map(|c| c.at[0]).collect();
+    let cut_links: BTreeSet<(u32, u32)> = topology
+        .cut_links()
+        .iter()
+        .map(|c| (c.at[0], c.at[1]))
+        .collect();
+
+    println!("graph mesh {{");
+    println!("  node [shape=box, fontname=\"Helvetica\"];");
+    println!("  edge [fontname=\"Helvetica\", fontsize=10];");
+    for &node in &topology.nodes {
+        let label = match node_db.node_name(node) {
+            Some(name) if !name.is_empty() => format!("{}\\n{}", dot_escape(name), id(node)),
+            _ => id(node),
+        };
+        let mut attrs = vec![format!("label=\"{}\"", label)];
+        if cut_nodes.contains(&node) {
+            attrs.push("style=filled, fillcolor=\"#ffcdd2\"".into());
+        } else if topology.adjacency[&node].is_empty() {
+            attrs.push("style=filled, fillcolor=\"#e0e0e0\"".into());
+        } else if !topology.reporters.contains(&node) {
+            attrs.push("style=dashed".into());
+        }
+        println!("  \"{}\" [{}];", id(node), attrs.join(", "));
+    }
+    for link in &topology.links {
+        let weakest = link.weakest();
+        let color = if weakest >= GOOD_SNR {
+            "#2e7d32"
+        } else if weakest >= WEAK_SNR {
+            "#ef6c00"
+        } else {
+            "#c62828"
+        };
+        let mut attrs = vec![format!("color=\"{}\"", color)];
+        // Each end is labelled with the SNR at which that node hears the other
+        if let Some(snr) = link.snr_at_a {
+            attrs.push(format!("taillabel=\"{:.1}\"", snr));
+        }
+        if let Some(snr) = link.snr_at_b {
+            attrs.push(format!("headlabel=\"{:.1}\"", snr));
+        }
+        if link.snr_at_a.is_none() || link.snr_at_b.is_none() {
+            attrs.push("style=dashed".into());
+        }
+        if cut_links.contains(&(link.a, link.b)) {
+            attrs.push("penwidth=2.5".into());
+        }
+        println!(
+            "  \"{}\" -- \"{}\" [{}];",
+            id(link.a),
+            id(link.b),
+            attrs.join(", ")
+        );
+    }
+    println!("}}");
+}
+
+fn print_topology(
+    reports: &Reports,
+    node_db: &NodeDb,
+    home: Option<u32>,
+    format: &TopologyFormat,
+    json: bool,
+) -> anyhow::Result<()> {
+    let topology = Topology::build(reports, home);
+    match format {
+        TopologyFormat::Dot => print_dot(&topology, node_db),
+        TopologyFormat::Summary if json => print_json(&topology, node_db)?,
+        TopologyFormat::Summary => {
+            if topology.nodes.is_empty() {
+                println!("{} No NeighborInfo reports found.", "x".red());
+                println!(
+                    "  Nodes only send them with the NeighborInfo module enabled; see 'config set neighbor_info.enabled true'."
+                );
+                return Ok(());
+            }
+            print_summary(&topology, node_db);
+        }
+    }
+    Ok(())
+}
+
+// ── Commands ──────────────────────────────────────────────────────
+
+pub struct TopologyCommand {
+    pub duration: Duration,
+    pub targets: Vec<DestinationSpec>,
+    pub delay_secs: u64,
+    pub format: TopologyFormat,
+    pub json: bool,
+}
+
+impl TopologyCommand {
+    fn quiet(&self) -> bool {
+        self.json || matches!(self.format, TopologyFormat::Dot)
+    }
+
+    async fn request(&self, ctx: &mut CommandContext, target: u32) -> anyhow::Result<()> {
+        let packet = MeshPacket {
+            from: ctx.node_db.my_node_num(),
+            to: target,
+            id: generate_rand_id(),
+            channel: 0,
+            hop_limit: 3,
+            payload_variant: Some(MeshPayload::Decoded(Data {
+                portnum: PortNum::NeighborinfoApp as i32,
+                payload: NeighborInfo::default().encode_to_vec(),
+                want_response: true,
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        ctx.api
+            .send_to_radio_packet(Some(protobufs::to_radio::PayloadVariant::Packet(packet)))
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Command for TopologyCommand {
+    async fn execute(&self, ctx: &mut CommandContext) -> anyhow::Result<()> {
+        let quiet = self.quiet();
+        let mut pending = BTreeMap::new();
+        for (i, spec) in self.targets.iter().enumerate() {
+            let (dest, label) = resolve_destination(spec, &ctx.node_db)?;
+            let PacketDestination::Node(node) = dest else {
+                bail!("Neighbor requests need a specific node destination");
+            };
+            if i > 0 {
+                tokio::time::sleep(Duration::from_secs(self.delay_secs)).await;
+            }
+            if !quiet {
+                println!(
+                    "{} Requesting neighbors from {}...",
+                    "->".cyan(),
+                    label.bold()
+                );
+            }
+            self.request(ctx, node.id()).await?;
+            pending.insert(node.id(), label);
+        }
+
+        if !quiet {
+            println!(
+                "{} Listening for NeighborInfo for {}s... Press {} to finish early.",
+                "->".cyan(),
+                self.duration.as_secs(),
+                "Ctrl+C".bold()
+            );
+        }
+
+        let mut reports = Reports::default();
+        let mut sightings = SightingRecorder::new();
+        let deadline = tokio::time::sleep(self.duration);
+        tokio::pin!(deadline);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        loop {
+            let packet = tokio::select! {
+                packet = ctx.packet_receiver.recv() => packet,
+                _ = &mut deadline => break,
+                _ = &mut ctrl_c => break,
+            };
+            let Some(packet) = packet else {
+                bail!("Packet receiver closed unexpectedly");
+            };
+            let Some(PayloadVariant::Packet(mesh_packet)) = packet.payload_variant else {
+                continue;
+            };
+            sightings.record(&mesh_packet, &ctx.node_db);
+            let Some(node) = reports.record(&mesh_packet) else {
+                continue;
+            };
+            if !quiet {
+                let count = reports.0[&node].neighbors.len();
+                println!(
+                    "{} {} reports {} neighbor{}",
+                    "ok".green(),
+                    node_label(&ctx.node_db, node),
+                    count,
+                    if count == 1 { "" } else { "s" }
+                );
+            }
+            if pending.remove(&node).is_some() && pending.is_empty() && !self.targets.is_empty() {
+                break;
+            }
+        }
+
+        if !quiet {
+            for label in pending.values() {
+                println!("{} No neighbor report from {}", "x".red(), label);
+            }
+            println!();
+        }
+
+        let home = Some(ctx.node_db.my_node_num());
+        print_topology(&reports, &ctx.node_db, home, &self.format, self.json)
+    }
+}
+
+/// Builds the topology from the NeighborInfo packets in a `listen --log` file.
+pub fn handle_topology_log(file: &str, format: &TopologyFormat, json: bool) -> anyhow::Result<()> {
+    let log = load_log(file, json)?;
+    let mut reports = Reports::default();
+    for packet in &log.packets {
+        reports.record(packet);
+    }
+    print_topology(&reports, &log.node_db, None, format, json)
+}
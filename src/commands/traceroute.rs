@@ -10,6 +10,8 @@ use meshtastic::utils::generate_rand_id;
 use meshtastic::Message;
 use serde::Serialize;
 
+use super::route_stats::{self, RouteStats};
+use super::survey::Route;
 use super::{resolve_destination, Command, CommandContext, DestinationSpec};
 
 #[derive(Serialize)]
@@ -30,9 +32,19 @@ struct TracerouteHopJson {
     snr_db: Option<f64>,
 }
 
+#[derive(Serialize)]
+struct TracerouteTimeoutJson<'a> {
+    dest: &'a str,
+    timeout: bool,
+    timeout_secs: u64,
+}
+
 pub struct TracerouteCommand {
     pub destination: DestinationSpec,
     pub timeout_secs: u64,
+    /// Traces to send; more than one shows per-hop statistics
+    pub count: u32,
+    pub interval_secs: u64,
     pub json: bool,
 }
 
@@ -46,115 +58,205 @@ impl Command for TracerouteCommand {
             _ => bail!("Traceroute requires a specific node destination"),
         };
 
-        let packet_id: u32 = generate_rand_id();
-        let my_node_num = ctx.node_db.my_node_num();
-
-        let route_discovery = RouteDiscovery {
-            route: vec![],
-            snr_towards: vec![],
-            route_back: vec![],
-            snr_back: vec![],
-        };
-        let mut payload_bytes = Vec::new();
-        route_discovery.encode(&mut payload_bytes)?;
-
-        let mesh_packet = MeshPacket {
-            from: my_node_num,
-            to: target_node_id,
-            id: packet_id,
-            want_ack: true,
-            channel: 0,
-            hop_limit: 7,
-            payload_variant: Some(MeshPayload::Decoded(Data {
-                portnum: PortNum::TracerouteApp as i32,
-                payload: payload_bytes,
-                want_response: true,
-                dest: target_node_id,
-                ..Default::default()
-            })),
-            ..Default::default()
-        };
+        if self.count > 1 {
+            return self
+                .execute_repeated(ctx, target_node_id, &dest_label)
+                .await;
+        }
 
-        let payload_variant = Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet));
+        let my_node_num = ctx.node_db.my_node_num();
 
-        println!(
-            "{} Tracing route to {}...\n",
-            "->".cyan(),
-            dest_label.bold()
-        );
+        if !self.json {
+            println!(
+                "{} Tracing route to {}...\n",
+                "->".cyan(),
+                dest_label.bold()
+            );
+        }
 
         let start = Instant::now();
-
-        ctx.api.send_to_radio_packet(payload_variant).await?;
-
+        let packet_id = send_request(ctx, target_node_id).await?;
         let timeout = Duration::from_secs(self.timeout_secs);
 
-        loop {
-            let remaining = timeout.saturating_sub(start.elapsed());
-            if remaining.is_zero() {
+        let Some(data) = wait_for_response(ctx, packet_id, start, timeout).await? else {
+            if self.json {
+                let out = TracerouteTimeoutJson {
+                    dest: &dest_label,
+                    timeout: true,
+                    timeout_secs: self.timeout_secs,
+                };
+                ctx.print_json(&out)?;
+            } else {
                 println!(
                     "{} Timeout after {}s — no traceroute response from {}",
                     "x".red(),
                     self.timeout_secs,
                     dest_label
                 );
-                return Ok(());
             }
+            return Ok(());
+        };
+        let rtt = start.elapsed();
 
-            let packet = tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await;
-
-            match packet {
-                Err(_) => {
-                    println!(
-                        "{} Timeout after {}s — no traceroute response from {}",
-                        "x".red(),
-                        self.timeout_secs,
-                        dest_label
-                    );
-                    return Ok(());
-                }
-                Ok(None) => {
-                    bail!("Disconnected while waiting for traceroute response");
+        let Ok(route) = RouteDiscovery::decode(data.payload.as_slice()) else {
+            println!(
+                "{} Received traceroute response but failed to decode",
+                "?".yellow()
+            );
+            return Ok(());
+        };
+
+        if self.json {
+            print_route_json(&route, my_node_num, target_node_id, &dest_label, rtt, ctx)?;
+        } else {
+            print_route(&route, my_node_num, target_node_id, &dest_label, rtt, ctx);
+        }
+
+        Ok(())
+    }
+}
+
+impl TracerouteCommand {
+    /// Traces `count` times, `interval_secs` apart, redrawing per-hop
+    /// statistics after each answer or timeout.
+    async fn execute_repeated(
+        &self,
+        ctx: &mut CommandContext,
+        target: u32,
+        dest_label: &str,
+    ) -> anyhow::Result<()> {
+        let mut stats = RouteStats::new(target, ctx.node_db.my_node_num());
+        let interval = Duration::from_secs(self.interval_secs);
+        let timeout = Duration::from_secs(self.timeout_secs);
+        let ctrl_c = tokio::signal::ctrl_c();
+        tokio::pin!(ctrl_c);
+
+        if !self.json {
+            route_stats::render(&stats, &ctx.node_db, dest_label, self.count)?;
+        }
+        for i in 0..self.count {
+            let sent = Instant::now();
+            let route = tokio::select! {
+                route = trace(ctx, target, timeout) => route?,
+                _ = &mut ctrl_c => break,
+            };
+            stats.record(route.as_ref());
+            if !self.json {
+                route_stats::render(&stats, &ctx.node_db, dest_label, self.count)?;
+            }
+
+            // Space requests out from when each was sent, not when it ended
+            if i + 1 < self.count {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval.saturating_sub(sent.elapsed())) => {}
+                    _ = &mut ctrl_c => break,
                 }
-                Ok(Some(from_radio)) => {
-                    let Some(PayloadVariant::Packet(mesh_pkt)) = from_radio.payload_variant else {
-                        continue;
-                    };
-
-                    let Some(MeshPayload::Decoded(ref data)) = mesh_pkt.payload_variant else {
-                        continue;
-                    };
-
-                    if data.portnum != PortNum::TracerouteApp as i32 || data.request_id != packet_id
-                    {
-                        continue;
-                    }
-
-                    let rtt = start.elapsed();
-
-                    let Ok(route) = RouteDiscovery::decode(data.payload.as_slice()) else {
-                        println!(
-                            "{} Received traceroute response but failed to decode",
-                            "?".yellow()
-                        );
-                        return Ok(());
-                    };
-
-                    if self.json {
-                        print_route_json(
-                            &route,
-                            my_node_num,
-                            target_node_id,
-                            &dest_label,
-                            rtt,
-                            ctx,
-                        )?;
-                    } else {
-                        print_route(&route, my_node_num, target_node_id, &dest_label, rtt, ctx);
-                    }
-
-                    return Ok(());
+            }
+        }
+
+        if self.json {
+            let report = route_stats::json_report(&stats, &ctx.node_db, dest_label);
+            ctx.print_json(&report)?;
+        } else {
+            println!(
+                "{} {} traces to {}: {} answered, {:.0}% loss",
+                "ok".green(),
+                stats.sent,
+                dest_label.bold(),
+                stats.received,
+                stats.loss_pct()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Sends an empty RouteDiscovery to `target` and returns the request's packet ID.
+async fn send_request(ctx: &mut CommandContext, target: u32) -> anyhow::Result<u32> {
+    let packet_id: u32 = generate_rand_id();
+
+    let route_discovery = RouteDiscovery {
+        route: vec![],
+        snr_towards: vec![],
+        route_back: vec![],
+        snr_back: vec![],
+    };
+    let mut payload_bytes = Vec::new();
+    route_discovery.encode(&mut payload_bytes)?;
+
+    let mesh_packet = MeshPacket {
+        from: ctx.node_db.my_node_num(),
+        to: target,
+        id: packet_id,
+        want_ack: true,
+        channel: 0,
+        hop_limit: 7,
+        payload_variant: Some(MeshPayload::Decoded(Data {
+            portnum: PortNum::TracerouteApp as i32,
+            payload: payload_bytes,
+            want_response: true,
+            dest: target,
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+
+    let payload_variant = Some(protobufs::to_radio::PayloadVariant::Packet(mesh_packet));
+    ctx.api.send_to_radio_packet(payload_variant).await?;
+    Ok(packet_id)
+}
+
+/// Traces the route to `target` once. `None` when no answer came within
+/// `timeout` or it could not be decoded.
+pub(super) async fn trace(
+    ctx: &mut CommandContext,
+    target: u32,
+    timeout: Duration,
+) -> anyhow::Result<Option<Route>> {
+    let start = Instant::now();
+    let packet_id = send_request(ctx, target).await?;
+    let Some(data) = wait_for_response(ctx, packet_id, start, timeout).await? else {
+        return Ok(None);
+    };
+    let rtt = start.elapsed();
+    Ok(RouteDiscovery::decode(data.payload.as_slice())
+        .ok()
+        .map(|route| Route::from_discovery(&route, rtt)))
+}
+
+/// Waits until `timeout` after `start` for the traceroute answering
+/// `packet_id`. `None` on timeout.
+async fn wait_for_response(
+    ctx: &mut CommandContext,
+    packet_id: u32,
+    start: Instant,
+    timeout: Duration,
+) -> anyhow::Result<Option<Data>> {
+    loop {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+
+        match tokio::time::timeout(remaining, ctx.packet_receiver.recv()).await {
+            Err(_) => return Ok(None),
+            Ok(None) => {
+                bail!("Disconnected while waiting for traceroute response");
+            }
+            Ok(Some(from_radio)) => {
+                let Some(PayloadVariant::Packet(mesh_pkt)) = from_radio.payload_variant else {
+                    continue;
+                };
+
+                let Some(MeshPayload::Decoded(data)) = mesh_pkt.payload_variant else {
+                    continue;
+                };
+
+                if data.portnum != PortNum::TracerouteApp as i32 || data.request_id != packet_id {
+                    continue;
                 }
+
+                return Ok(Some(data));
             }
         }
     }
@@ -258,7 +360,7 @@ fn print_route_json(
     target_node_id: u32,
     dest_label: &str,
     rtt: Duration,
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
 ) -> anyhow::Result<()> {
     let make_hop = |num: u32, snr_list: &[i32], idx: usize| -> TracerouteHopJson {
         TracerouteHopJson {
@@ -306,6 +408,5 @@ fn print_route_json(
         hops,
         return_hops,
     };
-    println!("{}", serde_json::to_string_pretty(&result)?);
-    Ok(())
+    ctx.print_json(&result)
 }
@@ -10,10 +10,15 @@ use meshtastic::protobufs::mesh_packet::PayloadVariant as MeshPayload;
 use meshtastic::protobufs::NodeInfo;
 use serde::Serialize;
 
+use super::geo::{Origin, Units};
+use super::history::SightingRecorder;
+use super::parsers::truncate;
 use super::{Command, CommandContext};
 
 pub struct WatchCommand {
     pub interval_secs: u64,
+    /// Reference point for distances instead of the local node's position
+    pub from: Option<(f64, f64)>,
     pub json: bool,
 }
 
@@ -24,6 +29,10 @@ struct WatchNodeJson {
     battery: Option<u32>,
     snr: f32,
     last_heard: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_m: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bearing_deg: Option<f64>,
 }
 
 #[async_trait]
@@ -32,21 +41,22 @@ impl Command for WatchCommand {
         let my_node_num = ctx.node_db.my_node_num();
         let json = self.json;
         let mut interval = tokio::time::interval(Duration::from_secs(self.interval_secs));
+        let mut sightings = SightingRecorder::new();
 
         // Initial render
         if json {
-            print_json_snapshot(ctx)?;
+            print_json_snapshot(ctx, self.origin(ctx))?;
         } else {
-            render_table_from_ctx(ctx, my_node_num)?;
+            render_table_from_ctx(ctx, my_node_num, self.origin(ctx))?;
         }
 
         loop {
             tokio::select! {
                 _ = interval.tick() => {
                     if json {
-                        print_json_snapshot(ctx)?;
+                        print_json_snapshot(ctx, self.origin(ctx))?;
                     } else {
-                        render_table_from_ctx(ctx, my_node_num)?;
+                        render_table_from_ctx(ctx, my_node_num, self.origin(ctx))?;
                     }
                 }
                 packet = ctx.packet_receiver.recv() => {
@@ -56,6 +66,7 @@ impl Command for WatchCommand {
                     let Some(PayloadVariant::Packet(mesh_pkt)) = from_radio.payload_variant else {
                         continue;
                     };
+                    sightings.record(&mesh_pkt, &ctx.node_db);
                     let Some(MeshPayload::Decoded(ref data)) = mesh_pkt.payload_variant else {
                         continue;
                     };
@@ -72,6 +83,15 @@ impl Command for WatchCommand {
     }
 }
 
+impl WatchCommand {
+    fn origin(&self, ctx: &CommandContext) -> Option<Origin> {
+        Origin::resolve(
+            self.from,
+            ctx.node_db.nodes().get(&ctx.node_db.my_node_num()),
+        )
+    }
+}
+
 fn update_node_from_packet(
     ctx: &mut CommandContext,
     from: u32,
@@ -87,23 +107,28 @@ fn update_node_from_packet(
     // node_db state periodically.
 }
 
-fn print_json_snapshot(ctx: &CommandContext) -> anyhow::Result<()> {
+fn print_json_snapshot(ctx: &CommandContext, origin: Option<Origin>) -> anyhow::Result<()> {
     let nodes = ctx.node_db.nodes();
     let mut sorted: Vec<_> = nodes.values().collect();
     sorted.sort_by_key(|n| n.num);
 
     let json_nodes: Vec<WatchNodeJson> = sorted
         .iter()
-        .map(|n| WatchNodeJson {
-            id: format!("!{:08x}", n.num),
-            name: n
-                .user
-                .as_ref()
-                .map(|u| u.long_name.clone())
-                .unwrap_or_else(|| "Unknown".into()),
-            battery: n.device_metrics.as_ref().and_then(|m| m.battery_level),
-            snr: n.snr,
-            last_heard: n.last_heard,
+        .map(|n| {
+            let relative = origin.and_then(|o| o.relative_to_node(n));
+            WatchNodeJson {
+                id: format!("!{:08x}", n.num),
+                name: n
+                    .user
+                    .as_ref()
+                    .map(|u| u.long_name.clone())
+                    .unwrap_or_else(|| "Unknown".into()),
+                battery: n.device_metrics.as_ref().and_then(|m| m.battery_level),
+                snr: n.snr,
+                last_heard: n.last_heard,
+                distance_m: relative.map(|r| (r.distance_m * 10.0).round() / 10.0),
+                bearing_deg: relative.map(|r| (r.bearing_deg * 10.0).round() / 10.0),
+            }
         })
         .collect();
 
@@ -111,7 +136,11 @@ fn print_json_snapshot(ctx: &CommandContext) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn render_table_from_ctx(ctx: &CommandContext, my_node_num: u32) -> anyhow::Result<()> {
+fn render_table_from_ctx(
+    ctx: &CommandContext,
+    my_node_num: u32,
+    origin: Option<Origin>,
+) -> anyhow::Result<()> {
     let nodes = ctx.node_db.nodes();
     let mut stdout = std::io::stdout();
 
@@ -124,16 +153,23 @@ fn render_table_from_ctx(ctx: &CommandContext, my_node_num: u32) -> anyhow::Resu
     let mut sorted: Vec<_> = nodes.values().collect();
     sorted.sort_by_key(|n| n.num);
 
-    let header = format!(
+    let mut header = format!(
         "{:<12} {:<20} {:<8} {:<8} {:<6} {:<20}",
         "ID", "Name", "Battery", "SNR", "Hops", "Last Heard"
     );
+    // Distance columns only when there is a position to measure from
+    if origin.is_some() {
+        header.push_str(&format!(" {:<10} {:<8}", "Distance", "Bearing"));
+    }
     writeln!(stdout, "{}", header.bold())?;
-    writeln!(stdout, "{}", "-".repeat(76))?;
+    writeln!(stdout, "{}", "-".repeat(header.chars().count()))?;
 
     for node in &sorted {
         let is_local = node.num == my_node_num;
-        let line = format_node_row(node);
+        let mut line = format_node_row(node);
+        if let Some(origin) = origin {
+            line.push_str(&format_distance_cells(node, &origin, ctx.units));
+        }
 
         if is_local {
             writeln!(stdout, "{}", line.green())?;
@@ -194,10 +230,13 @@ fn format_node_row(node: &NodeInfo) -> String {
     )
 }
 
-fn truncate(s: &str, max: usize) -> String {
-    if s.len() > max {
-        format!("{}…", &s[..max - 1])
-    } else {
-        s.to_string()
+fn format_distance_cells(node: &NodeInfo, origin: &Origin, units: Units) -> String {
+    match origin.relative_to_node(node) {
+        Some(r) => format!(
+            " {:<10} {:<8}",
+            units.format_distance(r.distance_m),
+            r.format_bearing()
+        ),
+        None => format!(" {:<10} {:<8}", "N/A", "N/A"),
     }
 }
@@ -1,9 +1,11 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use anyhow::Context;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-use crate::cli::ConnectionArgs;
+use crate::cli::{ConnectionArgs, UnitsArg};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -17,6 +19,15 @@ pub struct AppConfig {
     pub ble: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub json: Option<bool>,
+    /// `metric` or `imperial`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    /// Address book: nickname -> node ID (`!04e1c43b`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// Named groups of aliases, node names or node IDs, used as `--to @name`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
 }
 
 pub fn config_dir() -> PathBuf {
@@ -99,4 +110,11 @@ pub fn merge_with_cli(config: &AppConfig, cli: &mut ConnectionArgs) {
             cli.json = true;
         }
     }
+
+    if cli.units.is_none() {
+        cli.units = config
+            .units
+            .as_deref()
+            .and_then(|u| UnitsArg::from_str(u, true).ok());
+    }
 }
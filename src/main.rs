@@ -9,8 +9,12 @@ mod router;
 
 use clap::Parser;
 
-use cli::{Cli, Commands};
-use commands::{create_command, handle_config_file, CommandContext};
+use cli::{Cli, Commands, LoraAction, NodesAction, SnapshotAction};
+use commands::{
+    create_command, handle_config_file, handle_from_snapshot, handle_log, handle_lora_offline,
+    handle_messages, handle_node_history, handle_snapshot_diff, handle_topology_log,
+    record_handshake, CommandContext, HistoryOptions,
+};
 use router::MeshRouter;
 
 #[tokio::main]
@@ -33,6 +37,13 @@ async fn main() -> anyhow::Result<()> {
         std::process::exit(1);
     };
 
+    let units = cli
+        .connection
+        .units
+        .as_ref()
+        .map(Into::into)
+        .unwrap_or_default();
+
     // Handle commands that don't need a device connection
     match cmd {
         Commands::Completions { shell } => {
@@ -43,20 +54,80 @@ async fn main() -> anyhow::Result<()> {
             handle_config_file(action, cli.connection.json)?;
             return Ok(());
         }
+        Commands::Lora {
+            action: action @ LoraAction::Calc { offline: true, .. },
+        } => {
+            handle_lora_offline(action, cli.connection.json)?;
+            return Ok(());
+        }
+        Commands::Log { action } => {
+            handle_log(action, cli.connection.json)?;
+            return Ok(());
+        }
+        Commands::Messages { action, reassemble } => {
+            handle_messages(action, *reassemble, cli.connection.json)?;
+            return Ok(());
+        }
+        Commands::Nodes {
+            action:
+                Some(NodesAction::History {
+                    node,
+                    since,
+                    gap,
+                    last,
+                }),
+            ..
+        } => {
+            let options = HistoryOptions {
+                node: node.as_deref(),
+                since: since.as_deref(),
+                gap,
+                last: *last,
+            };
+            handle_node_history(&options, units, cli.connection.json)?;
+            return Ok(());
+        }
+        Commands::Nodes {
+            action:
+                Some(NodesAction::Snapshot {
+                    action: SnapshotAction::Diff { old, new },
+                }),
+            ..
+        } => {
+            handle_snapshot_diff(old, new, cli.connection.json)?;
+            return Ok(());
+        }
+        Commands::Topology {
+            from_log: Some(file),
+            format,
+            ..
+        } => {
+            handle_topology_log(file, &format.into(), cli.connection.json)?;
+            return Ok(());
+        }
         _ => {}
     }
 
+    if handle_from_snapshot(cmd, cli.connection.json, units)? {
+        return Ok(());
+    }
+
+    // Validate arguments (and read any message input) before connecting
+    let command = create_command(cmd, cli.connection.json)?;
+
     let conn = connection::establish(&cli.connection).await?;
     let router = MeshRouter::new(conn.node_db.my_node_num());
+    record_handshake(&conn.node_db);
 
     let mut ctx = CommandContext {
         api: conn.api,
         node_db: conn.node_db,
         packet_receiver: conn.packet_receiver,
         router,
+        units,
+        json_results: None,
     };
 
-    let command = create_command(cmd, cli.connection.json)?;
     command.execute(&mut ctx).await?;
 
     Ok(())
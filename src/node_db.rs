@@ -30,6 +30,33 @@ impl NodeDb {
         }
     }
 
+    /// A node DB holding only node names, for commands that work from a
+    /// log or local store instead of a device. Later names for a node
+    /// replace earlier ones.
+    pub fn from_names(names: impl Iterator<Item = (u32, String)>) -> Self {
+        let nodes = names
+            .map(|(num, long_name)| {
+                let node = protobufs::NodeInfo {
+                    num,
+                    user: Some(protobufs::User {
+                        long_name,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+                (num, node)
+            })
+            .collect();
+        Self::new(
+            protobufs::MyNodeInfo::default(),
+            nodes,
+            Vec::new(),
+            None,
+            protobufs::LocalConfig::default(),
+            protobufs::LocalModuleConfig::default(),
+        )
+    }
+
     pub fn my_node_num(&self) -> u32 {
         self.my_node_info.my_node_num
     }
@@ -69,16 +96,83 @@ impl NodeDb {
         &self.local_module_config
     }
 
+    /// The nodes sharing the best match quality for `name`; more than one
+    /// means the name is ambiguous.
     pub fn find_by_name(&self, name: &str) -> Vec<(u32, &protobufs::NodeInfo)> {
+        let matches = self.search_by_name(name);
+        let best = matches.first().map(|(_, _, kind)| *kind);
+        matches
+            .into_iter()
+            .filter(|(_, _, kind)| Some(*kind) == best)
+            .map(|(num, node, _)| (num, node))
+            .collect()
+    }
+
+    /// Every node matching `name` (case-insensitive) by long name, short
+    /// name, the last 4 hex digits of its ID, or a long name prefix or
+    /// substring. Best matches first, then by long name.
+    pub fn search_by_name(&self, name: &str) -> Vec<(u32, &protobufs::NodeInfo, NameMatch)> {
         let query = name.to_lowercase();
-        self.nodes
+        let id_suffix = query.strip_prefix('!').unwrap_or(&query).to_string();
+        let is_id_suffix = id_suffix.len() == 4 && id_suffix.chars().all(|c| c.is_ascii_hexdigit());
+
+        let mut matches: Vec<_> = self
+            .nodes
             .iter()
-            .filter(|(_, node)| {
-                node.user
-                    .as_ref()
-                    .is_some_and(|u| u.long_name.to_lowercase() == query)
+            .filter_map(|(&num, node)| {
+                let user = node.user.as_ref();
+                let long_name = user.map(|u| u.long_name.to_lowercase()).unwrap_or_default();
+                let short_name = user
+                    .map(|u| u.short_name.to_lowercase())
+                    .unwrap_or_default();
+
+                let kind = if !long_name.is_empty() && long_name == query {
+                    NameMatch::LongName
+                } else if !short_name.is_empty() && short_name == query {
+                    NameMatch::ShortName
+                } else if is_id_suffix && format!("{:08x}", num).ends_with(&id_suffix) {
+                    NameMatch::IdSuffix
+                } else if !query.is_empty() && long_name.starts_with(&query) {
+                    NameMatch::Prefix
+                } else if !query.is_empty() && long_name.contains(&query) {
+                    NameMatch::Substring
+                } else {
+                    return None;
+                };
+                Some((num, node, kind))
             })
-            .map(|(&num, node)| (num, node))
-            .collect()
+            .collect();
+
+        matches.sort_by_cached_key(|(num, node, kind)| {
+            let long_name = node
+                .user
+                .as_ref()
+                .map(|u| u.long_name.to_lowercase())
+                .unwrap_or_default();
+            (*kind, long_name, *num)
+        });
+        matches
+    }
+}
+
+/// How a node name query matched, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NameMatch {
+    LongName,
+    ShortName,
+    IdSuffix,
+    Prefix,
+    Substring,
+}
+
+impl NameMatch {
+    pub fn describe(self) -> &'static str {
+        match self {
+            NameMatch::LongName => "long name",
+            NameMatch::ShortName => "short name",
+            NameMatch::IdSuffix => "ID suffix",
+            NameMatch::Prefix => "name prefix",
+            NameMatch::Substring => "part of name",
+        }
     }
 }